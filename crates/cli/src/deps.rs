@@ -0,0 +1,186 @@
+//! Printing the import graph of an app, package or platform, for `roc deps`.
+
+use std::fmt::Write;
+use std::io;
+use std::path::PathBuf;
+
+use bumpalo::Bump;
+use roc_build::program::handle_loading_problem;
+use roc_load::{ExecutionMode, FunctionKind, LoadConfig, LoadedModule, Threading};
+use roc_module::symbol::ModuleId;
+use roc_mono::ir::OptLevel;
+use roc_packaging::cache::RocCacheDir;
+use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_target::Target;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DepsFormat {
+    Dot,
+    Json,
+}
+
+/// Load the given module and print every module it depends on, along with what each one imports.
+/// Builtin modules are left out, since every module can use them.
+pub fn print_deps(
+    roc_file_path: PathBuf,
+    format: DepsFormat,
+    roc_cache_dir: RocCacheDir<'_>,
+    threading: Threading,
+) -> io::Result<i32> {
+    let arena = Bump::new();
+
+    let load_config = LoadConfig {
+        // only used for generating errors
+        target: Target::LinuxX64,
+        function_kind: FunctionKind::LambdaSet,
+        render: RenderTarget::ColorTerminal,
+        palette: DEFAULT_PALETTE,
+        threading,
+        exec_mode: ExecutionMode::Check,
+        opt_level: OptLevel::Development,
+    };
+
+    let loaded =
+        match roc_load::load_and_typecheck(&arena, roc_file_path, roc_cache_dir, load_config) {
+            Ok(loaded) => loaded,
+            Err(problem) => return handle_loading_problem(problem),
+        };
+
+    print!("{}", render_deps(&import_graph(&loaded), format));
+
+    Ok(0)
+}
+
+/// Every non-builtin module with the non-builtin modules it imports, sorted by name
+fn import_graph(loaded: &LoadedModule) -> Vec<(String, Vec<String>)> {
+    let module_name = |module_id: ModuleId| -> String {
+        let name = loaded.interns.module_name(module_id).as_str();
+
+        if name.is_empty() {
+            // the App module
+            loaded.filename.file_name().map_or_else(
+                || "app".to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            )
+        } else {
+            name.to_string()
+        }
+    };
+
+    let mut graph: Vec<(String, Vec<String>)> = loaded
+        .imports
+        .iter()
+        .filter(|(module_id, _)| !module_id.is_builtin())
+        .map(|(module_id, imports)| {
+            let mut imports: Vec<String> = imports
+                .iter()
+                .filter(|import| !import.is_builtin())
+                .map(|import| module_name(*import))
+                .collect();
+
+            imports.sort();
+
+            (module_name(*module_id), imports)
+        })
+        .collect();
+
+    graph.sort();
+
+    graph
+}
+
+fn render_deps(graph: &[(String, Vec<String>)], format: DepsFormat) -> String {
+    let mut buf = String::new();
+
+    match format {
+        DepsFormat::Dot => {
+            buf.push_str("digraph imports {\n");
+
+            for (module, _) in graph {
+                writeln!(buf, "    {};", quote(module)).unwrap();
+            }
+
+            for (module, imports) in graph {
+                for import in imports {
+                    writeln!(buf, "    {} -> {};", quote(module), quote(import)).unwrap();
+                }
+            }
+
+            buf.push_str("}\n");
+        }
+        DepsFormat::Json => {
+            buf.push_str("{\n");
+
+            for (index, (module, imports)) in graph.iter().enumerate() {
+                let imports: Vec<String> = imports.iter().map(|import| quote(import)).collect();
+
+                write!(buf, "  {}: [{}]", quote(module), imports.join(", ")).unwrap();
+
+                if index + 1 < graph.len() {
+                    buf.push(',');
+                }
+
+                buf.push('\n');
+            }
+
+            buf.push_str("}\n");
+        }
+    }
+
+    buf
+}
+
+/// A double-quoted string, which both DOT and JSON accept
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> Vec<(String, Vec<String>)> {
+        vec![
+            ("Parser".to_string(), vec![]),
+            (
+                "main.roc".to_string(),
+                vec!["Parser".to_string(), "pf.Stdout".to_string()],
+            ),
+            ("pf.Stdout".to_string(), vec![]),
+        ]
+    }
+
+    #[test]
+    fn test_render_deps_dot() {
+        assert_eq!(
+            render_deps(&graph(), DepsFormat::Dot),
+            indoc::indoc!(
+                r#"
+                digraph imports {
+                    "Parser";
+                    "main.roc";
+                    "pf.Stdout";
+                    "main.roc" -> "Parser";
+                    "main.roc" -> "pf.Stdout";
+                }
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_deps_json() {
+        assert_eq!(
+            render_deps(&graph(), DepsFormat::Json),
+            indoc::indoc!(
+                r#"
+                {
+                  "Parser": [],
+                  "main.roc": ["Parser", "pf.Stdout"],
+                  "pf.Stdout": []
+                }
+                "#
+            )
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::ffi::OsStr;
 use std::io::Write;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
 use bumpalo::Bump;
@@ -18,18 +19,30 @@ pub enum FormatMode {
     CheckOnly,
 }
 
+/// A file listing paths that `roc format` skips when it formats the directory the file is in.
+/// Each line is a file or directory, relative to that directory. Blank lines and lines starting
+/// with `#` are ignored.
+pub const FORMAT_IGNORE_FILE: &str = ".rocformatignore";
+
 fn flatten_directories(files: std::vec::Vec<PathBuf>) -> std::vec::Vec<PathBuf> {
     let mut to_flatten = files;
     let mut files = vec![];
+    let mut ignored = vec![];
 
     while let Some(path) = to_flatten.pop() {
         if path.is_dir() {
+            ignored.extend(read_ignore_file(&path));
+
             match path.read_dir() {
                 Ok(directory) => {
                     for item in directory {
                         match item {
                             Ok(file) => {
                                 let file_path = file.path();
+                                if ignored.contains(&file_path) {
+                                    continue;
+                                }
+
                                 if file_path.is_dir() {
                                     to_flatten.push(file_path);
                                 } else if is_roc_file(&file_path) {
@@ -58,30 +71,51 @@ fn flatten_directories(files: std::vec::Vec<PathBuf>) -> std::vec::Vec<PathBuf>
     files
 }
 
+/// The paths listed in the directory's ignore file, if it has one
+fn read_ignore_file(dir: &Path) -> Vec<PathBuf> {
+    let Ok(contents) = std::fs::read_to_string(dir.join(FORMAT_IGNORE_FILE)) else {
+        return Vec::new();
+    };
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| dir.join(line.trim_end_matches('/')))
+        .collect()
+}
+
 fn is_roc_file(path: &Path) -> bool {
     matches!(path.extension().and_then(OsStr::to_str), Some("roc"))
 }
 
 pub fn format_files(files: std::vec::Vec<PathBuf>, mode: FormatMode) -> Result<(), String> {
-    let arena = Bump::new();
+    let files = flatten_directories(files);
     let mut files_to_reformat = Vec::new(); // to track which files failed `roc format --check`
+    let mut reformatted_files = Vec::new();
 
-    for file in flatten_directories(files) {
-        let src = std::fs::read_to_string(&file).unwrap();
-
-        match format_src(&arena, &src) {
+    for (file, (src, result)) in files.iter().zip(format_in_parallel(&files)) {
+        match result {
             Ok(buf) => {
                 match mode {
                     FormatMode::CheckOnly => {
                         // If a file fails `format --check`, add it to the file
                         // list for reporting afterwards.
                         if buf.as_str() != src {
-                            files_to_reformat.push(file.display().to_string());
+                            let path = file.display().to_string();
+
+                            print!("{}", unified_diff(&path, &src, buf.as_str()));
+
+                            files_to_reformat.push(path);
                         }
                     }
                     FormatMode::WriteToFile => {
                         // If all the checks above passed, actually write out the new file.
-                        std::fs::write(&file, buf.as_str()).unwrap();
+                        if buf.as_str() != src {
+                            std::fs::write(file, buf.as_str()).unwrap();
+
+                            reformatted_files.push(file.display().to_string());
+                        }
                     }
                     FormatMode::WriteToStdout => {
                         std::io::stdout().lock().write_all(buf.as_bytes()).unwrap()
@@ -162,9 +196,215 @@ pub fn format_files(files: std::vec::Vec<PathBuf>, mode: FormatMode) -> Result<(
             file_list
         ));
     }
+
+    if let FormatMode::WriteToFile = mode {
+        if reformatted_files.is_empty() {
+            println!("All {} file(s) were already formatted.", files.len());
+        } else {
+            println!(
+                "Reformatted {} of {} file(s):\n\t{}",
+                reformatted_files.len(),
+                files.len(),
+                reformatted_files.join("\n\t")
+            );
+        }
+    }
+
     Ok(())
 }
 
+/// Read and format every file, spreading the files over the available cores.
+/// The results are in the same order as the files.
+fn format_in_parallel(files: &[PathBuf]) -> Vec<(String, Result<String, FormatProblem>)> {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk_size = files.len().div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut arena = Bump::new();
+
+                    chunk
+                        .iter()
+                        .map(|file| {
+                            let src = std::fs::read_to_string(file).unwrap();
+                            let result = format_src(&arena, &src);
+
+                            arena.reset();
+
+                            (src, result)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// How many unchanged lines to show around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// A unified diff of the lines of `before` and `after`, as `roc format --check` prints it for
+/// every file that would change. Returns an empty string if there is no difference.
+pub fn unified_diff(path: &str, before: &str, after: &str) -> String {
+    use std::fmt::Write;
+
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+    let lines = diff_lines(&before, &after);
+
+    let changes: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Same(_)))
+        .map(|(index, _)| index)
+        .collect();
+
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // The number of lines of `before` and `after` that come before each line of the diff
+    let mut before_offsets = Vec::with_capacity(lines.len() + 1);
+    let mut after_offsets = Vec::with_capacity(lines.len() + 1);
+    let (mut before_offset, mut after_offset) = (0, 0);
+
+    for line in lines.iter() {
+        before_offsets.push(before_offset);
+        after_offsets.push(after_offset);
+
+        match line {
+            DiffLine::Same(_) => {
+                before_offset += 1;
+                after_offset += 1;
+            }
+            DiffLine::Removed(_) => before_offset += 1,
+            DiffLine::Added(_) => after_offset += 1,
+        }
+    }
+
+    before_offsets.push(before_offset);
+    after_offsets.push(after_offset);
+
+    let mut diff = format!("--- {path}\n+++ {path}\n");
+    let mut changes = changes.into_iter().peekable();
+
+    while let Some(first_change) = changes.next() {
+        let start = first_change.saturating_sub(DIFF_CONTEXT);
+        let mut last_change = first_change;
+
+        // Changes whose context would overlap go into the same hunk
+        while let Some(&change) = changes.peek() {
+            if change > last_change + 2 * DIFF_CONTEXT + 1 {
+                break;
+            }
+
+            last_change = change;
+            changes.next();
+        }
+
+        let end = (last_change + 1 + DIFF_CONTEXT).min(lines.len());
+
+        let before_len = before_offsets[end] - before_offsets[start];
+        let after_len = after_offsets[end] - after_offsets[start];
+
+        // An empty range starts at the line before it, like in `diff -u`
+        let before_start = before_offsets[start] + usize::from(before_len > 0);
+        let after_start = after_offsets[start] + usize::from(after_len > 0);
+
+        writeln!(
+            diff,
+            "@@ -{before_start},{before_len} +{after_start},{after_len} @@"
+        )
+        .unwrap();
+
+        for line in &lines[start..end] {
+            match line {
+                DiffLine::Same(text) => writeln!(diff, " {text}"),
+                DiffLine::Removed(text) => writeln!(diff, "-{text}"),
+                DiffLine::Added(text) => writeln!(diff, "+{text}"),
+            }
+            .unwrap();
+        }
+    }
+
+    diff
+}
+
+/// The lines of `before` and `after`, in order, with the lines that are in both only once
+fn diff_lines<'a>(before: &[&'a str], after: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // Formatting usually changes a few places in a file, so skipping the common start and end
+    // keeps the table below small.
+    let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old = &before[prefix..before.len() - suffix];
+    let new = &after[prefix..after.len() - suffix];
+
+    // `common[i * width + j]` is the length of the longest common subsequence of `old[i..]`
+    // and `new[j..]`
+    let width = new.len() + 1;
+    let mut common = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i * width + j] = if old[i] == new[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<DiffLine> = before[..prefix]
+        .iter()
+        .map(|&line| DiffLine::Same(line))
+        .collect();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len() || common[(i + 1) * width + j] >= common[i * width + j + 1])
+        {
+            lines.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+
+    lines.extend(
+        before[before.len() - suffix..]
+            .iter()
+            .map(|&line| DiffLine::Same(line)),
+    );
+
+    lines
+}
+
 #[derive(Debug)]
 pub enum FormatProblem {
     ParsingFailed {
@@ -230,6 +470,60 @@ pub fn format_src(arena: &Bump, src: &str) -> Result<String, FormatProblem> {
     Ok(buf.as_str().to_string())
 }
 
+/// Format only the top-level defs of `src` that overlap the byte range `range`, leaving the
+/// rest of the file byte-for-byte as it was. An empty range formats the def it is in.
+pub fn format_src_range(
+    arena: &Bump,
+    src: &str,
+    range: Range<usize>,
+) -> Result<String, FormatProblem> {
+    let formatted_src = format_src(arena, src)?;
+
+    // Both parses succeed, because format_src already checked them
+    let ast = parse_all(arena, src).unwrap();
+    let formatted_ast = parse_all(arena, &formatted_src).unwrap();
+
+    let mut buf = String::with_capacity(src.len());
+    let mut copied_until = 0;
+
+    // Formatting does not change the defs, so the defs of both trees line up
+    for (region, formatted_region) in ast.defs.regions.iter().zip(&formatted_ast.defs.regions) {
+        let start = region.start().offset as usize;
+        let end = region.end().offset as usize;
+
+        if end < range.start || start > range.end {
+            continue;
+        }
+
+        buf.push_str(&src[copied_until..start]);
+        buf.push_str(
+            &formatted_src
+                [formatted_region.start().offset as usize..formatted_region.end().offset as usize],
+        );
+
+        copied_until = end;
+    }
+
+    buf.push_str(&src[copied_until..]);
+
+    Ok(buf)
+}
+
+/// Parse the `START..END` argument of `roc format --range`, which are byte offsets into the file
+pub fn parse_byte_range(arg: &str) -> Result<Range<usize>, String> {
+    let invalid = || format!("`{arg}` is not a byte range like `120..300`");
+
+    let (start, end) = arg.split_once("..").ok_or_else(invalid)?;
+    let start: usize = start.parse().map_err(|_| invalid())?;
+    let end: usize = end.parse().map_err(|_| invalid())?;
+
+    if start > end {
+        return Err(invalid());
+    }
+
+    Ok(start..end)
+}
+
 fn parse_all<'a>(arena: &'a Bump, src: &'a str) -> Result<Ast<'a>, SyntaxError<'a>> {
     let (module, state) = module::parse_header(arena, State::new(src.as_bytes()))
         .map_err(|e| SyntaxError::Header(e.problem))?;
@@ -331,6 +625,81 @@ main =
         cleanup_temp_dir(dir);
     }
 
+    #[test]
+    fn test_ignore_file() {
+        let dir = tempdir().unwrap();
+        let kept = setup_test_file(dir.path(), "kept.roc", FORMATTED_ROC);
+        setup_test_file(dir.path(), "ignored.roc", UNFORMATTED_ROC);
+        std::fs::create_dir(dir.path().join("generated")).unwrap();
+        setup_test_file(&dir.path().join("generated"), "file.roc", UNFORMATTED_ROC);
+        setup_test_file(
+            dir.path(),
+            FORMAT_IGNORE_FILE,
+            "# not formatted\nignored.roc\n\ngenerated/",
+        );
+
+        assert_eq!(
+            flatten_directories(vec![dir.path().to_path_buf()]),
+            vec![kept]
+        );
+
+        let result = format_files(vec![dir.path().to_path_buf()], FormatMode::CheckOnly);
+        assert!(result.is_ok());
+
+        cleanup_temp_dir(dir);
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let after = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nm\nn\n";
+
+        assert_eq!(
+            unified_diff("Main.roc", before, after),
+            indoc::indoc!(
+                "
+                --- Main.roc
+                +++ Main.roc
+                @@ -1,5 +1,5 @@
+                 a
+                -b
+                +B
+                 c
+                 d
+                 e
+                @@ -9,5 +9,5 @@
+                 i
+                 j
+                 k
+                -l
+                 m
+                +n
+                "
+            )
+        );
+
+        assert_eq!(unified_diff("Main.roc", before, before), "");
+    }
+
+    #[test]
+    fn test_format_src_range() {
+        let arena = Bump::new();
+        let src = "app [main] { pf: platform \"main.roc\" }\n\nfoo  =   1\n\nbar  =   2\n\nmain  =   foo\n";
+        let bar = src.find("bar").unwrap();
+
+        assert_eq!(
+            format_src_range(&arena, src, bar..bar + 3).unwrap(),
+            "app [main] { pf: platform \"main.roc\" }\n\nfoo  =   1\n\nbar = 2\n\nmain  =   foo\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(parse_byte_range("12..40"), Ok(12..40));
+        assert!(parse_byte_range("40..12").is_err());
+        assert!(parse_byte_range("12").is_err());
+    }
+
     #[test]
     fn test_some_files_need_reformatting() {
         let dir = tempdir().unwrap();
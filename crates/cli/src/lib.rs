@@ -36,8 +36,12 @@ use strum::IntoEnumIterator;
 #[cfg(not(target_os = "linux"))]
 use tempfile::TempDir;
 
+mod deps;
 mod format;
-pub use format::{format_files, format_src, FormatMode};
+pub use deps::{print_deps, DepsFormat};
+pub use format::{
+    format_files, format_src, format_src_range, parse_byte_range, unified_diff, FormatMode,
+};
 
 pub const CMD_BUILD: &str = "build";
 pub const CMD_RUN: &str = "run";
@@ -45,6 +49,7 @@ pub const CMD_DEV: &str = "dev";
 pub const CMD_REPL: &str = "repl";
 pub const CMD_DOCS: &str = "docs";
 pub const CMD_CHECK: &str = "check";
+pub const CMD_DEPS: &str = "deps";
 pub const CMD_VERSION: &str = "version";
 pub const CMD_FORMAT: &str = "format";
 pub const CMD_TEST: &str = "test";
@@ -53,6 +58,8 @@ pub const CMD_GEN_STUB_LIB: &str = "gen-stub-lib";
 pub const CMD_PREPROCESS_HOST: &str = "preprocess-host";
 
 pub const FLAG_EMIT_LLVM_IR: &str = "emit-llvm-ir";
+pub const FLAG_EMIT_SIZE_REPORT: &str = "emit-size-report";
+pub const FLAG_EMIT_GC_STACK_MAPS: &str = "emit-gc-stack-maps";
 pub const FLAG_PROFILING: &str = "profiling";
 pub const FLAG_BUNDLE: &str = "bundle";
 pub const FLAG_DEV: &str = "dev";
@@ -67,8 +74,11 @@ pub const FLAG_VERBOSE: &str = "verbose";
 pub const FLAG_LINKER: &str = "linker";
 pub const FLAG_PREBUILT: &str = "prebuilt-platform";
 pub const FLAG_CHECK: &str = "check";
+pub const FLAG_ANNOTATE: &str = "annotate";
 pub const FLAG_STDIN: &str = "stdin";
 pub const FLAG_STDOUT: &str = "stdout";
+pub const FLAG_RANGE: &str = "range";
+pub const FLAG_FORMAT: &str = "format";
 pub const FLAG_WASM_STACK_SIZE_KB: &str = "wasm-stack-size-kb";
 pub const FLAG_OUTPUT: &str = "output";
 pub const FLAG_FUZZ: &str = "fuzz";
@@ -113,6 +123,18 @@ pub fn build_app() -> Command {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_emit_gc_stack_maps = Arg::new(FLAG_EMIT_GC_STACK_MAPS)
+        .long(FLAG_EMIT_GC_STACK_MAPS)
+        .help("Record where every refcounted value lives at each call between Roc functions, in the `.llvm_stackmaps` section of the object file")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
+    let flag_emit_size_report = Arg::new(FLAG_EMIT_SIZE_REPORT)
+        .long(FLAG_EMIT_SIZE_REPORT)
+        .help("Print how much code each function generated, with its number of specializations and mono IR statements")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
     let flag_profiling = Arg::new(FLAG_PROFILING)
         .long(FLAG_PROFILING)
         .help("Keep debug info in the final generated program even in optimized builds")
@@ -181,6 +203,8 @@ pub fn build_app() -> Command {
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_emit_llvm_ir.clone())
+            .arg(flag_emit_gc_stack_maps.clone())
+            .arg(flag_emit_size_report.clone())
             .arg(flag_profiling.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
@@ -232,6 +256,8 @@ pub fn build_app() -> Command {
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_emit_llvm_ir.clone())
+            .arg(flag_emit_gc_stack_maps.clone())
+            .arg(flag_emit_size_report.clone())
             .arg(flag_profiling.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
@@ -263,6 +289,8 @@ pub fn build_app() -> Command {
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_emit_llvm_ir.clone())
+            .arg(flag_emit_gc_stack_maps.clone())
+            .arg(flag_emit_size_report.clone())
             .arg(flag_profiling.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
@@ -278,6 +306,8 @@ pub fn build_app() -> Command {
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_emit_llvm_ir.clone())
+            .arg(flag_emit_gc_stack_maps.clone())
+            .arg(flag_emit_size_report.clone())
             .arg(flag_profiling.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
@@ -297,7 +327,7 @@ pub fn build_app() -> Command {
             .arg(
                 Arg::new(FLAG_CHECK)
                     .long(FLAG_CHECK)
-                    .help("Checks that specified files are formatted\n(If formatting is needed, return a non-zero exit code.)")
+                    .help("Checks that specified files are formatted\n(If formatting is needed, print a diff of the changes and return a non-zero exit code.)")
                     .action(ArgAction::SetTrue)
                     .required(false),
             )
@@ -315,7 +345,15 @@ pub fn build_app() -> Command {
                     .action(ArgAction::SetTrue)
                     .required(false),
             )
-            .after_help("If DIRECTORY_OR_FILES is omitted, the .roc files in the current working\ndirectory are formatted.")
+            .arg(
+                Arg::new(FLAG_RANGE)
+                    .long(FLAG_RANGE)
+                    .help("Only format the top-level definitions that overlap this byte range, like 120..300\n(This needs exactly one file, or --stdin.)")
+                    .value_name("START..END")
+                    .value_parser(parse_byte_range)
+                    .required(false),
+            )
+            .after_help("If DIRECTORY_OR_FILES is omitted, the .roc files in the current working\ndirectory are formatted.\n\nFiles and directories listed in a .rocformatignore file are skipped when\nformatting the directory it is in.")
         )
         .subcommand(Command::new(CMD_VERSION)
             .about(concatcp!("Print the Roc compiler’s version, which is currently ", VERSION)))
@@ -323,6 +361,13 @@ pub fn build_app() -> Command {
             .about("Check the code for problems, but don’t build or run it")
             .arg(flag_time.clone())
             .arg(flag_max_threads.clone())
+            .arg(
+                Arg::new(FLAG_ANNOTATE)
+                    .long(FLAG_ANNOTATE)
+                    .help("Add the inferred types of unannotated top-level definitions to the file as annotations")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file of an app to check")
@@ -331,6 +376,25 @@ pub fn build_app() -> Command {
                     .default_value(DEFAULT_ROC_FILENAME),
             )
             )
+        .subcommand(Command::new(CMD_DEPS)
+            .about("Print the graph of which modules import which, as DOT or JSON")
+            .arg(flag_max_threads.clone())
+            .arg(
+                Arg::new(FLAG_FORMAT)
+                    .long(FLAG_FORMAT)
+                    .help("The format to print the graph in")
+                    .value_parser(["dot", "json"])
+                    .required(false)
+                    .default_value("dot"),
+            )
+            .arg(
+                Arg::new(ROC_FILE)
+                    .help("The .roc file of an app, package or platform")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false)
+                    .default_value(DEFAULT_ROC_FILENAME),
+            )
+        )
         .subcommand(
             Command::new(CMD_DOCS)
                 .about("Generate documentation for a Roc package")
@@ -410,6 +474,8 @@ pub fn build_app() -> Command {
         .arg(flag_opt_size)
         .arg(flag_dev)
         .arg(flag_emit_llvm_ir)
+        .arg(flag_emit_gc_stack_maps)
+        .arg(flag_emit_size_report)
         .arg(flag_profiling)
         .arg(flag_time)
         .arg(flag_linker)
@@ -505,6 +571,7 @@ pub fn test(matches: &ArgMatches, target: Target) -> io::Result<i32> {
         palette: roc_reporting::report::DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Test,
+        opt_level,
     };
     let load_result = roc_load::load_and_monomorphize(
         arena,
@@ -799,6 +866,13 @@ pub fn build(
         user_error!("Cannot emit llvm ir while using a dev backend.");
     }
 
+    let emit_gc_stack_maps = matches.get_flag(FLAG_EMIT_GC_STACK_MAPS);
+    if emit_gc_stack_maps && !matches!(code_gen_backend, CodeGenBackend::Llvm(_)) {
+        user_error!("Cannot emit gc stack maps while using a dev backend.");
+    }
+
+    let emit_size_report = matches.get_flag(FLAG_EMIT_SIZE_REPORT);
+
     let emit_debug_info = matches.get_flag(FLAG_PROFILING)
         || matches!(opt_level, OptLevel::Development | OptLevel::Normal);
     let emit_timings = matches.get_flag(FLAG_TIME);
@@ -855,10 +929,12 @@ pub fn build(
         opt_level,
         emit_debug_info,
         emit_llvm_ir,
+        emit_size_report,
         fuzz,
+        emit_gc_stack_maps,
     };
 
-    let load_config = standard_load_config(target, build_ordering, threading);
+    let load_config = standard_load_config(target, build_ordering, threading, opt_level);
 
     let res_binary_path = build_file(
         &arena,
@@ -3,11 +3,12 @@ use bumpalo::Bump;
 use roc_build::link::LinkType;
 use roc_build::program::{check_file, CodeGenBackend};
 use roc_cli::{
-    build_app, format_files, format_src, test, BuildConfig, FormatMode, CMD_BUILD, CMD_CHECK,
-    CMD_DEV, CMD_DOCS, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_PREPROCESS_HOST, CMD_REPL,
-    CMD_RUN, CMD_TEST, CMD_VERSION, DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_DEV, FLAG_LIB,
-    FLAG_NO_LINK, FLAG_OUTPUT, FLAG_STDIN, FLAG_STDOUT, FLAG_TARGET, FLAG_TIME, GLUE_DIR,
-    GLUE_SPEC, ROC_FILE,
+    build_app, format_files, format_src, format_src_range, print_deps, test, unified_diff,
+    BuildConfig, DepsFormat, FormatMode, CMD_BUILD, CMD_CHECK, CMD_DEPS, CMD_DEV, CMD_DOCS,
+    CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_PREPROCESS_HOST, CMD_REPL, CMD_RUN, CMD_TEST,
+    CMD_VERSION, DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_DEV, FLAG_FORMAT, FLAG_LIB, FLAG_NO_LINK,
+    FLAG_OUTPUT, FLAG_RANGE, FLAG_STDIN, FLAG_STDOUT, FLAG_TARGET, FLAG_TIME, GLUE_DIR, GLUE_SPEC,
+    ROC_FILE,
 };
 use roc_docs::generate_docs_html;
 use roc_error_macros::user_error;
@@ -18,6 +19,7 @@ use roc_packaging::cache::{self, RocCacheDir};
 use roc_target::Target;
 use std::fs::{self, FileType};
 use std::io::{self, Read, Write};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use target_lexicon::Triple;
@@ -192,6 +194,7 @@ fn main() -> io::Result<()> {
             let arena = Bump::new();
 
             let emit_timings = matches.get_flag(FLAG_TIME);
+            let annotate = matches.get_flag(roc_cli::FLAG_ANNOTATE);
             let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
             let threading = match matches.get_one::<usize>(roc_cli::FLAG_MAX_THREADS) {
                 None => Threading::AllAvailable,
@@ -204,6 +207,7 @@ fn main() -> io::Result<()> {
                 &arena,
                 roc_file_path.to_owned(),
                 emit_timings,
+                annotate,
                 RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
                 threading,
             ) {
@@ -222,6 +226,26 @@ fn main() -> io::Result<()> {
                 }
             }
         }
+        Some((CMD_DEPS, matches)) => {
+            let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+            let format = match matches.get_one::<String>(FLAG_FORMAT).unwrap().as_str() {
+                "json" => DepsFormat::Json,
+                _ => DepsFormat::Dot,
+            };
+            let threading = match matches.get_one::<usize>(roc_cli::FLAG_MAX_THREADS) {
+                None => Threading::AllAvailable,
+                Some(0) => user_error!("cannot build with at most 0 threads"),
+                Some(1) => Threading::Single,
+                Some(n) => Threading::AtMost(*n),
+            };
+
+            print_deps(
+                roc_file_path.to_owned(),
+                format,
+                RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+                threading,
+            )
+        }
         Some((CMD_REPL, _)) => Ok(roc_repl_cli::main()),
         Some((CMD_DOCS, matches)) => {
             let root_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
@@ -283,7 +307,67 @@ fn main() -> io::Result<()> {
                 roc_files
             };
 
-            let format_exit_code = if from_stdin {
+            let format_exit_code = if let Some(range) = matches.get_one::<Range<usize>>(FLAG_RANGE)
+            {
+                let (file, src) = match (from_stdin, roc_files.as_slice()) {
+                    (true, []) => {
+                        let mut src = String::new();
+
+                        io::stdin().read_to_string(&mut src).unwrap_or_else(|err| {
+                            eprintln!("Could not read stdin: {err:?}");
+                            std::process::exit(1);
+                        });
+
+                        (None, src)
+                    }
+                    (false, [file]) => (Some(file), fs::read_to_string(file)?),
+                    _ => {
+                        eprintln!("The --range flag needs exactly one file to format, either as an argument or from --stdin.");
+                        std::process::exit(1);
+                    }
+                };
+
+                let arena = Bump::new();
+
+                match format_src_range(&arena, &src, range.clone()) {
+                    Ok(formatted_src) => match format_mode {
+                        FormatMode::CheckOnly => {
+                            if src != formatted_src {
+                                let path = file.map_or("<stdin>".to_string(), |file| {
+                                    file.display().to_string()
+                                });
+
+                                print!("{}", unified_diff(&path, &src, &formatted_src));
+                                eprintln!("The given range needs to be reformatted.");
+                                1
+                            } else {
+                                0
+                            }
+                        }
+                        FormatMode::WriteToStdout => {
+                            std::io::stdout()
+                                .lock()
+                                .write_all(formatted_src.as_bytes())
+                                .unwrap();
+
+                            0
+                        }
+                        FormatMode::WriteToFile => {
+                            // We would have errored out already if you specified --stdin
+                            // without either --stdout or --check specified as well.
+                            let file = file.unwrap();
+
+                            fs::write(file, formatted_src)?;
+
+                            0
+                        }
+                    },
+                    Err(problem) => {
+                        eprintln!("`roc format` failed: {problem:?}");
+                        1
+                    }
+                }
+            } else if from_stdin {
                 let mut buf = Vec::new();
                 let arena = Bump::new();
 
@@ -298,7 +382,8 @@ fn main() -> io::Result<()> {
                     Ok(formatted_src) => {
                         match format_mode {
                             FormatMode::CheckOnly => {
-                                if src == formatted_src {
+                                if src != formatted_src {
+                                    print!("{}", unified_diff("<stdin>", src, &formatted_src));
                                     eprintln!("One or more files need to be reformatted.");
                                     1
                                 } else {
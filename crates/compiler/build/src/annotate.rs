@@ -0,0 +1,225 @@
+//! Writing the inferred types of unannotated top-level definitions back into the source, for
+//! `roc check --annotate`.
+
+use roc_can::expr::DeclarationTag;
+use roc_load::LoadedModule;
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+
+/// The source of the root module, with an annotation inserted above every top-level value or
+/// function definition that does not have one yet. Returns `None` if there was nothing to add.
+pub fn annotate_root_module(loaded: &mut LoadedModule) -> Option<String> {
+    let home = loaded.module_id;
+    let declarations = loaded.declarations_by_id.get(&home)?;
+    let (_, source) = loaded.sources.get(&home)?;
+    let subs = loaded.solved.inner_mut();
+
+    // (offset of the start of the line, indentation, name, type)
+    let mut annotations = Vec::new();
+
+    for index in 0..declarations.len() {
+        use DeclarationTag::*;
+
+        match declarations.declarations[index] {
+            Value | Function(_) | Recursive(_) | TailRecursive(_) => {}
+            Destructure(_) | MutualRecursion { .. } | Expectation | ExpectationFx => continue,
+        }
+
+        if declarations.annotations[index].is_some() {
+            continue;
+        }
+
+        let loc_symbol = declarations.symbols[index];
+        let name = loc_symbol.value.as_str(&loaded.interns);
+        let offset = loc_symbol.region.start().offset as usize;
+
+        // Skip definitions that the compiler generated, which have no name in the source
+        if !source
+            .get(offset..)
+            .is_some_and(|rest| rest.starts_with(name))
+        {
+            continue;
+        }
+
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &source[line_start..offset];
+
+        if !indent.chars().all(|c| c == ' ') {
+            continue;
+        }
+
+        let typ = name_and_print_var(
+            declarations.variables[index],
+            subs,
+            home,
+            &loaded.interns,
+            DebugPrint::NOTHING,
+        );
+
+        annotations.push((line_start, indent, name, typ));
+    }
+
+    if annotations.is_empty() {
+        return None;
+    }
+
+    annotations.sort_by_key(|(line_start, ..)| *line_start);
+
+    // keep the line endings of the file as they are
+    let newline = if source.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut annotated = String::with_capacity(source.len());
+    let mut copied_until = 0;
+
+    for (line_start, indent, name, typ) in annotations {
+        annotated.push_str(&source[copied_until..line_start]);
+        annotated.push_str(&format!("{indent}{name} : {typ}{newline}"));
+        copied_until = line_start;
+    }
+
+    annotated.push_str(&source[copied_until..]);
+
+    Some(annotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::annotate_root_module;
+    use bumpalo::Bump;
+    use indoc::indoc;
+    use roc_load::FunctionKind;
+    use roc_packaging::cache::RocCacheDir;
+    use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+    use roc_target::Target;
+
+    fn annotate(source: &str) -> Option<String> {
+        let arena = Bump::new();
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut loaded = roc_load::load_and_typecheck_str(
+            &arena,
+            dir.path().join("Test.roc"),
+            source,
+            dir.path().to_path_buf(),
+            Target::LinuxX64,
+            FunctionKind::LambdaSet,
+            RenderTarget::Generic,
+            RocCacheDir::Disallowed,
+            DEFAULT_PALETTE,
+        )
+        .unwrap_or_else(|_| panic!("failed to load module"));
+
+        annotate_root_module(&mut loaded)
+    }
+
+    const SOURCE: &str = indoc!(
+        r#"
+        interface Test
+            exposes [double, greeting]
+            imports []
+
+        double = \n -> n * 2
+
+        greeting = "hello"
+        "#
+    );
+
+    const ANNOTATED: &str = indoc!(
+        r#"
+        interface Test
+            exposes [double, greeting]
+            imports []
+
+        double : Num a -> Num a
+        double = \n -> n * 2
+
+        greeting : Str
+        greeting = "hello"
+        "#
+    );
+
+    #[test]
+    fn lf_file() {
+        assert_eq!(annotate(SOURCE).as_deref(), Some(ANNOTATED));
+    }
+
+    #[test]
+    fn crlf_file() {
+        let source = SOURCE.replace('\n', "\r\n");
+        let annotated = ANNOTATED.replace('\n', "\r\n");
+
+        assert_eq!(annotate(&source), Some(annotated));
+    }
+
+    #[test]
+    fn already_annotated_def() {
+        let source = indoc!(
+            r#"
+            interface Test
+                exposes [double, greeting]
+                imports []
+
+            double : I64 -> I64
+            double = \n -> n * 2
+
+            greeting = "hello"
+            "#
+        );
+
+        assert_eq!(
+            annotate(source).as_deref(),
+            Some(indoc!(
+                r#"
+                interface Test
+                    exposes [double, greeting]
+                    imports []
+
+                double : I64 -> I64
+                double = \n -> n * 2
+
+                greeting : Str
+                greeting = "hello"
+                "#
+            ))
+        );
+
+        assert_eq!(annotate(ANNOTATED), None);
+    }
+
+    #[test]
+    fn def_in_nested_scope() {
+        let source = indoc!(
+            r#"
+            interface Test
+                exposes [greeting]
+                imports []
+
+            greeting =
+                name = "world"
+
+                Str.concat "hello " name
+            "#
+        );
+
+        // only the top-level definition gets an annotation
+        assert_eq!(
+            annotate(source).as_deref(),
+            Some(indoc!(
+                r#"
+                interface Test
+                    exposes [greeting]
+                    imports []
+
+                greeting : Str
+                greeting =
+                    name = "world"
+
+                    Str.concat "hello " name
+                "#
+            ))
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::link::{
     legacy_host_file, link, preprocess_host_wasm32, rebuild_host, LinkType, LinkingStrategy,
 };
+use crate::size_report::SizeReport;
 use bumpalo::Bump;
 use inkwell::memory_buffer::MemoryBuffer;
 use roc_error_macros::internal_error;
@@ -85,7 +86,9 @@ pub struct CodeGenOptions {
     pub opt_level: OptLevel,
     pub emit_debug_info: bool,
     pub emit_llvm_ir: bool,
+    pub emit_size_report: bool,
     pub fuzz: bool,
+    pub emit_gc_stack_maps: bool,
 }
 
 type GenFromMono<'a> = (CodeObject, CodeGenTiming, ExpectMetadata<'a>);
@@ -104,6 +107,7 @@ pub fn gen_from_mono_module<'a>(
     let debug = code_gen_options.emit_debug_info;
     let emit_llvm_ir = code_gen_options.emit_llvm_ir;
     let fuzz = code_gen_options.fuzz;
+    let gc_stack_maps = code_gen_options.emit_gc_stack_maps;
     let opt = code_gen_options.opt_level;
 
     match code_gen_options.backend {
@@ -133,6 +137,7 @@ pub fn gen_from_mono_module<'a>(
             debug,
             emit_llvm_ir,
             fuzz,
+            gc_stack_maps,
         ),
     }
 }
@@ -151,6 +156,7 @@ fn gen_from_mono_module_llvm<'a>(
     emit_debug_info: bool,
     emit_llvm_ir: bool,
     fuzz: bool,
+    gc_stack_maps: bool,
 ) -> GenFromMono<'a> {
     use crate::target::{self, convert_opt_level};
     use inkwell::attributes::{Attribute, AttributeLoc};
@@ -195,6 +201,13 @@ fn gen_from_mono_module_llvm<'a>(
         }
     }
 
+    let gen_sanitizers = cfg!(feature = "sanitizers") && std::env::var("ROC_SANITIZERS").is_ok();
+    let gen_cfi = gen_sanitizers
+        && std::env::var("ROC_SANITIZERS")
+            .unwrap()
+            .split(',')
+            .any(|x| x.trim() == "cfi");
+
     let builder = context.create_builder();
     let (dibuilder, compile_unit) = roc_gen_llvm::llvm::build::Env::new_debug_info(module);
     let (mpm, _fpm) = roc_gen_llvm::llvm::build::construct_optimization_passes(module, opt_level);
@@ -217,6 +230,8 @@ fn gen_from_mono_module_llvm<'a>(
             .keys()
             .copied()
             .collect(),
+        cfi_checks: gen_cfi,
+        gc_stack_maps,
     };
 
     // does not add any externs for this mode (we have a host) but cleans up some functions around
@@ -268,9 +283,18 @@ fn gen_from_mono_module_llvm<'a>(
         // write the ll code to a file, so we can modify it
         env.module.print_to_file(&app_ll_file).unwrap();
 
+        // the module-level error does not say where the problem is, so narrow it down
+        let failed_functions: Vec<_> = env
+            .module
+            .get_functions()
+            .filter(|function| function.count_basic_blocks() > 0 && !function.verify(false))
+            .map(|function| function.get_name().to_string_lossy().into_owned())
+            .collect();
+
         internal_error!(
-            "😱 LLVM errors when defining module; I wrote the full LLVM IR to {:?}\n\n {}",
+            "😱 LLVM errors when defining module; I wrote the full LLVM IR to {:?}\n\nFunctions that failed verification: {:?}\n\n {}",
             app_ll_file,
+            failed_functions,
             errors.to_string(),
         );
     }
@@ -278,7 +302,6 @@ fn gen_from_mono_module_llvm<'a>(
     // Uncomment this to see the module's optimized LLVM instruction output:
     // env.module.print_to_stderr();
 
-    let gen_sanitizers = cfg!(feature = "sanitizers") && std::env::var("ROC_SANITIZERS").is_ok();
     let memory_buffer = if fuzz || gen_sanitizers {
         let dir = tempfile::tempdir().unwrap();
         let dir = dir.into_path();
@@ -316,6 +339,7 @@ fn gen_from_mono_module_llvm<'a>(
                     "address" => passes.push("asan-module"),
                     "memory" => passes.push("msan-module"),
                     "thread" => passes.push("tsan-module"),
+                    "cfi" => passes.push("lowertypetests"),
                     x => unrecognized.push(x.to_owned()),
                 }
             }
@@ -326,7 +350,7 @@ fn gen_from_mono_module_llvm<'a>(
                 .map(|x| format!("{x:?}"))
                 .collect::<Vec<String>>()
                 .join(", ");
-            eprintln!("Unrecognized sanitizer: {out}\nSupported options are \"address\", \"memory\", \"thread\", \"cargo-fuzz\", and \"afl.rs\".");
+            eprintln!("Unrecognized sanitizer: {out}\nSupported options are \"address\", \"memory\", \"thread\", \"cfi\", \"cargo-fuzz\", and \"afl.rs\".");
             eprintln!("Note: \"cargo-fuzz\" and \"afl.rs\" both enable sanitizer coverage for fuzzing. They just use different parameters to match the respective libraries.")
         }
 
@@ -606,6 +630,37 @@ fn report_timing(buf: &mut String, label: &str, duration: Duration) {
     .unwrap()
 }
 
+/// When each module started and finished loading, relative to the first module to start.
+/// Modules are loaded on several threads, so this shows which modules the others waited on.
+fn report_timeline<'a>(
+    buf: &mut String,
+    modules: impl Iterator<Item = (&'a str, Instant, Instant)>,
+) {
+    use std::fmt::Write;
+
+    let mut modules: Vec<_> = modules.collect();
+    modules.sort_by_key(|(_, start_time, _)| *start_time);
+
+    let Some((_, first_start, _)) = modules.first().copied() else {
+        return;
+    };
+
+    buf.push_str("    Timeline\n");
+
+    for (module_name, start_time, end_time) in modules {
+        let start = start_time.duration_since(first_start).as_secs_f64() * 1000.0;
+        let end = end_time.duration_since(first_start).as_secs_f64() * 1000.0;
+
+        let module_name = if module_name.is_empty() {
+            "Application Module"
+        } else {
+            module_name
+        };
+
+        writeln!(buf, "        {start:9.3} ms - {end:9.3} ms   {module_name}").unwrap();
+    }
+}
+
 pub struct BuiltFile<'a> {
     pub binary_path: PathBuf,
     pub problems: Problems,
@@ -690,6 +745,7 @@ pub fn standard_load_config(
     target: Target,
     order: BuildOrdering,
     threading: Threading,
+    opt_level: OptLevel,
 ) -> LoadConfig {
     let exec_mode = match order {
         BuildOrdering::BuildIfChecks => ExecutionMode::ExecutableIfCheck,
@@ -714,6 +770,7 @@ pub fn standard_load_config(
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode,
+        opt_level,
     }
 }
 
@@ -904,6 +961,28 @@ fn build_loaded_file<'a>(
         }
     }
 
+    buf.push('\n');
+    report_timeline(
+        buf,
+        loaded.timings.iter().map(|(module_id, module_timing)| {
+            (
+                loaded.interns.module_name(*module_id),
+                module_timing.start_time,
+                module_timing.end_time,
+            )
+        }),
+    );
+
+    buf.push('\n');
+    buf.push_str("    ");
+    buf.push_str("Mono IR Passes");
+    buf.push('\n');
+
+    {
+        use std::fmt::Write;
+        write!(buf, "{}", loaded.mono_timing).unwrap();
+    }
+
     // This only needs to be mutable for report_problems. This can't be done
     // inside a nested scope without causing a borrow error!
     let mut loaded = loaded;
@@ -933,6 +1012,10 @@ fn build_loaded_file<'a>(
         None
     };
 
+    let mut size_report = code_gen_options
+        .emit_size_report
+        .then(|| SizeReport::from_mono(&loaded));
+
     let (roc_app_bytes, code_gen_timing, expect_metadata) = gen_from_mono_module(
         arena,
         loaded,
@@ -957,6 +1040,15 @@ fn build_loaded_file<'a>(
     buf.push('\n');
     report_timing(buf, "Total", code_gen_timing.total);
 
+    if let Some(size_report) = size_report.as_mut() {
+        size_report.add_object_sizes(&roc_app_bytes);
+
+        println!(
+            "\nHere's how much code each function generated, largest first:\n\n{}",
+            size_report.render()
+        );
+    }
+
     let compilation_end = compilation_start.elapsed();
     let size = roc_app_bytes.len();
 
@@ -1188,6 +1280,7 @@ pub fn check_file<'a>(
     arena: &'a Bump,
     roc_file_path: PathBuf,
     emit_timings: bool,
+    annotate: bool,
     roc_cache_dir: RocCacheDir<'_>,
     threading: Threading,
 ) -> Result<(Problems, Duration), LoadingProblem<'a>> {
@@ -1208,6 +1301,7 @@ pub fn check_file<'a>(
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Check,
+        opt_level: OptLevel::Development,
     };
     let mut loaded =
         roc_load::load_and_typecheck(arena, roc_file_path, roc_cache_dir, load_config)?;
@@ -1244,6 +1338,18 @@ pub fn check_file<'a>(
         }
     }
 
+    buf.push('\n');
+    report_timeline(
+        buf,
+        loaded.timings.iter().map(|(module_id, module_timing)| {
+            (
+                loaded.interns.module_name(*module_id),
+                module_timing.start_time,
+                module_timing.end_time,
+            )
+        }),
+    );
+
     let compilation_end = compilation_start.elapsed();
 
     if emit_timings {
@@ -1254,7 +1360,21 @@ pub fn check_file<'a>(
         println!("Finished checking in {} ms\n", compilation_end.as_millis(),);
     }
 
-    Ok((report_problems_typechecked(&mut loaded), compilation_end))
+    let problems = report_problems_typechecked(&mut loaded);
+
+    // Only annotate code that type checks, so the inferred types are the intended ones
+    if annotate && problems.errors == 0 {
+        if let Some(annotated) = crate::annotate::annotate_root_module(&mut loaded) {
+            std::fs::write(&loaded.filename, annotated).map_err(|error| {
+                LoadingProblem::FileProblem {
+                    filename: loaded.filename.clone(),
+                    error: error.kind(),
+                }
+            })?;
+        }
+    }
+
+    Ok((problems, compilation_end))
 }
 
 pub fn build_str_test<'a>(
@@ -1270,7 +1390,9 @@ pub fn build_str_test<'a>(
         opt_level: OptLevel::Normal,
         emit_debug_info: false,
         emit_llvm_ir: false,
+        emit_size_report: false,
         fuzz: false,
+        emit_gc_stack_maps: false,
     };
 
     let emit_timings = false;
@@ -1282,7 +1404,12 @@ pub fn build_str_test<'a>(
     let build_ordering = BuildOrdering::AlwaysBuild;
     let threading = Threading::AtMost(2);
 
-    let load_config = standard_load_config(target, build_ordering, threading);
+    let load_config = standard_load_config(
+        target,
+        build_ordering,
+        threading,
+        code_gen_options.opt_level,
+    );
 
     let compilation_start = std::time::Instant::now();
 
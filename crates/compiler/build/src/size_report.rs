@@ -0,0 +1,182 @@
+//! A report of which functions the generated code is spent on, for `--emit-size-report`.
+//!
+//! Both the LLVM and the dev backends name the specializations of a function
+//! `{module}_{ident}_{suffix}`, so the symbols of the object file can be traced back to the
+//! function they were specialized from.
+
+use object::{Object, ObjectSymbol, SymbolKind};
+use roc_collections::MutMap;
+use roc_load::MonomorphizedModule;
+
+pub struct SizeReport {
+    functions: MutMap<String, FunctionSize>,
+}
+
+struct FunctionSize {
+    name: String,
+    specializations: usize,
+    /// The number of mono IR statements in all specializations
+    statements: usize,
+    /// The size of the generated code of all specializations
+    bytes: u64,
+}
+
+impl SizeReport {
+    /// Count the specializations of every function and their mono IR statements.
+    /// This must happen before code gen, which consumes the procedures.
+    pub fn from_mono(loaded: &MonomorphizedModule) -> Self {
+        let mut functions: MutMap<String, FunctionSize> = MutMap::default();
+
+        for ((symbol, _), proc) in loaded.procedures.iter() {
+            let module_name = loaded
+                .interns
+                .module_ids
+                .get_name(symbol.module_id())
+                .unwrap();
+            let ident = symbol.as_str(&loaded.interns);
+
+            let function = functions
+                .entry(format!("{module_name}_{ident}"))
+                .or_insert_with(|| FunctionSize {
+                    name: format!("{module_name}.{ident}"),
+                    specializations: 0,
+                    statements: 0,
+                    bytes: 0,
+                });
+
+            function.specializations += 1;
+            function.statements += proc.size();
+        }
+
+        Self { functions }
+    }
+
+    /// Attribute the size of every function symbol in the object file to the function it was
+    /// specialized from. Helpers generated by the backends have no mono IR and are left out.
+    pub fn add_object_sizes(&mut self, object_bytes: &[u8]) {
+        let file = match object::File::parse(object_bytes) {
+            Ok(file) => file,
+            Err(error) => {
+                eprintln!("Could not read the generated object file for the size report: {error}");
+                return;
+            }
+        };
+
+        let symbols = file
+            .symbols()
+            .filter(|symbol| symbol.kind() == SymbolKind::Text)
+            .filter_map(|symbol| Some((symbol.name().ok()?, symbol.size())));
+
+        self.add_symbol_sizes(symbols);
+    }
+
+    fn add_symbol_sizes<'a>(&mut self, symbols: impl Iterator<Item = (&'a str, u64)>) {
+        for (name, size) in symbols {
+            // Mach-O prefixes every symbol with an underscore
+            let candidates = [Some(name), name.strip_prefix('_')];
+
+            for name in candidates.into_iter().flatten() {
+                let Some((prefix, _suffix)) = name.rsplit_once('_') else {
+                    continue;
+                };
+
+                if let Some(function) = self.functions.get_mut(prefix) {
+                    function.bytes += size;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// The functions, largest first
+    pub fn render(&self) -> String {
+        use std::fmt::Write;
+
+        let mut functions: Vec<&FunctionSize> = self.functions.values().collect();
+        functions.sort_by(|a, b| {
+            (b.bytes, b.statements)
+                .cmp(&(a.bytes, a.statements))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let mut buf = String::new();
+
+        writeln!(
+            buf,
+            "{:>10}   {:>10}   {:>15}   function",
+            "bytes", "statements", "specializations"
+        )
+        .unwrap();
+
+        for function in functions {
+            writeln!(
+                buf,
+                "{:>10}   {:>10}   {:>15}   {}",
+                function.bytes, function.statements, function.specializations, function.name
+            )
+            .unwrap();
+        }
+
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FunctionSize, SizeReport};
+
+    fn size_report(functions: &[(&str, &str, usize, usize)]) -> SizeReport {
+        let functions = functions
+            .iter()
+            .map(|&(prefix, name, specializations, statements)| {
+                let function = FunctionSize {
+                    name: name.to_string(),
+                    specializations,
+                    statements,
+                    bytes: 0,
+                };
+
+                (prefix.to_string(), function)
+            })
+            .collect();
+
+        SizeReport { functions }
+    }
+
+    #[test]
+    fn sizes_are_summed_and_sorted() {
+        let mut report = size_report(&[
+            ("Test_helper", "Test.helper", 1, 3),
+            ("Test_other", "Test.other", 1, 4),
+            ("Num_add", "Num.add", 2, 4),
+            ("Test_main", "Test.main", 1, 10),
+        ]);
+
+        report.add_symbol_sizes(
+            [
+                ("Test_main_1234", 64),
+                ("Num_add_aa", 16),
+                // as Mach-O names it
+                ("_Num_add_bb", 16),
+                ("Test_other_cc", 32),
+                // helpers generated by the backend, which have no function in the report
+                ("roc_builtins.list.map", 100),
+                ("Test_main", 8),
+            ]
+            .into_iter(),
+        );
+
+        let rendered = report.render();
+
+        assert_eq!(
+            rendered.lines().collect::<Vec<_>>(),
+            [
+                "     bytes   statements   specializations   function",
+                "        64           10                 1   Test.main",
+                "        32            4                 2   Num.add",
+                "        32            4                 1   Test.other",
+                "         0            3                 1   Test.helper",
+            ]
+        );
+    }
+}
@@ -905,13 +905,22 @@ divChecked = \a, b ->
         Ok (Num.div a b)
 
 divCeil : Int a, Int a -> Int a
+divCeil = \a, b ->
+    if Num.isZero b then
+        crash "Integer division by 0!"
+    else
+        Num.divCeilUnchecked a b
 
 divCeilChecked : Int a, Int a -> Result (Int a) [DivByZero]
 divCeilChecked = \a, b ->
     if Num.isZero b then
         Err DivByZero
     else
-        Ok (Num.divCeil a b)
+        Ok (Num.divCeilUnchecked a b)
+
+## Like [Num.divCeil], but without checking for zero first. If the second
+## argument is zero, it will crash.
+divCeilUnchecked : Int a, Int a -> Int a
 
 ## Divides two integers, truncating the result towards zero.
 ##
@@ -1161,9 +1161,19 @@ fn desugar_bin_ops<'a>(
     let mut arg_stack: Vec<&'a Loc<Expr>> = Vec::with_capacity_in(lefts.len() + 1, arena);
     let mut op_stack: Vec<Loc<BinOp>> = Vec::with_capacity_in(lefts.len(), arena);
 
-    for (loc_expr, loc_op) in lefts {
+    for (index, (loc_expr, loc_op)) in lefts.iter().enumerate() {
         arg_stack.push(desugar_expr(arena, loc_expr, src, line_info, module_path));
-        match run_binop_step(arena, whole_region, &mut arg_stack, &mut op_stack, *loc_op) {
+
+        let next_operand_region = right_operand_region(lefts, right, index);
+
+        match run_binop_step(
+            arena,
+            whole_region,
+            &mut arg_stack,
+            &mut op_stack,
+            *loc_op,
+            next_operand_region,
+        ) {
             Err(problem) => return problem,
             Ok(()) => continue,
         }
@@ -1178,6 +1188,30 @@ fn desugar_bin_ops<'a>(
     expr
 }
 
+/// The region of the right operand of the operator after `lefts[index]`: the next operand,
+/// together with the operators of higher precedence that follow it and their operands.
+/// For the second `==` in `a == b == c + d`, that is `c + d`.
+fn right_operand_region(
+    lefts: &[(Loc<Expr<'_>>, Loc<BinOp>)],
+    right: &Loc<Expr<'_>>,
+    index: usize,
+) -> Region {
+    let op = lefts[index].1.value;
+    let mut operands = lefts[index + 1..]
+        .iter()
+        .map(|(operand, next_op)| (operand.region, Some(next_op.value)))
+        .chain(std::iter::once((right.region, None)));
+
+    let (start, mut next_op) = operands.next().unwrap();
+    let mut end = start;
+
+    while next_op.is_some_and(|next_op| next_op > op) {
+        (end, next_op) = operands.next().unwrap();
+    }
+
+    Region::span_across(&start, &end)
+}
+
 enum Step<'a> {
     Error(&'a Loc<Expr<'a>>),
     Push(Loc<BinOp>),
@@ -1190,12 +1224,27 @@ fn run_binop_step<'a>(
     arg_stack: &mut Vec<&'a Loc<Expr<'a>>>,
     op_stack: &mut Vec<Loc<BinOp>>,
     next_op: Loc<BinOp>,
+    next_operand_region: Region,
 ) -> Result<(), &'a Loc<Expr<'a>>> {
     use Step::*;
 
-    match binop_step(arena, whole_region, arg_stack, op_stack, next_op) {
+    match binop_step(
+        arena,
+        whole_region,
+        arg_stack,
+        op_stack,
+        next_op,
+        next_operand_region,
+    ) {
         Error(problem) => Err(problem),
-        Push(loc_op) => run_binop_step(arena, whole_region, arg_stack, op_stack, loc_op),
+        Push(loc_op) => run_binop_step(
+            arena,
+            whole_region,
+            arg_stack,
+            op_stack,
+            loc_op,
+            next_operand_region,
+        ),
         Skip => Ok(()),
     }
 }
@@ -1206,6 +1255,7 @@ fn binop_step<'a>(
     arg_stack: &mut Vec<&'a Loc<Expr<'a>>>,
     op_stack: &mut Vec<Loc<BinOp>>,
     next_op: Loc<BinOp>,
+    next_operand_region: Region,
 ) -> Step<'a> {
     use roc_module::called_via::Associativity::*;
     use std::cmp::Ordering;
@@ -1261,6 +1311,7 @@ fn binop_step<'a>(
                             let bad_op = next_op;
                             let right = arg_stack.pop().unwrap();
                             let left = arg_stack.pop().unwrap();
+                            let operand_regions = [left.region, right.region, next_operand_region];
                             let broken_expr =
                                 arena.alloc(new_op_call_expr(arena, left, stack_op, right));
                             let region = broken_expr.region;
@@ -1270,6 +1321,7 @@ fn binop_step<'a>(
                                 binop1: stack_op.value,
                                 binop2_position: bad_op.region.start(),
                                 binop2: bad_op.value,
+                                operand_regions,
                                 expr: arena.alloc(broken_expr),
                             };
                             let value = Expr::PrecedenceConflict(arena.alloc(data));
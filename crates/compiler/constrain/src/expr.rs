@@ -1725,13 +1725,16 @@ pub fn constrain_expr(
             constraints.exists_many(vars, arg_cons)
         }
         TypedHole(var) => {
-            // store the expected type for this position
-            constraints.equal_types_var(
+            // store the expected type for this position, and report it once solving is done
+            let eq = constraints.equal_types_var(
                 *var,
                 expected,
                 Category::Storage(std::file!(), std::line!()),
                 region,
-            )
+            );
+            let hole = constraints.typed_hole(*var, region);
+
+            constraints.exists_many([*var], [eq, hole])
         }
         RuntimeError(_) => {
             // Runtime Errors are always going to crash, so they don't introduce any new
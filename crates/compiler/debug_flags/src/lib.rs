@@ -89,6 +89,12 @@ flags! {
     /// Only use this in single-threaded mode!
     ROC_PRINT_UNIFICATIONS_DERIVED
 
+    /// Traces every unification as it starts and ends, one line per event, indented by nesting
+    /// depth. Each line lists the mode, both variables with their ranks and contents, and, once
+    /// the unification ends, its outcome, so that the trace can be filtered with e.g. grep.
+    /// Only use this in single-threaded mode!
+    ROC_TRACE_UNIFY
+
     /// Prints all type mismatches hit during type unification.
     ROC_PRINT_MISMATCHES
 
@@ -135,6 +141,10 @@ flags! {
     /// instructions.
     ROC_PRINT_IR_AFTER_REFCOUNT
 
+    /// Writes the inferred ownership of the parameters of every procedure to stderr,
+    /// before refcount instructions are inserted.
+    ROC_PRINT_BORROW_SIGNATURES
+
     /// Writes a pretty-printed mono IR to stderr after the tail recursion (modulo cons)
     /// has been applied.
     ROC_PRINT_IR_AFTER_TRMC
@@ -143,6 +153,26 @@ flags! {
     /// Which inlines drop functions to remove pairs of alloc/dealloc instructions of its children.
     ROC_PRINT_IR_AFTER_DROP_SPECIALIZATION
 
+    /// Writes a pretty-printed mono IR to stderr after calls to higher-order builtins
+    /// with a known function have been turned into loops.
+    ROC_PRINT_IR_AFTER_HIGHER_ORDER_SPECIALIZATION
+
+    /// Writes a pretty-printed mono IR to stderr after small procedures
+    /// have been inlined into their callers.
+    ROC_PRINT_IR_AFTER_INLINING
+
+    /// Writes a pretty-printed mono IR to stderr after constants have been folded
+    /// and branches on constants have been pruned.
+    ROC_PRINT_IR_AFTER_CONSTANT_FOLDING
+
+    /// Writes a pretty-printed mono IR to stderr after repeated computations of the same
+    /// value have been shared.
+    ROC_PRINT_IR_AFTER_COMMON_SUBEXPRESSION_ELIMINATION
+
+    /// Writes a pretty-printed mono IR to stderr after unused bindings, unreachable branches
+    /// and unused join points have been removed.
+    ROC_PRINT_IR_AFTER_DEAD_CODE_ELIMINATION
+
     /// Prints debug information during the alias analysis pass.
     ROC_DEBUG_ALIAS_ANALYSIS
 
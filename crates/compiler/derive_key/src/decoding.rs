@@ -79,7 +79,7 @@ impl FlatDecodable {
                     Err(Underivable) // yet
                 }
                 FlatType::EmptyRecord => Ok(Key(FlatDecodableKey::Record(vec![]))),
-                FlatType::EmptyTuple => todo!(),
+                FlatType::EmptyTuple => Ok(Key(FlatDecodableKey::Tuple(0))),
                 FlatType::EmptyTagUnion => {
                     Err(Underivable) // yet
                 }
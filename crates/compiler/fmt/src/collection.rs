@@ -50,6 +50,11 @@ pub fn fmt_collection<'a, 'buf, T: ExtractSpaces<'a> + Formattable>(
 
             if item.before.is_empty() || is_only_newlines {
                 buf.ensure_ends_with_newline();
+
+                if !is_first_item && item.before.len() > 1 {
+                    // A blank line between items separates groups of them, so keep 1 blank line.
+                    buf.newline();
+                }
             } else {
                 if is_first_item {
                     // The first item in a multiline collection always begins with exactly
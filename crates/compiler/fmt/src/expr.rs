@@ -1422,10 +1422,10 @@ fn fmt_record_like<'a, Field, Format, ToSpaceBefore>(
                 let is_first_item = index == 0;
                 if let Some((_sub_field, spaces)) = to_space_before(&field.value) {
                     let is_only_newlines = spaces.iter().all(|s| s.is_newline());
-                    if !is_first_item
-                        && !is_only_newlines
-                        && count_leading_newlines(spaces.iter()) > 1
-                    {
+
+                    // A blank line before a field separates groups of fields, so keep 1 blank
+                    // line, whether or not there is a comment after it.
+                    if !is_first_item && count_leading_newlines(spaces.iter()) > 1 {
                         buf.newline();
                     }
 
@@ -1,10 +1,11 @@
 /// Helpers for interacting with the zig that generates bitcode
 use crate::debug_info_init;
 use crate::llvm::build::{
-    complex_bitcast_check_size, load_roc_value, to_cc_return, CCReturn, Env, C_CALL_CONV,
-    FAST_CALL_CONV,
+    complex_bitcast_check_size, entry_block_alloca_zerofill, load_roc_value, to_cc_return,
+    CCReturn, Env, C_CALL_CONV, FAST_CALL_CONV,
 };
 use crate::llvm::convert::basic_type_from_layout;
+use crate::llvm::fn_ptr::add_zig_callback_cfi_type;
 use crate::llvm::refcounting::{
     decrement_refcount_layout, increment_n_refcount_layout, increment_refcount_layout,
 };
@@ -262,6 +263,7 @@ fn build_transform_caller_help<'a, 'ctx>(
 
     // called from zig, must use C calling convention
     function_value.set_call_conventions(C_CALL_CONV);
+    add_zig_callback_cfi_type(env, function_value);
 
     let kind_id = Attribute::get_named_enum_kind_id("alwaysinline");
     debug_assert!(kind_id > 0);
@@ -447,6 +449,7 @@ fn build_rc_wrapper<'a, 'ctx>(
 
             // called from zig, must use C calling convention
             function_value.set_call_conventions(C_CALL_CONV);
+            add_zig_callback_cfi_type(env, function_value);
 
             let kind_id = Attribute::get_named_enum_kind_id("alwaysinline");
             debug_assert!(kind_id > 0);
@@ -538,6 +541,7 @@ pub fn build_eq_wrapper<'a, 'ctx>(
 
             // called from zig, must use C calling convention
             function_value.set_call_conventions(C_CALL_CONV);
+            add_zig_callback_cfi_type(env, function_value);
 
             let kind_id = Attribute::get_named_enum_kind_id("alwaysinline");
             debug_assert!(kind_id > 0);
@@ -636,6 +640,7 @@ pub fn build_compare_wrapper<'a, 'ctx>(
 
             // called from zig, must use C calling convention
             function_value.set_call_conventions(C_CALL_CONV);
+            add_zig_callback_cfi_type(env, function_value);
 
             // we expose this function to zig; must use c calling convention
             function_value.set_call_conventions(C_CALL_CONV);
@@ -1174,21 +1179,21 @@ pub(crate) fn call_bitcode_fn_with_record_arg<'ctx>(
     arg: BasicValueEnum<'ctx>,
     fn_name: &str,
 ) -> BasicValueEnum<'ctx> {
-    let roc_call_alloca = env
-        .builder
-        .new_build_alloca(arg.get_type(), "roc_call_alloca");
+    let roc_call_alloca = entry_block_alloca_zerofill(env, arg.get_type(), "roc_call_alloca");
+    env.lifetime_start(roc_call_alloca);
     env.builder.new_build_store(roc_call_alloca, arg);
 
     let fn_val = env.module.get_function(fn_name).unwrap();
 
+    let mut zig_call_alloca = None;
     let mut args: Vec<BasicValueEnum<'ctx>> = Vec::with_capacity(fn_val.count_params() as usize);
     if fn_val.get_first_param().unwrap().is_pointer_value() {
         // call by pointer
-        let zig_call_alloca = env
-            .builder
-            .new_build_alloca(arg.get_type(), "zig_return_alloca");
-        env.builder.new_build_store(zig_call_alloca, arg);
-        args.push(zig_call_alloca.into());
+        let alloca = entry_block_alloca_zerofill(env, arg.get_type(), "zig_return_alloca");
+        env.lifetime_start(alloca);
+        env.builder.new_build_store(alloca, arg);
+        args.push(alloca.into());
+        zig_call_alloca = Some(alloca);
     } else if fn_val.count_params() == 1 {
         //c all single with arg as
         let zig_param_type = fn_val.get_params()[0].get_type();
@@ -1212,7 +1217,14 @@ pub(crate) fn call_bitcode_fn_with_record_arg<'ctx>(
             args.push(zig_value);
         }
     }
-    call_bitcode_fn(env, &args, fn_name)
+    let result = call_bitcode_fn(env, &args, fn_name);
+
+    env.lifetime_end(roc_call_alloca);
+    if let Some(alloca) = zig_call_alloca {
+        env.lifetime_end(alloca);
+    }
+
+    result
 }
 
 pub(crate) fn call_bitcode_fn_returning_record<'ctx>(
@@ -1232,24 +1244,30 @@ pub(crate) fn call_bitcode_fn_returning_record<'ctx>(
             .module
             .get_struct_type(bitcode_return_type_name)
             .unwrap();
-        zig_return_alloca = env
-            .builder
-            .new_build_alloca(bitcode_return_type, "zig_return_alloca");
+        zig_return_alloca =
+            entry_block_alloca_zerofill(env, bitcode_return_type.into(), "zig_return_alloca");
+        env.lifetime_start(zig_return_alloca);
         call_void_bitcode_fn(env, &[zig_return_alloca.into(), arg], fn_name);
     } else {
         // direct return
         let zig_result = call_bitcode_fn(env, &[arg], fn_name);
-        zig_return_alloca = env
-            .builder
-            .new_build_alloca(zig_result.get_type(), "zig_return_alloca");
+        zig_return_alloca =
+            entry_block_alloca_zerofill(env, zig_result.get_type(), "zig_return_alloca");
+        env.lifetime_start(zig_return_alloca);
         env.builder.new_build_store(zig_return_alloca, zig_result);
     }
 
-    load_roc_value(
+    // load_roc_value copies values that are passed by reference into a fresh alloca,
+    // so the zig return slot is dead after this point
+    let result = load_roc_value(
         env,
         layout_interner,
         layout_repr,
         zig_return_alloca,
         "result",
-    )
+    );
+
+    env.lifetime_end(zig_return_alloca);
+
+    result
 }
@@ -23,13 +23,13 @@ use inkwell::memory_buffer::MemoryBuffer;
 use inkwell::module::{Linkage, Module};
 use inkwell::passes::{PassManager, PassManagerBuilder};
 use inkwell::types::{
-    AnyType, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FloatMathType, FunctionType,
-    IntMathType, IntType, PointerMathType, StructType,
+    AnyType, AnyTypeEnum, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FloatMathType,
+    FunctionType, IntMathType, IntType, PointerMathType, StructType,
 };
 use inkwell::values::{
     BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallSiteValue, FloatMathValue,
-    FunctionValue, InstructionOpcode, InstructionValue, IntMathValue, IntValue, PhiValue,
-    PointerMathValue, PointerValue, StructValue,
+    FunctionValue, InstructionOpcode, InstructionValue, IntMathValue, IntValue, MetadataValue,
+    PhiValue, PointerMathValue, PointerValue, StructValue,
 };
 use inkwell::{AddressSpace, IntPredicate};
 use inkwell::{FloatPredicate, OptimizationLevel};
@@ -58,8 +58,8 @@ use std::path::Path;
 
 use super::convert::{struct_type_from_union_layout, RocUnion};
 use super::intrinsics::{
-    add_intrinsics, LLVM_FRAME_ADDRESS, LLVM_MEMSET_I32, LLVM_MEMSET_I64, LLVM_SETJMP,
-    LLVM_STACK_SAVE,
+    add_intrinsics, LLVM_FRAME_ADDRESS, LLVM_LIFETIME_END, LLVM_LIFETIME_START, LLVM_MEMSET_I32,
+    LLVM_MEMSET_I64, LLVM_SETJMP, LLVM_STACKMAP, LLVM_STACK_SAVE, LLVM_TRAP, LLVM_TYPE_TEST,
 };
 use super::lowlevel::run_higher_order_low_level;
 use super::scope::Scope;
@@ -739,6 +739,12 @@ pub struct Env<'a, 'ctx, 'env> {
     pub target: Target,
     pub mode: LlvmBackendMode,
     pub exposed_to_host: MutSet<Symbol>,
+    /// Guard indirect calls of erased functions with a type test (forward-edge CFI).
+    /// The checks must be lowered by LLVM's `lowertypetests` pass.
+    pub cfi_checks: bool,
+    /// Record the stack slots of the refcounted values that are live across direct calls between
+    /// Roc procedures, in the `.llvm_stackmaps` section of the object file.
+    pub gc_stack_maps: bool,
 }
 
 impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
@@ -887,6 +893,29 @@ impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
         )
     }
 
+    /// Marks the start of the live range of a stack slot. Together with [`Env::lifetime_end`]
+    /// this lets LLVM overlap the stack slots of short-lived temporaries, which keeps frame
+    /// sizes down in functions with many of them.
+    pub fn lifetime_start(&self, alloca: PointerValue<'ctx>) -> CallSiteValue<'ctx> {
+        self.build_lifetime_marker(LLVM_LIFETIME_START, alloca)
+    }
+
+    /// Marks the end of the live range of a stack slot; the slot must not be read afterwards.
+    pub fn lifetime_end(&self, alloca: PointerValue<'ctx>) -> CallSiteValue<'ctx> {
+        self.build_lifetime_marker(LLVM_LIFETIME_END, alloca)
+    }
+
+    fn build_lifetime_marker(
+        &self,
+        intrinsic_name: &'static str,
+        alloca: PointerValue<'ctx>,
+    ) -> CallSiteValue<'ctx> {
+        // a size of -1 means the marker covers the whole alloca
+        let size = self.context.i64_type().const_all_ones();
+
+        self.build_intrinsic_call(intrinsic_name, &[size.into(), alloca.into()])
+    }
+
     pub fn call_panic(
         &self,
         env: &Env<'a, 'ctx, 'env>,
@@ -1485,6 +1514,180 @@ fn small_str_ptr_width_4<'ctx>(env: &Env<'_, 'ctx, '_>, str_literal: &str) -> St
     )
 }
 
+/// Bind `symbol`. With stack maps, a refcounted value also gets a stack slot of its own, which
+/// holds the current value of the symbol, see [build_stack_mapped_call]. A value that is passed by
+/// reference is copied to its slot, and the symbol then refers to the slot itself.
+fn bind_symbol<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    scope: &mut Scope<'a, 'ctx>,
+    parent: FunctionValue<'ctx>,
+    symbol: Symbol,
+    layout: InLayout<'a>,
+    value: BasicValueEnum<'ctx>,
+) {
+    if !(env.gc_stack_maps && layout_interner.contains_refcounted(layout)) {
+        scope.insert(symbol, layout, value);
+        return;
+    }
+
+    let repr = layout_interner.get_repr(layout);
+    let basic_type = basic_type_from_layout(env, layout_interner, repr);
+    let slot = create_entry_block_alloca(env, parent, basic_type, "gc_root");
+
+    store_roc_value(env, layout_interner, repr, slot, value);
+
+    let value = if repr.is_passed_by_reference(layout_interner) {
+        slot.into()
+    } else {
+        value
+    };
+
+    scope.insert(symbol, layout, value);
+    scope.insert_gc_root(symbol, slot);
+}
+
+/// Load the refcounted values among `symbols` back from their stack slots, because a collector
+/// may have moved them. The symbols are bound to the loaded values, and their previous values are
+/// added to `rebound`, so the caller can restore them once they go out of scope.
+fn reload_gc_roots<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    scope: &mut Scope<'a, 'ctx>,
+    symbols: &[Symbol],
+    rebound: &mut std::vec::Vec<(Symbol, InLayout<'a>, BasicValueEnum<'ctx>)>,
+) {
+    for symbol in symbols {
+        let (Some(slot), Some(&(layout, value))) =
+            (scope.get_gc_root(symbol), scope.get_symbol(symbol))
+        else {
+            continue;
+        };
+
+        // the symbol already refers to its slot
+        if layout_interner.is_passed_by_reference(layout) {
+            continue;
+        }
+
+        let reloaded = env
+            .builder
+            .new_build_load(value.get_type(), slot, "gc_root_reload");
+
+        scope.insert(*symbol, layout, reloaded);
+        rebound.push((*symbol, layout, value));
+    }
+}
+
+/// The symbols that `stmt` still uses, directly or in the body of a join point that it jumps to
+fn live_symbols<'a>(
+    env: &Env<'a, '_, '_>,
+    scope: &Scope<'a, '_>,
+    stmt: &roc_mono::ir::Stmt<'a>,
+) -> MutSet<Symbol> {
+    let (mut live, jumps) = roc_mono::dead_code::used_symbols_and_jumps(env.arena, stmt);
+
+    for id in jumps {
+        if let Some(uses) = scope.get_join_point_uses(id) {
+            live.extend(uses.iter().copied());
+        }
+    }
+
+    live
+}
+
+fn is_direct_call(expr: &roc_mono::ir::Expr<'_>) -> bool {
+    matches!(
+        expr,
+        roc_mono::ir::Expr::Call(roc_mono::ir::Call {
+            call_type: CallType::ByName { .. },
+            ..
+        })
+    )
+}
+
+/// Call a Roc procedure, and record the stack slots of the refcounted values that are live across
+/// the call with an `llvm.experimental.stackmap` directly after it, so the recorded offset is the
+/// return address. A collector may update the slots during the call, so the values are loaded back
+/// from them afterwards.
+///
+/// The stack map id numbers the function in the high 32 bits and the call within that function
+/// in the low 32 bits, in the order they are generated, so every entry in the `.llvm_stackmaps`
+/// section belongs to exactly one call.
+fn build_stack_mapped_call<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    func_spec_solutions: &FuncSpecSolutions,
+    scope: &mut Scope<'a, 'ctx>,
+    call: &roc_mono::ir::Call<'a>,
+    live_after: &MutSet<Symbol>,
+    rebound: &mut std::vec::Vec<(Symbol, InLayout<'a>, BasicValueEnum<'ctx>)>,
+) -> BasicValueEnum<'ctx> {
+    let roc_mono::ir::Call {
+        call_type:
+            CallType::ByName {
+                name,
+                specialization_id,
+                ret_layout,
+                ..
+            },
+        arguments,
+    } = call
+    else {
+        internal_error!("only direct calls get a stack map");
+    };
+
+    let mut roots: std::vec::Vec<(Symbol, PointerValue<'ctx>)> = live_after
+        .iter()
+        .filter_map(|symbol| Some((*symbol, scope.get_gc_root(symbol)?)))
+        .collect();
+
+    // set iteration order is unspecified; keep the stack map layout stable between builds
+    roots.sort_by_key(|(symbol, _)| *symbol);
+
+    let mut args: Vec<BasicValueEnum> = Vec::with_capacity_in(arguments.len(), env.arena);
+
+    for symbol in arguments.iter() {
+        args.push(scope.load_symbol(symbol));
+    }
+
+    let bytes = specialization_id.to_bytes();
+    let callee_var = CalleeSpecVar(&bytes);
+    let func_spec = func_spec_solutions.callee_spec(callee_var).unwrap();
+    let fn_val = function_value_by_func_spec(env, FuncBorrowSpec::Some(func_spec), name.name());
+
+    let id = scope.next_stack_map_id();
+
+    let build_call = |arguments: &[BasicMetadataValueEnum<'ctx>]| {
+        let call = env.builder.new_build_call(fn_val, arguments, "call");
+
+        let mut stack_map_args: Vec<BasicValueEnum<'ctx>> =
+            Vec::with_capacity_in(roots.len() + 2, env.arena);
+
+        stack_map_args.push(env.context.i64_type().const_int(id, false).into());
+        // we only need the locations, so don't reserve a shadow for patching
+        stack_map_args.push(env.context.i32_type().const_zero().into());
+        stack_map_args.extend(roots.iter().map(|(_, slot)| BasicValueEnum::from(*slot)));
+
+        env.build_intrinsic_call(LLVM_STACKMAP, &stack_map_args);
+
+        call
+    };
+
+    let result = call_roc_function_help(
+        env,
+        layout_interner,
+        build_call,
+        fn_val.get_type(),
+        layout_interner.get_repr(*ret_layout),
+        args.into_bump_slice(),
+    );
+
+    let symbols: std::vec::Vec<Symbol> = roots.iter().map(|(symbol, _)| *symbol).collect();
+    reload_gc_roots(env, layout_interner, scope, &symbols, rebound);
+
+    result
+}
+
 pub(crate) fn build_exp_call<'a, 'ctx>(
     env: &Env<'a, 'ctx, '_>,
     layout_interner: &STLayoutInterner<'a>,
@@ -1701,7 +1904,7 @@ pub(crate) fn build_exp_expr<'a, 'ctx>(
         }
 
         FunctionPointer { lambda_name } => {
-            let alloca = fn_ptr::build(env, *lambda_name);
+            let alloca = fn_ptr::build(env, layout_interner, *lambda_name, layout);
             alloca.into()
         }
         ErasedMake { value, callee } => {
@@ -2690,8 +2893,6 @@ pub fn get_tag_id<'a, 'ctx>(
     union_layout: &UnionLayout<'a>,
     argument: BasicValueEnum<'ctx>,
 ) -> IntValue<'ctx> {
-    let builder = env.builder;
-
     let tag_id_layout = union_layout.tag_id_layout();
     let tag_id_int_type = basic_type_from_layout(
         env,
@@ -2726,7 +2927,9 @@ pub fn get_tag_id<'a, 'ctx>(
             let else_block = ctx.append_basic_block(parent, "else");
             let cont_block = ctx.append_basic_block(parent, "cont");
 
-            let result = builder.new_build_alloca(tag_id_int_type, "result");
+            // only live for this `when`, so its stack slot can be shared with other temporaries
+            let result = create_entry_block_alloca(env, parent, tag_id_int_type.into(), "result");
+            env.lifetime_start(result);
 
             env.builder
                 .new_build_conditional_branch(is_null, then_block, else_block);
@@ -2752,9 +2955,14 @@ pub fn get_tag_id<'a, 'ctx>(
 
             env.builder.position_at_end(cont_block);
 
-            env.builder
+            let tag_id = env
+                .builder
                 .new_build_load(tag_id_int_type, result, "load_result")
-                .into_int_value()
+                .into_int_value();
+
+            env.lifetime_end(result);
+
+            tag_id
         }
         UnionLayout::NullableUnwrapped { nullable_id, .. } => {
             let argument_ptr = argument.into_pointer_value();
@@ -3237,22 +3445,53 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
 
             let mut stack = Vec::with_capacity_in(queue.len(), env.arena);
 
-            for (symbol, expr, layout) in queue {
+            // with stack maps, find the values that are still live after each direct call
+            let mut live_after = std::vec::Vec::new();
+            if env.gc_stack_maps {
+                let mut live = live_symbols(env, scope, cont);
+                live_after.resize_with(queue.len(), MutSet::default);
+
+                for (i, (symbol, expr, _)) in queue.iter().enumerate().rev() {
+                    if is_direct_call(expr) {
+                        live_after[i] = live.clone();
+                    }
+
+                    live.remove(*symbol);
+                    live.extend(roc_mono::dead_code::expr_used_symbols(env.arena, expr));
+                }
+            }
+
+            let mut rebound = std::vec::Vec::new();
+
+            for (i, (symbol, expr, layout)) in queue.into_iter().enumerate() {
                 debug_assert!(!matches!(
                     layout_interner.get_repr(*layout),
                     LayoutRepr::RecursivePointer(_)
                 ));
 
-                let val = build_exp_expr(
-                    env,
-                    layout_interner,
-                    layout_ids,
-                    func_spec_solutions,
-                    scope,
-                    parent,
-                    *layout,
-                    expr,
-                );
+                let val = match expr {
+                    roc_mono::ir::Expr::Call(call) if env.gc_stack_maps && is_direct_call(expr) => {
+                        build_stack_mapped_call(
+                            env,
+                            layout_interner,
+                            func_spec_solutions,
+                            scope,
+                            call,
+                            &live_after[i],
+                            &mut rebound,
+                        )
+                    }
+                    _ => build_exp_expr(
+                        env,
+                        layout_interner,
+                        layout_ids,
+                        func_spec_solutions,
+                        scope,
+                        parent,
+                        *layout,
+                        expr,
+                    ),
+                };
 
                 // Make a new scope which includes the binding we just encountered.
                 // This should be done *after* compiling the bound expr, since any
@@ -3261,7 +3500,7 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
                 // access itself!
                 // scope = scope.clone();
 
-                scope.insert(*symbol, *layout, val);
+                bind_symbol(env, layout_interner, scope, parent, *symbol, *layout, val);
                 stack.push(*symbol);
             }
 
@@ -3275,6 +3514,10 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
                 cont,
             );
 
+            for (symbol, layout, value) in rebound.into_iter().rev() {
+                scope.insert(symbol, layout, value);
+            }
+
             for symbol in stack {
                 scope.remove(&symbol);
             }
@@ -3363,6 +3606,18 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
             // store this join point
             scope.insert_join_point(*id, cont_block, joinpoint_args);
 
+            // with stack maps, a jump keeps alive the values that the body uses from outside
+            let mut outer_uses = std::vec::Vec::new();
+            if env.gc_stack_maps {
+                outer_uses = live_symbols(env, scope, continuation)
+                    .into_iter()
+                    .filter(|symbol| scope.get_symbol(symbol).is_some())
+                    .collect();
+                outer_uses.sort();
+
+                scope.insert_join_point_uses(*id, outer_uses.clone());
+            }
+
             // construct the blocks that may jump to this join point
             build_exp_stmt(
                 env,
@@ -3384,6 +3639,25 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
                 .bind_parameters_to_join_point(*id, parameters.iter())
                 .expect("join point not found, but it was inserted above");
 
+            let mut rebound = std::vec::Vec::new();
+            if env.gc_stack_maps {
+                for param in parameters.iter() {
+                    let value = scope.load_symbol(&param.symbol);
+                    bind_symbol(
+                        env,
+                        layout_interner,
+                        scope,
+                        parent,
+                        param.symbol,
+                        param.layout,
+                        value,
+                    );
+                }
+
+                // the values from outside may have moved while the body was not yet reached
+                reload_gc_roots(env, layout_interner, scope, &outer_uses, &mut rebound);
+            }
+
             // put the continuation in
             let result = build_exp_stmt(
                 env,
@@ -3395,6 +3669,10 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
                 continuation,
             );
 
+            for (symbol, layout, value) in rebound.into_iter().rev() {
+                scope.insert(symbol, layout, value);
+            }
+
             // remove this join point again
             scope.remove_join_point(*id);
 
@@ -5490,7 +5768,7 @@ pub(crate) fn build_proc_headers<'a, 'r, 'ctx>(
     env: &'r Env<'a, 'ctx, '_>,
     layout_interner: &'r STLayoutInterner<'a>,
     mod_solutions: &'a ModSolutions,
-    procedures: MutMap<(Symbol, ProcLayout<'a>), roc_mono::ir::Proc<'a>>,
+    procedures: std::vec::Vec<((Symbol, ProcLayout<'a>), roc_mono::ir::Proc<'a>)>,
     scope: &mut Scope<'a, 'ctx>,
     layout_ids: &mut LayoutIds<'a>,
     // alias_analysis_solutions: AliasAnalysisSolutions,
@@ -5738,6 +6016,34 @@ pub fn build_procedures_expose_expects<'a>(
     expect_names_by_module
 }
 
+/// The procedures in an order that only depends on their names and layouts. Symbols are numbered
+/// by module id, and module ids are handed out in whatever order the worker threads load the
+/// modules in, so the order of the map would change the order of the functions in the module.
+fn sorted_procedures<'a>(
+    env: &Env<'a, '_, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    procedures: MutMap<(Symbol, ProcLayout<'a>), roc_mono::ir::Proc<'a>>,
+) -> std::vec::Vec<((Symbol, ProcLayout<'a>), roc_mono::ir::Proc<'a>)> {
+    let mut procedures: std::vec::Vec<_> = procedures.into_iter().collect();
+
+    procedures.sort_by_cached_key(|((symbol, layout), _)| {
+        let arguments: std::vec::Vec<_> = layout
+            .arguments
+            .iter()
+            .map(|argument| layout_interner.dbg(*argument))
+            .collect();
+
+        (
+            symbol.module_string(&env.interns).to_string(),
+            symbol.as_str(&env.interns).to_string(),
+            arguments,
+            layout_interner.dbg(layout.result),
+        )
+    });
+
+    procedures
+}
+
 fn build_procedures_help<'a>(
     env: &Env<'a, '_, '_>,
     layout_interner: &STLayoutInterner<'a>,
@@ -5750,7 +6056,19 @@ fn build_procedures_help<'a>(
     let mut layout_ids = roc_mono::layout::LayoutIds::default();
     let mut scope = Scope::default();
 
-    let it1 = procedures.iter().map(|x| x.1);
+    // Before any Roc code is generated, the only functions with a body come from the zig builtins
+    let zig_functions: std::vec::Vec<_> = if env.cfi_checks {
+        env.module
+            .get_functions()
+            .filter(|function| function.count_basic_blocks() > 0)
+            .collect()
+    } else {
+        vec![]
+    };
+
+    let procedures = sorted_procedures(env, layout_interner, procedures);
+
+    let it1 = procedures.iter().map(|x| &x.1);
     let it2 = host_exposed_lambda_sets.iter().map(|(_, _, hels)| hels);
 
     let solutions = match roc_alias_analysis::spec_program(
@@ -5785,6 +6103,13 @@ fn build_procedures_help<'a>(
 
     let (_, function_pass) = construct_optimization_passes(env.module, opt_level);
 
+    // Keep going when a function fails verification, so that a single run reports every
+    // broken function rather than just the first one.
+    let mut failed_verification: std::vec::Vec<String> = std::vec::Vec::new();
+
+    // gives every stack map in the module a distinct id, see [build_stack_mapped_call]
+    let mut function_number = 0;
+
     for (proc, fn_vals) in headers {
         for (func_spec_solutions, fn_val) in fn_vals {
             let mut current_scope = scope.clone();
@@ -5794,12 +6119,16 @@ fn build_procedures_help<'a>(
             let home = proc.name.name().module_id();
             current_scope.retain_top_level_thunks_for_module(home);
 
+            let mut proc_scope = scope.clone();
+            proc_scope.set_stack_map_function(function_number);
+            function_number += 1;
+
             build_proc(
                 env,
                 layout_interner,
                 &mut layout_ids,
                 func_spec_solutions,
-                scope.clone(),
+                proc_scope,
                 &proc,
                 fn_val,
             );
@@ -5810,37 +6139,48 @@ fn build_procedures_help<'a>(
             if fn_val.verify(true) {
                 function_pass.run_on(&fn_val);
             } else {
-                let mode = "NON-OPTIMIZED";
+                let fn_name = fn_val.get_name().to_str().unwrap().to_string();
 
                 eprintln!(
-                    "\n\nFunction {:?} failed LLVM verification in {} build. Its content was:\n",
-                    fn_val.get_name().to_str().unwrap(),
-                    mode,
+                    "\n\nFunction {:?} failed LLVM verification in NON-OPTIMIZED build. Its content was:\n",
+                    fn_name,
                 );
 
                 fn_val.print_to_stderr();
 
-                if let Some(app_ll_file) = debug_output_file {
-                    env.module.print_to_file(app_ll_file).unwrap();
-
-                    panic!(
-                        r"😱 LLVM errors when defining function {:?}; I wrote the full LLVM IR to {:?}",
-                        fn_val.get_name().to_str().unwrap(),
-                        app_ll_file,
-                    );
-                } else {
-                    env.module.print_to_stderr();
+                eprintln!(
+                    "\nIt was generated from this mono IR:\n\n{}",
+                    proc.to_pretty(layout_interner, 200, true)
+                );
 
-                    panic!(
-                    "The preceding code was from {:?}, which failed LLVM verification in {} build.",
-                     fn_val.get_name().to_str().unwrap(),
-                    mode,
-                    )
-                }
+                failed_verification.push(fn_name);
             }
         }
     }
 
+    build_zig_callback_cfi_checks(env, &zig_functions);
+
+    if !failed_verification.is_empty() {
+        let names = failed_verification.join(", ");
+
+        if let Some(app_ll_file) = debug_output_file {
+            env.module.print_to_file(app_ll_file).unwrap();
+
+            panic!(
+                r"😱 LLVM errors when defining {} function(s): {}; I wrote the full LLVM IR to {:?}",
+                failed_verification.len(),
+                names,
+                app_ll_file,
+            );
+        } else {
+            panic!(
+                "{} function(s) failed LLVM verification in NON-OPTIMIZED build: {}",
+                failed_verification.len(),
+                names,
+            )
+        }
+    }
+
     use LlvmBackendMode::*;
     match env.mode {
         GenTest | WasmGenTest | CliTest => { /* no host, or exposing types is not supported */ }
@@ -6249,7 +6589,15 @@ fn build_proc<'a, 'ctx>(
     // Add args to scope
     for (arg_val, (layout, arg_symbol)) in fn_val.get_param_iter().zip(args) {
         arg_val.set_name(arg_symbol.as_str(&env.interns));
-        scope.insert(*arg_symbol, *layout, arg_val);
+        bind_symbol(
+            env,
+            layout_interner,
+            &mut scope,
+            fn_val,
+            *arg_symbol,
+            *layout,
+            arg_val,
+        );
     }
 
     let body = build_exp_stmt(
@@ -6325,6 +6673,169 @@ fn roc_call_direct_with_args<'a, 'ctx>(
     )
 }
 
+/// Traps unless `function_pointer` points to a function that was marked with `type_id` (see
+/// [`fn_ptr::add_cfi_type`]).
+fn build_cfi_check<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    function_pointer: PointerValue<'ctx>,
+    type_id: MetadataValue<'ctx>,
+) {
+    let builder = env.builder;
+    let parent = builder.get_insert_block().unwrap().get_parent().unwrap();
+
+    let type_test = env.module.get_function(LLVM_TYPE_TEST).unwrap();
+
+    let is_valid_target = builder
+        .new_build_call(
+            type_test,
+            &[function_pointer.into(), type_id.into()],
+            "cfi_type_test",
+        )
+        .try_as_basic_value()
+        .left()
+        .unwrap()
+        .into_int_value();
+
+    build_trap_unless(env, parent, is_valid_target);
+}
+
+/// Traps unless `condition` holds, and continues building in a new block where it does
+fn build_trap_unless<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    parent: FunctionValue<'ctx>,
+    condition: IntValue<'ctx>,
+) {
+    let builder = env.builder;
+
+    let valid_block = env.context.append_basic_block(parent, "cfi_valid");
+    let invalid_block = env.context.append_basic_block(parent, "cfi_invalid");
+
+    builder.new_build_conditional_branch(condition, valid_block, invalid_block);
+
+    builder.position_at_end(invalid_block);
+    env.build_intrinsic_call(LLVM_TRAP, &[]);
+    builder.new_build_unreachable();
+
+    builder.position_at_end(valid_block);
+}
+
+/// Guards the indirect calls in the zig builtins, which go to the wrappers that Roc code passes
+/// them (see [`fn_ptr::add_zig_callback_cfi_type`]). Splitting the blocks of zig functions is
+/// not worth it here, so each call is preceded by a call to a function that does the branch.
+fn build_zig_callback_cfi_checks<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    zig_functions: &[FunctionValue<'ctx>],
+) {
+    if zig_functions.is_empty() {
+        return;
+    }
+
+    let type_test = env.module.get_function(LLVM_TYPE_TEST).unwrap();
+    let trap_unless = cfi_trap_unless_function(env);
+
+    // the calls are not part of the Roc function that was built last
+    env.builder.unset_current_debug_location();
+
+    for function in zig_functions {
+        for block in function.get_basic_blocks() {
+            let mut next = block.get_first_instruction();
+
+            while let Some(instruction) = next {
+                next = instruction.get_next_instruction();
+
+                let Some((callee, function_type)) = indirect_call(*function, instruction) else {
+                    continue;
+                };
+
+                env.builder.position_before(&instruction);
+
+                let type_id = fn_ptr::zig_callback_type_id(env, function_type);
+                let is_valid_target = env
+                    .builder
+                    .new_build_call(type_test, &[callee.into(), type_id.into()], "cfi_type_test")
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap();
+
+                env.builder
+                    .new_build_call(trap_unless, &[is_valid_target.into()], "");
+            }
+        }
+    }
+}
+
+/// `void roc_cfi_trap_unless(i1 is_valid_target)`
+fn cfi_trap_unless_function<'ctx>(env: &Env<'_, 'ctx, '_>) -> FunctionValue<'ctx> {
+    let fn_name = "roc_cfi_trap_unless";
+
+    if let Some(function) = env.module.get_function(fn_name) {
+        return function;
+    }
+
+    let fn_type = env
+        .context
+        .void_type()
+        .fn_type(&[env.context.bool_type().into()], false);
+    let function = env
+        .module
+        .add_function(fn_name, fn_type, Some(Linkage::Private));
+
+    let kind_id = Attribute::get_named_enum_kind_id("alwaysinline");
+    debug_assert!(kind_id > 0);
+    let attr = env.context.create_enum_attribute(kind_id, 0);
+    function.add_attribute(AttributeLoc::Function, attr);
+
+    let entry = env.context.append_basic_block(function, "entry");
+    env.builder.position_at_end(entry);
+
+    let is_valid_target = function.get_first_param().unwrap().into_int_value();
+    build_trap_unless(env, function, is_valid_target);
+    env.builder.new_build_return(None);
+
+    function
+}
+
+/// The pointer and the type of the call if `instruction` calls a function pointer that `function`
+/// was given or loaded. Calls of functions and of inline assembly are not indirect.
+fn indirect_call<'ctx>(
+    function: FunctionValue<'ctx>,
+    instruction: InstructionValue<'ctx>,
+) -> Option<(PointerValue<'ctx>, FunctionType<'ctx>)> {
+    if instruction.get_opcode() != InstructionOpcode::Call {
+        return None;
+    }
+
+    // the callee is the last operand of a call
+    let callee_index = instruction.get_num_operands() - 1;
+    let BasicValueEnum::PointerValue(callee) = instruction.get_operand(callee_index)?.left()?
+    else {
+        return None;
+    };
+
+    let is_computed = callee.as_instruction().is_some()
+        || function
+            .get_param_iter()
+            .any(|param| param == BasicValueEnum::PointerValue(callee));
+
+    if !is_computed {
+        return None;
+    }
+
+    let arguments: std::vec::Vec<BasicMetadataTypeEnum<'ctx>> = (0..callee_index)
+        .filter_map(|index| instruction.get_operand(index)?.left())
+        .map(|argument| argument.get_type().into())
+        .collect();
+
+    let function_type = match instruction.get_type() {
+        AnyTypeEnum::VoidType(void_type) => void_type.fn_type(&arguments, false),
+        other => BasicTypeEnum::try_from(other)
+            .ok()?
+            .fn_type(&arguments, false),
+    };
+
+    Some((callee, function_type))
+}
+
 #[inline(always)]
 fn roc_call_erased_with_args<'a, 'ctx>(
     env: &Env<'a, 'ctx, '_>,
@@ -6340,6 +6851,11 @@ fn roc_call_erased_with_args<'a, 'ctx>(
 
     let function_pointer = fn_ptr::cast_to_function_ptr_type(env, pointer, function_ptr_type);
 
+    if env.cfi_checks {
+        let type_id = fn_ptr::cfi_type_id(env, layout_interner, argument_layouts, result_layout);
+        build_cfi_check(env, function_pointer, type_id);
+    }
+
     let build_call = |arguments: &[BasicMetadataValueEnum<'ctx>]| {
         env.builder
             .new_build_indirect_call(function_type, function_pointer, arguments, "call")
@@ -6397,7 +6913,8 @@ fn call_roc_function_help<'a, 'ctx>(
             arguments.pop();
 
             let result_type = basic_type_from_layout(env, layout_interner, result_layout);
-            let result_alloca = env.builder.new_build_alloca(result_type, "result_value");
+            let result_alloca = entry_block_alloca_zerofill(env, result_type, "result_value");
+            env.lifetime_start(result_alloca);
 
             arguments.push(result_alloca.into());
 
@@ -6407,8 +6924,13 @@ fn call_roc_function_help<'a, 'ctx>(
             // roc functions should have the fast calling convention
             call.set_call_convention(FAST_CALL_CONV);
 
-            env.builder
-                .new_build_load(result_type, result_alloca, "load_result")
+            let result = env
+                .builder
+                .new_build_load(result_type, result_alloca, "load_result");
+
+            env.lifetime_end(result_alloca);
+
+            result
         }
         RocReturn::ByPointer => {
             let it = arguments.iter().map(|x| (*x).into());
@@ -1,10 +1,13 @@
 use bumpalo::collections::CollectIn;
 use inkwell::{
     types::{FunctionType, PointerType},
-    values::{FunctionValue, PointerValue},
+    values::{FunctionValue, MetadataValue, PointerValue},
 };
 
-use roc_mono::layout::{InLayout, LambdaName, LayoutInterner, STLayoutInterner};
+use roc_error_macros::internal_error;
+use roc_mono::layout::{
+    FunctionPointer, InLayout, LambdaName, LayoutInterner, LayoutRepr, STLayoutInterner,
+};
 
 use super::{
     build::{
@@ -33,12 +36,91 @@ pub fn function_type<'a, 'ctx>(
     fn_spec.typ
 }
 
-pub fn build<'a, 'ctx>(env: &Env<'a, 'ctx, '_>, lambda_name: LambdaName<'a>) -> PointerValue<'ctx> {
+pub fn build<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    lambda_name: LambdaName<'a>,
+    layout: InLayout<'a>,
+) -> PointerValue<'ctx> {
     let func_value: FunctionValue<'ctx> =
         function_value_by_func_spec(env, FuncBorrowSpec::Erased, lambda_name.name());
+
+    if env.cfi_checks {
+        let LayoutRepr::FunctionPointer(FunctionPointer { args, ret }) =
+            layout_interner.get_repr(layout)
+        else {
+            internal_error!("{lambda_name:?} is not a function pointer")
+        };
+
+        let type_id = cfi_type_id(env, layout_interner, args, ret);
+        add_cfi_type(env, func_value, type_id);
+    }
+
     func_value.as_global_value().as_pointer_value()
 }
 
+/// The CFI type identifier of erased functions with these argument and return layouts. Indirect
+/// calls are only allowed to reach functions that carry the identifier of the call's signature.
+///
+/// The identifier is built from the runtime representation of the Roc layouts rather than from
+/// the LLVM function type: with opaque pointers, a function taking a `Str` and one taking a
+/// `List U8` have the same LLVM type.
+pub fn cfi_type_id<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    arguments: &[InLayout<'a>],
+    return_type: InLayout<'a>,
+) -> MetadataValue<'ctx> {
+    let dbg = |layout| layout_interner.dbg(layout_interner.runtime_representation_in(layout));
+
+    let arguments: std::vec::Vec<_> = arguments.iter().map(|arg| dbg(*arg)).collect();
+    let name = format!(
+        "roc_erased.({}) -> {}",
+        arguments.join(", "),
+        dbg(return_type)
+    );
+
+    env.context.metadata_string(&name)
+}
+
+/// The CFI type identifier of the wrappers that zig builtins call through pointers, like the
+/// transform callers and the refcounting, eq and compare wrappers. The zig call sites only know
+/// the C signature they call, so that is all the identifier is built from.
+pub fn zig_callback_type_id<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    function_type: FunctionType<'ctx>,
+) -> MetadataValue<'ctx> {
+    let name = format!("roc_zig_callback.{}", function_type.print_to_string());
+
+    env.context.metadata_string(&name)
+}
+
+/// Marks `function` as a valid target of indirect calls of the given type, by attaching
+/// `!type !{i64 0, !"<type id>"}` metadata to it.
+pub fn add_cfi_type<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    function: FunctionValue<'ctx>,
+    type_id: MetadataValue<'ctx>,
+) {
+    let type_kind_id = env.context.get_kind_id("type");
+    let offset = env.context.i64_type().const_zero();
+
+    let type_metadata = env.context.metadata_node(&[offset.into(), type_id.into()]);
+
+    function
+        .as_global_value()
+        .set_metadata(type_metadata, type_kind_id);
+}
+
+/// Marks a wrapper as a valid target of the indirect calls in zig builtins, see
+/// [zig_callback_type_id].
+pub fn add_zig_callback_cfi_type<'ctx>(env: &Env<'_, 'ctx, '_>, function: FunctionValue<'ctx>) {
+    if env.cfi_checks {
+        let type_id = zig_callback_type_id(env, function.get_type());
+        add_cfi_type(env, function, type_id);
+    }
+}
+
 pub fn cast_to_function_ptr_type<'ctx>(
     env: &Env<'_, 'ctx, '_>,
     pointer: PointerValue<'ctx>,
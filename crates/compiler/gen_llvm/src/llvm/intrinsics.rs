@@ -78,6 +78,7 @@ pub(crate) fn add_intrinsics<'ctx>(ctx: &'ctx Context, module: &Module<'ctx>) {
     let i8_type = ctx.i8_type();
     let i8_ptr_type = i8_type.ptr_type(AddressSpace::default());
     let i32_type = ctx.i32_type();
+    let i64_type = ctx.i64_type();
     let void_type = ctx.void_type();
 
     add_intrinsic(
@@ -108,6 +109,36 @@ pub(crate) fn add_intrinsics<'ctx>(ctx: &'ctx Context, module: &Module<'ctx>) {
         i8_ptr_type.fn_type(&[], false),
     );
 
+    add_intrinsic(
+        ctx,
+        module,
+        LLVM_LIFETIME_START,
+        void_type.fn_type(&[i64_type.into(), i8_ptr_type.into()], false),
+    );
+
+    add_intrinsic(
+        ctx,
+        module,
+        LLVM_LIFETIME_END,
+        void_type.fn_type(&[i64_type.into(), i8_ptr_type.into()], false),
+    );
+
+    add_intrinsic(ctx, module, LLVM_TRAP, void_type.fn_type(&[], false));
+
+    add_intrinsic(
+        ctx,
+        module,
+        LLVM_TYPE_TEST,
+        i1_type.fn_type(&[i8_ptr_type.into(), ctx.metadata_type().into()], false),
+    );
+
+    add_intrinsic(
+        ctx,
+        module,
+        LLVM_STACKMAP,
+        void_type.fn_type(&[i64_type.into(), i32_type.into()], true),
+    );
+
     add_int_intrinsic(ctx, module, &LLVM_ADD_WITH_OVERFLOW, |t| {
         let fields = [t.into(), i1_type.into()];
         ctx.struct_type(&fields, false)
@@ -141,6 +172,14 @@ pub static LLVM_MEMSET_I32: &str = "llvm.memset.p0i8.i32";
 pub static LLVM_FRAME_ADDRESS: &str = "llvm.frameaddress.p0";
 pub static LLVM_STACK_SAVE: &str = "llvm.stacksave";
 
+pub static LLVM_LIFETIME_START: &str = "llvm.lifetime.start.p0";
+pub static LLVM_LIFETIME_END: &str = "llvm.lifetime.end.p0";
+
+pub static LLVM_STACKMAP: &str = "llvm.experimental.stackmap";
+
+pub static LLVM_TRAP: &str = "llvm.trap";
+pub static LLVM_TYPE_TEST: &str = "llvm.type.test";
+
 pub static LLVM_SETJMP: &str = "llvm.eh.sjlj.setjmp";
 pub static LLVM_LONGJMP: &str = "llvm.eh.sjlj.longjmp";
 
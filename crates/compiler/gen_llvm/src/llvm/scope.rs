@@ -1,6 +1,6 @@
 use inkwell::{
     basic_block::BasicBlock,
-    values::{BasicValueEnum, FunctionValue, PhiValue},
+    values::{BasicValueEnum, FunctionValue, PhiValue, PointerValue},
 };
 use roc_collections::ImMap;
 use roc_module::symbol::{ModuleId, Symbol};
@@ -8,12 +8,20 @@ use roc_mono::{
     ir::{JoinPointId, Param, ProcLayout},
     layout::InLayout,
 };
+use std::{cell::Cell, rc::Rc};
 
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Scope<'a, 'ctx> {
     symbols: ImMap<Symbol, (InLayout<'a>, BasicValueEnum<'ctx>)>,
     top_level_thunks: ImMap<Symbol, (ProcLayout<'a>, FunctionValue<'ctx>)>,
     join_points: ImMap<JoinPointId, (BasicBlock<'ctx>, Vec<PhiValue<'ctx>>)>,
+    /// With stack maps: the stack slot that holds each refcounted value
+    gc_roots: ImMap<Symbol, PointerValue<'ctx>>,
+    /// With stack maps: the symbols from outside of each join point that its body uses
+    join_point_uses: ImMap<JoinPointId, Vec<Symbol>>,
+    /// With stack maps: the number of the current function, and of its next stack mapped call.
+    /// The call counter is shared by all copies of the scope within a function.
+    stack_map_ids: (u32, Rc<Cell<u32>>),
 }
 
 #[derive(Debug)]
@@ -39,6 +47,42 @@ impl<'a, 'ctx> Scope<'a, 'ctx> {
         }
     }
 
+    pub fn get_symbol(&self, symbol: &Symbol) -> Option<&(InLayout<'a>, BasicValueEnum<'ctx>)> {
+        self.symbols.get(symbol)
+    }
+
+    pub fn insert_gc_root(&mut self, symbol: Symbol, slot: PointerValue<'ctx>) {
+        self.gc_roots.insert(symbol, slot);
+    }
+
+    pub fn get_gc_root(&self, symbol: &Symbol) -> Option<PointerValue<'ctx>> {
+        self.gc_roots.get(symbol).copied()
+    }
+
+    pub fn insert_join_point_uses(&mut self, join_point_id: JoinPointId, uses: Vec<Symbol>) {
+        self.join_point_uses.insert(join_point_id, uses);
+    }
+
+    pub fn get_join_point_uses(&self, join_point_id: JoinPointId) -> Option<&[Symbol]> {
+        self.join_point_uses
+            .get(&join_point_id)
+            .map(|uses| uses.as_slice())
+    }
+
+    pub fn set_stack_map_function(&mut self, function: u32) {
+        self.stack_map_ids = (function, Rc::new(Cell::new(0)));
+    }
+
+    /// A stack map id that is unique within the module: the number of the function in the high
+    /// 32 bits, and the number of the call within that function in the low 32 bits
+    pub fn next_stack_map_id(&self) -> u64 {
+        let (function, calls) = &self.stack_map_ids;
+        let call = calls.get();
+        calls.set(call + 1);
+
+        ((*function as u64) << 32) | call as u64
+    }
+
     pub fn insert_top_level_thunk(
         &mut self,
         symbol: Symbol,
@@ -51,6 +95,7 @@ impl<'a, 'ctx> Scope<'a, 'ctx> {
 
     pub fn remove(&mut self, symbol: &Symbol) {
         self.symbols.remove(symbol);
+        self.gc_roots.remove(symbol);
     }
 
     pub fn retain_top_level_thunks_for_module(&mut self, module_id: ModuleId) {
@@ -69,6 +114,7 @@ impl<'a, 'ctx> Scope<'a, 'ctx> {
 
     pub fn remove_join_point(&mut self, join_point_id: JoinPointId) {
         self.join_points.remove(&join_point_id);
+        self.join_point_uses.remove(&join_point_id);
     }
 
     pub fn get_join_point(
@@ -65,6 +65,9 @@ pub struct WasmBackend<'a, 'r> {
     host_lookup: Vec<'a, (&'a str, u32)>,
     helper_proc_gen: CodeGenHelp<'a>,
     can_relocate_heap: bool,
+    /// Addresses of the byte strings already stored in the data section, so identical
+    /// literals share one segment
+    data_section_lookup: MutMap<&'a [u8], u32>,
 
     // Function-level data
     pub code_builder: CodeBuilder<'a>,
@@ -135,6 +138,7 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
             host_lookup,
             helper_proc_gen,
             can_relocate_heap: has_heap_base && has_heap_end,
+            data_section_lookup: MutMap::default(),
 
             // Function-level data
             block_depth: 0,
@@ -1250,9 +1254,13 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         };
     }
 
-    /// Create a string constant in the module data section
+    /// Create a string constant in the module data section, or reuse an identical one
     /// Return the data we need for code gen: linker symbol index and memory address
     fn store_bytes_in_data_section(&mut self, bytes: &[u8]) -> u32 {
+        if let Some(elements_addr) = self.data_section_lookup.get(bytes) {
+            return *elements_addr;
+        }
+
         // Place the segment at a 4-byte aligned offset
         let segment_addr = round_up_to_alignment!(self.module.data.end_addr, PTR_SIZE);
         let elements_addr = segment_addr + PTR_SIZE;
@@ -1271,6 +1279,9 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
 
         self.module.data.append_segment(segment);
 
+        let key = self.env.arena.alloc_slice_copy(bytes);
+        self.data_section_lookup.insert(key, elements_addr);
+
         elements_addr
     }
 
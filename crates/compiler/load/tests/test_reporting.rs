@@ -13,7 +13,9 @@ mod test_reporting {
     use indoc::indoc;
     use roc_can::abilities::AbilitiesStore;
     use roc_can::expr::PendingDerives;
-    use roc_load::{self, ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
+    use roc_load::{
+        self, ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, OptLevel, Threading,
+    };
     use roc_module::symbol::{Interns, ModuleId};
     use roc_packaging::cache::RocCacheDir;
     use roc_parse::module::parse_header;
@@ -130,6 +132,7 @@ mod test_reporting {
                 threading: Threading::Single,
                 exec_mode: ExecutionMode::Check,
                 function_kind: FunctionKind::LambdaSet,
+                opt_level: OptLevel::Development,
             };
             let result = roc_load::load_and_typecheck(
                 arena,
@@ -602,6 +605,41 @@ mod test_reporting {
 
     6│          if selectedId != thisId == adminsId then
                    ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+
+    Add parentheses to pick one of the two ways to group them:
+
+        (selectedId != thisId) == adminsId
+        selectedId != (thisId == adminsId)
+    "
+    );
+
+    test_report!(
+        report_precedence_problem_with_longer_right_operand,
+        indoc!(
+            r"x = 1
+            y =
+                if a == b == c + d then
+                    4
+
+                else
+                    5
+
+            { x, y }
+            "
+        ),
+        @r"
+    ── SYNTAX PROBLEM in /code/proj/Main.roc ───────────────────────────────────────
+
+    Using more than one == like this requires parentheses, to clarify how
+    things should be grouped.
+
+    6│          if a == b == c + d then
+                   ^^^^^^^^^^^^^^^
+
+    Add parentheses to pick one of the two ways to group them:
+
+        (a == b) == c + d
+        a == (b == c + d)
     "
     );
 
@@ -688,6 +726,11 @@ mod test_reporting {
     5│>          1
     6│>              == 2
     7│>              == 3
+
+    Add parentheses to pick one of the two ways to group them:
+
+        (1 == 2) == 3
+        1 == (2 == 3)
     "
     );
 
@@ -1121,6 +1164,10 @@ mod test_reporting {
     infinitely.
 
         (∞ -> a) -> a
+
+    The part of the type that repeats contains itself as:
+
+        the 1st argument of a function
     "
     );
 
@@ -1146,6 +1193,10 @@ mod test_reporting {
     infinitely.
 
         List ∞ -> *
+
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
     "
     );
 
@@ -1173,6 +1224,10 @@ mod test_reporting {
 
         List ∞ -> *
 
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
+
     ── CIRCULAR TYPE in /code/proj/Main.roc ────────────────────────────────────────
 
     I'm inferring a weird self-referential type for `g`:
@@ -1185,6 +1240,10 @@ mod test_reporting {
     infinitely.
 
         List ∞ -> *
+
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
     "
     );
 
@@ -1282,6 +1341,10 @@ mod test_reporting {
     infinitely.
 
         List ∞ -> *
+
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
     "
     );
 
@@ -1308,6 +1371,10 @@ mod test_reporting {
     infinitely.
 
         List ∞ -> List *
+
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
     "
     );
 
@@ -1347,6 +1414,10 @@ mod test_reporting {
 
         List ∞ -> List *
 
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
+
     ── CIRCULAR TYPE in /code/proj/Main.roc ────────────────────────────────────────
 
     I'm inferring a weird self-referential type for `g`:
@@ -1359,6 +1430,10 @@ mod test_reporting {
     infinitely.
 
         List ∞ -> List *
+
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
     "
     );
 
@@ -1387,6 +1462,10 @@ mod test_reporting {
 
         List ∞ -> List *
 
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
+
     ── CIRCULAR TYPE in /code/proj/Main.roc ────────────────────────────────────────
 
     I'm inferring a weird self-referential type for `g`:
@@ -1399,6 +1478,10 @@ mod test_reporting {
     infinitely.
 
         List ∞ -> List *
+
+    The part of the type that repeats contains itself as:
+
+        a type argument of `List`
     "
     );
 
@@ -2318,6 +2401,16 @@ mod test_reporting {
         │     ↓
         │     bar
         └─────┘
+
+    `bar` is defined here:
+
+    6│      bar = foo
+            ^^^
+
+    Roc evaluates values strictly, so running this program would enter an
+    infinite loop!
+
+    Hint: Did you mean to define one of these values as a function?
     "
     );
 
@@ -2955,6 +3048,12 @@ mod test_reporting {
 
     Recursion in aliases is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `Foo` in a tag union that also has a
+    variant which does not refer to `Foo`, so that the recursion can end.
+    For example:
+
+        Foo : [Nil, Cons { x : Bar }]
     "
     );
 
@@ -2981,6 +3080,12 @@ mod test_reporting {
 
     Recursion in aliases is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `Foo` in a tag union that also has a
+    variant which does not refer to `Foo`, so that the recursion can end.
+    For example:
+
+        Foo : [Nil, Cons { x : Foo }]
     "
     );
 
@@ -3359,10 +3464,12 @@ mod test_reporting {
     4│      Foo a : [Foo]
                 ^
 
-    Roc does not allow unused type parameters!
+    Roc does not allow unused type parameters in aliases!
 
     Tip: If you want an unused type parameter (a so-called "phantom
-    type"), read the guide section on phantom values.
+    type"), make `Foo` an opaque type by defining it with `:=` instead of
+    `:`. Opaque types keep their type parameters even when they are
+    unused.
     "#
     );
 
@@ -6096,6 +6203,27 @@ All branches in an `if` must have the same type!
     "#
     );
 
+    test_report!(
+        if_condition_assignment,
+        indoc!(
+            r#"
+            main =
+                if x = 1 then "yes" else "no"
+            "#
+        ),
+        @r#"
+    ── UNKNOWN OPERATOR in tmp/if_condition_assignment/Test.roc ────────────────────
+
+    This looks like an operator, but it's not one I recognize!
+
+    5│          if x = 1 then "yes" else "no"
+                     ^
+
+    To check whether two values are equal, use == instead. A single = can
+    only define a name, like x = 5.
+    "#
+    );
+
     test_report!(
         inline_hastype,
         indoc!(
@@ -7105,6 +7233,12 @@ In roc, functions are always written as a lambda, like{}
 
     Recursion in aliases is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `F` in a tag union that also has a
+    variant which does not refer to `F`, so that the recursion can end.
+    For example:
+
+        F : [Nil, Cons F]
     "
     );
 
@@ -7128,6 +7262,12 @@ In roc, functions are always written as a lambda, like{}
 
     Recursion in aliases is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `F` in a tag union that also has a
+    variant which does not refer to `F`, so that the recursion can end.
+    For example:
+
+        F : [Nil, Cons F]
     "
     );
 
@@ -7150,6 +7290,12 @@ In roc, functions are always written as a lambda, like{}
 
     Recursion in aliases is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `F` in a tag union that also has a
+    variant which does not refer to `F`, so that the recursion can end.
+    For example:
+
+        F a b : [Nil, Cons (F a b)]
     "
     );
 
@@ -7914,6 +8060,12 @@ In roc, functions are always written as a lambda, like{}
 
     Recursion in aliases is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `R` in a tag union that also has a
+    variant which does not refer to `R`, so that the recursion can end.
+    For example:
+
+        R a : [Nil, Only (R a)]
     "
     );
 
@@ -7937,6 +8089,12 @@ In roc, functions are always written as a lambda, like{}
 
     Recursion in aliases is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `R` in a tag union that also has a
+    variant which does not refer to `R`, so that the recursion can end.
+    For example:
+
+        R a : [Nil, Only { very: [Deep (R a)] }]
     "
     );
 
@@ -7970,6 +8128,12 @@ In roc, functions are always written as a lambda, like{}
 
     Recursion in aliases is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `Foo` in a tag union that also has a
+    variant which does not refer to `Foo`, so that the recursion can end.
+    For example:
+
+        Foo a : [Nil, Thing (Bar a)]
     "
     );
 
@@ -9610,6 +9774,10 @@ In roc, functions are always written as a lambda, like{}
 
         { set : Set ∞ }
 
+    The part of the type that repeats contains itself as:
+
+        a type argument of `Set`
+
     ── CIRCULAR TYPE in /code/proj/Main.roc ────────────────────────────────────────
 
     I'm inferring a weird self-referential type for `goal`:
@@ -9622,6 +9790,10 @@ In roc, functions are always written as a lambda, like{}
     infinitely.
 
         Set ∞
+
+    The part of the type that repeats contains itself as:
+
+        a type argument of `Set`
     "
     );
 
@@ -9655,6 +9827,16 @@ In roc, functions are always written as a lambda, like{}
             │     ↓
             │     t2
             └─────┘
+
+        `t2` is defined here:
+
+        9│      t2 = t1 {}
+                ^^
+
+        Roc evaluates values strictly, so running this program would enter an
+        infinite loop!
+
+        Hint: Did you mean to define one of these values as a function?
         "
     );
 
@@ -9748,6 +9930,16 @@ In roc, functions are always written as a lambda, like{}
                 │     ↓
                 │     t2
                 └─────┘
+
+            `t2` is defined here:
+
+            8│  t2 = t1 {}
+                ^^
+
+            Roc evaluates values strictly, so running this program would enter an
+            infinite loop!
+
+            Hint: Did you mean to define one of these values as a function?
             "
     );
 
@@ -10573,20 +10765,58 @@ In roc, functions are always written as a lambda, like{}
         |golden| pretty_assertions::assert_eq!(
             golden,
             indoc!(
-                r"── SYNTAX PROBLEM in /code/proj/Main.roc ───────────────────────────────────────
+                r"── TYPED HOLE in /code/proj/Main.roc ───────────────────────────────────────────
 
-                An underscore is being used as a variable here:
+                This hole needs to be filled in:
 
                 6│      f 1 _ 1
                             ^
 
-                An underscore can be used to ignore a value when pattern matching, but
-                it cannot be used as a variable.
+                Based on how it is used, it should have the type:
+
+                    Num *
+
+                Tip: Any value of this type can go here. Until it is filled in,
+                reaching the hole at runtime will crash.
                 "
             ),
         )
     );
 
+    test_report!(
+        typed_hole_with_value_of_that_type_in_scope,
+        indoc!(
+            r#"
+            greeting = "hello"
+
+            Str.concat _ "!"
+            "#
+        ),
+        |golden| pretty_assertions::assert_eq!(
+            golden,
+            indoc!(
+                r#"── TYPED HOLE in /code/proj/Main.roc ───────────────────────────────────────────
+
+                This hole needs to be filled in:
+
+                6│      Str.concat _ "!"
+                                   ^
+
+                Based on how it is used, it should have the type:
+
+                    Str
+
+                These values in scope have that type:
+
+                    greeting
+
+                Tip: Any value of this type can go here. Until it is filled in,
+                reaching the hole at runtime will crash.
+                "#
+            ),
+        )
+    );
+
     test_report!(
         call_with_declared_identifier_starting_with_underscore,
         indoc!(
@@ -11067,6 +11297,12 @@ In roc, functions are always written as a lambda, like{}
 
     Recursion in opaquees is only allowed if recursion happens behind a
     tagged union, at least one variant of which is not recursive.
+
+    Tip: Wrap the recursive use of `Recursive` in a tag union that also
+    has a variant which does not refer to `Recursive`, so that the
+    recursion can end. For example:
+
+        Recursive := [Nil, Infinitely Recursive]
     "
     );
 
@@ -12695,6 +12931,29 @@ In roc, functions are always written as a lambda, like{}
     "#
     );
 
+    test_report!(
+        range_pattern,
+        indoc!(
+            r#"
+            when 5 is
+                0..9 -> "digit"
+                _ -> "other"
+            "#
+        ),
+    @r#"
+    ── RANGE PATTERN in tmp/range_pattern/Test.roc ─────────────────────────────────
+
+    This looks like a range pattern, but those are not supported yet:
+
+    5│          0..9 -> "digit"
+                ^
+
+    Match each number on its own instead, or use an `if` guard:
+
+        n if n >= 0 && n <= 9 ->
+    "#
+    );
+
     test_report!(
         list_pattern_weird_rest_pattern,
         indoc!(
@@ -13733,7 +13992,6 @@ In roc, functions are always written as a lambda, like{}
     "
     );
 
-    // TODO(weakening-reports)
     test_report!(
         concat_different_types,
         indoc!(
@@ -13759,6 +14017,40 @@ In roc, functions are always written as a lambda, like{}
     But `concat` needs its 2nd argument to be:
 
         List Str
+
+    `empty` is not a function or a number literal, and it is not defined
+    at the top level, so it only gets a single type. That type must be the
+    same everywhere it is used.
+
+    Tip: To use it at different types, turn it into a function, like
+    `\{} -> ...`, and call it at each use.
+    "#
+    );
+
+    test_report!(
+        mismatch_not_caused_by_weakening,
+        indoc!(
+            r#"
+            empty = []
+            str = Str.concat empty "a"
+
+            str
+        "#),
+    @r#"
+    ── TYPE MISMATCH in /code/proj/Main.roc ────────────────────────────────────────
+
+    This 1st argument to `concat` has an unexpected type:
+
+    5│      str = Str.concat empty "a"
+                             ^^^^^
+
+    This `empty` value is a:
+
+        List *
+
+    But `concat` needs its 1st argument to be:
+
+        Str
     "#
     );
 
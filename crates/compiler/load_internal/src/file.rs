@@ -4,7 +4,8 @@ use crate::docs::ModuleDocumentation;
 use crate::module::{
     CheckedModule, ConstrainedModule, EntryPoint, Expectations, ExposedToHost,
     FoundSpecializationsModule, LateSpecializationsModule, LoadedModule, ModuleHeader,
-    ModuleTiming, MonomorphizedModule, ParsedModule, ToplevelExpects, TypeCheckedModule,
+    ModuleTiming, MonoTiming, MonomorphizedModule, ParsedModule, ToplevelExpects,
+    TypeCheckedModule,
 };
 use crate::module_cache::ModuleCache;
 use bumpalo::{collections::CollectIn, Bump};
@@ -25,9 +26,11 @@ use roc_constrain::module::constrain_module;
 use roc_debug_flags::dbg_do;
 #[cfg(debug_assertions)]
 use roc_debug_flags::{
-    ROC_CHECK_MONO_IR, ROC_PRINT_IR_AFTER_DROP_SPECIALIZATION, ROC_PRINT_IR_AFTER_REFCOUNT,
-    ROC_PRINT_IR_AFTER_RESET_REUSE, ROC_PRINT_IR_AFTER_SPECIALIZATION, ROC_PRINT_IR_AFTER_TRMC,
-    ROC_PRINT_LOAD_LOG,
+    ROC_CHECK_MONO_IR, ROC_PRINT_IR_AFTER_COMMON_SUBEXPRESSION_ELIMINATION,
+    ROC_PRINT_IR_AFTER_CONSTANT_FOLDING, ROC_PRINT_IR_AFTER_DEAD_CODE_ELIMINATION,
+    ROC_PRINT_IR_AFTER_DROP_SPECIALIZATION, ROC_PRINT_IR_AFTER_HIGHER_ORDER_SPECIALIZATION,
+    ROC_PRINT_IR_AFTER_INLINING, ROC_PRINT_IR_AFTER_REFCOUNT, ROC_PRINT_IR_AFTER_RESET_REUSE,
+    ROC_PRINT_IR_AFTER_SPECIALIZATION, ROC_PRINT_IR_AFTER_TRMC, ROC_PRINT_LOAD_LOG,
 };
 use roc_derive::SharedDerivedModule;
 use roc_error_macros::internal_error;
@@ -37,6 +40,7 @@ use roc_module::symbol::{
     IdentIds, IdentIdsByModule, Interns, ModuleId, ModuleIds, PQModuleName, PackageModuleIds,
     PackageQualified, Symbol,
 };
+pub use roc_mono::ir::OptLevel;
 use roc_mono::ir::{
     CapturedSymbols, ExternalSpecializations, GlueLayouts, HostExposedLambdaSets, PartialProc,
     Proc, ProcLayout, Procs, ProcsBase, UpdateModeIds, UsageTrackingMap,
@@ -45,7 +49,10 @@ use roc_mono::layout::{
     GlobalLayoutInterner, LambdaName, Layout, LayoutCache, LayoutProblem, Niche, STLayoutInterner,
 };
 use roc_mono::reset_reuse;
-use roc_mono::{drop_specialization, inc_dec};
+use roc_mono::{
+    common_subexpression, constant_folding, dead_code, drop_specialization, higher_order, inc_dec,
+    inline,
+};
 use roc_packaging::cache::RocCacheDir;
 use roc_parse::ast::{self, CommentOrNewline, ExtractSpaces, Spaced, ValueDef};
 use roc_parse::header::{
@@ -108,6 +115,8 @@ pub struct LoadConfig {
     pub threading: Threading,
     pub exec_mode: ExecutionMode,
     pub function_kind: FunctionKind,
+    /// Optimizations on the mono IR only run for [OptLevel::Size] and [OptLevel::Optimize]
+    pub opt_level: OptLevel,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -721,6 +730,7 @@ struct State<'a> {
     pub exposed_symbols_by_module: MutMap<ModuleId, VecSet<Symbol>>,
 
     pub timings: MutMap<ModuleId, ModuleTiming>,
+    pub mono_timing: MonoTiming,
 
     // Each thread gets its own layout cache. When one "pending specializations"
     // pass completes, it returns its layout cache so another thread can use it.
@@ -732,6 +742,7 @@ struct State<'a> {
     pub render: RenderTarget,
     pub palette: Palette,
     pub exec_mode: ExecutionMode,
+    pub opt_level: OptLevel,
 
     /// All abilities across all modules.
     pub world_abilities: WorldAbilities,
@@ -765,6 +776,7 @@ impl<'a> State<'a> {
         palette: Palette,
         number_of_workers: usize,
         exec_mode: ExecutionMode,
+        opt_level: OptLevel,
     ) -> Self {
         let arc_shorthands = Arc::new(Mutex::new(MutMap::default()));
         let cache_dir = roc_packaging::cache::roc_cache_dir();
@@ -796,11 +808,13 @@ impl<'a> State<'a> {
             declarations_by_id: MutMap::default(),
             exposed_symbols_by_module: MutMap::default(),
             timings: MutMap::default(),
+            mono_timing: MonoTiming::default(),
             layout_caches: std::vec::Vec::with_capacity(number_of_workers),
             cached_types: Arc::new(Mutex::new(cached_types)),
             render,
             palette,
             exec_mode,
+            opt_level,
             make_specializations_pass: MakeSpecializationsPass::Pass(1),
             world_abilities: Default::default(),
             layout_interner: GlobalLayoutInterner::with_capacity(128, target),
@@ -853,6 +867,25 @@ impl std::fmt::Display for ModuleTiming {
     }
 }
 
+impl std::fmt::Display for MonoTiming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for pass in self.passes.iter() {
+            writeln!(
+                f,
+                "        {:9.3} ms   {:>10} bytes   {}",
+                pass.duration.as_secs_f64() * 1000.0,
+                pass.allocated_bytes,
+                pass.name,
+            )?;
+        }
+        f.write_str("\n")?;
+        writeln!(f, "        {} procedures", self.procedures)?;
+        writeln!(f, "        {} statements", self.statements)?;
+
+        Ok(())
+    }
+}
+
 /// A message sent _to_ a worker thread, describing the work to be done
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -1069,6 +1102,7 @@ pub fn load_and_typecheck_str<'a>(
         threading,
         exec_mode: ExecutionMode::Check,
         function_kind,
+        opt_level: OptLevel::Development,
     };
 
     match load(
@@ -1338,6 +1372,7 @@ pub fn load<'a>(
             load_config.render,
             load_config.palette,
             load_config.exec_mode,
+            load_config.opt_level,
             roc_cache_dir,
         ),
         Threads::Many(threads) => load_multi_threaded(
@@ -1351,6 +1386,7 @@ pub fn load<'a>(
             load_config.palette,
             threads,
             load_config.exec_mode,
+            load_config.opt_level,
             roc_cache_dir,
         ),
     }
@@ -1367,6 +1403,7 @@ pub fn load_single_threaded<'a>(
     render: RenderTarget,
     palette: Palette,
     exec_mode: ExecutionMode,
+    opt_level: OptLevel,
     roc_cache_dir: RocCacheDir<'_>,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
@@ -1401,6 +1438,7 @@ pub fn load_single_threaded<'a>(
         palette,
         number_of_workers,
         exec_mode,
+        opt_level,
     );
 
     // We'll add tasks to this, and then worker threads will take tasks from it.
@@ -1722,6 +1760,7 @@ fn load_multi_threaded<'a>(
     palette: Palette,
     available_threads: usize,
     exec_mode: ExecutionMode,
+    opt_level: OptLevel,
     roc_cache_dir: RocCacheDir<'_>,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
@@ -1771,6 +1810,7 @@ fn load_multi_threaded<'a>(
         palette,
         num_workers,
         exec_mode,
+        opt_level,
     );
 
     // an arena for every worker, stored in an arena-allocated bumpalo vec to make the lifetimes work
@@ -2916,31 +2956,145 @@ fn update<'a>(
 
                     let ident_ids = state.constrained_ident_ids.get_mut(&module_id).unwrap();
 
-                    roc_mono::tail_recursion::apply_trmc(
-                        arena,
-                        &mut layout_interner,
-                        module_id,
-                        ident_ids,
-                        &mut state.procedures,
-                    );
+                    // Inlined procedures are removed unless one of these still refers to them
+                    let mut roots: std::vec::Vec<Symbol> = state
+                        .exposed_to_host
+                        .top_level_values
+                        .keys()
+                        .chain(state.exposed_to_host.getters.iter())
+                        .copied()
+                        .collect();
+
+                    for (lambda_name, symbol, _) in state.host_exposed_lambda_sets.iter() {
+                        roots.push(lambda_name.name());
+                        roots.push(*symbol);
+                    }
+
+                    for expects in state.toplevel_expects.values() {
+                        roots.extend(expects.pure.keys().chain(expects.fx.keys()));
+                    }
+
+                    state.mono_timing.time_pass(arena, "Inlining", || {
+                        inline::inline_procedures(
+                            arena,
+                            module_id,
+                            ident_ids,
+                            &mut update_mode_ids,
+                            &mut state.procedures,
+                            &roots,
+                        )
+                    });
+
+                    debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_INLINING);
+
+                    state
+                        .mono_timing
+                        .time_pass(arena, "Tail recursion modulo cons", || {
+                            roc_mono::tail_recursion::apply_trmc(
+                                arena,
+                                &mut layout_interner,
+                                module_id,
+                                ident_ids,
+                                &mut state.procedures,
+                            )
+                        });
 
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_TRMC);
 
-                    inc_dec::insert_inc_dec_operations(
-                        arena,
-                        &layout_interner,
-                        &mut state.procedures,
-                    );
+                    if state.opt_level.optimize_mono_ir() {
+                        state
+                            .mono_timing
+                            .time_pass(arena, "Higher-order specialization", || {
+                                higher_order::specialize_higher_order_calls(
+                                    arena,
+                                    &mut layout_interner,
+                                    module_id,
+                                    ident_ids,
+                                    &mut update_mode_ids,
+                                    &mut state.procedures,
+                                )
+                            });
+
+                        debug_print_ir!(
+                            state,
+                            &layout_interner,
+                            ROC_PRINT_IR_AFTER_HIGHER_ORDER_SPECIALIZATION
+                        );
+
+                        state.mono_timing.time_pass(arena, "Constant folding", || {
+                            constant_folding::fold_constants(
+                                arena,
+                                &layout_interner,
+                                &mut state.procedures,
+                            )
+                        });
+
+                        debug_print_ir!(
+                            state,
+                            &layout_interner,
+                            ROC_PRINT_IR_AFTER_CONSTANT_FOLDING
+                        );
+
+                        state.mono_timing.time_pass(
+                            arena,
+                            "Common subexpression elimination",
+                            || {
+                                common_subexpression::eliminate_common_subexpressions(
+                                    arena,
+                                    &mut state.procedures,
+                                )
+                            },
+                        );
+
+                        debug_print_ir!(
+                            state,
+                            &layout_interner,
+                            ROC_PRINT_IR_AFTER_COMMON_SUBEXPRESSION_ELIMINATION
+                        );
+
+                        state
+                            .mono_timing
+                            .time_pass(arena, "Dead code elimination", || {
+                                dead_code::eliminate_dead_code(arena, &mut state.procedures)
+                            });
+
+                        debug_print_ir!(
+                            state,
+                            &layout_interner,
+                            ROC_PRINT_IR_AFTER_DEAD_CODE_ELIMINATION
+                        );
+                    }
+
+                    let host_exposed: std::vec::Vec<_> = state
+                        .exposed_to_host
+                        .top_level_values
+                        .keys()
+                        .copied()
+                        .collect();
+
+                    state.mono_timing.time_pass(arena, "Refcounting", || {
+                        inc_dec::insert_inc_dec_operations(
+                            arena,
+                            &layout_interner,
+                            &mut state.procedures,
+                            state.opt_level.optimize_mono_ir(),
+                            &host_exposed,
+                        )
+                    });
 
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_REFCOUNT);
 
-                    drop_specialization::specialize_drops(
-                        arena,
-                        &mut layout_interner,
-                        module_id,
-                        ident_ids,
-                        &mut state.procedures,
-                    );
+                    state
+                        .mono_timing
+                        .time_pass(arena, "Drop specialization", || {
+                            drop_specialization::specialize_drops(
+                                arena,
+                                &mut layout_interner,
+                                module_id,
+                                ident_ids,
+                                &mut state.procedures,
+                            )
+                        });
 
                     debug_print_ir!(
                         state,
@@ -2948,18 +3102,24 @@ fn update<'a>(
                         ROC_PRINT_IR_AFTER_DROP_SPECIALIZATION
                     );
 
-                    reset_reuse::insert_reset_reuse_operations(
-                        arena,
-                        &layout_interner,
-                        module_id,
-                        state.target,
-                        ident_ids,
-                        &mut update_mode_ids,
-                        &mut state.procedures,
-                    );
+                    state.mono_timing.time_pass(arena, "Reset/reuse", || {
+                        reset_reuse::insert_reset_reuse_operations(
+                            arena,
+                            &layout_interner,
+                            module_id,
+                            state.target,
+                            ident_ids,
+                            &mut update_mode_ids,
+                            &mut state.procedures,
+                        )
+                    });
 
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_RESET_REUSE);
 
+                    state.mono_timing.procedures = state.procedures.len();
+                    state.mono_timing.statements =
+                        state.procedures.values().map(|proc| proc.size()).sum();
+
                     // This is not safe with the new non-recursive RC updates that we do for tag unions
                     //
                     // Proc::optimize_refcount_operations(
@@ -3258,6 +3418,7 @@ fn finish_specialization<'a>(
         entry_point,
         sources,
         timings: state.timings,
+        mono_timing: state.mono_timing,
         toplevel_expects,
         glue_layouts: GlueLayouts { getters: vec![] },
         uses_prebuilt_platform,
@@ -6434,6 +6595,19 @@ fn to_parse_problem_report<'a>(
 ) -> String {
     use roc_reporting::report::{parse_problem, RocDocAllocator};
 
+    // Report the syntax errors in the top-level defs after the first one as well, so they can
+    // all be fixed in one go
+    let arena = Bump::new();
+    let bytes = problem.problem.bytes;
+    let filename = problem.filename.clone();
+    let later_errors =
+        match roc_parse::module::parse_header(&arena, roc_parse::state::State::new(bytes)) {
+            Ok((_, state)) => {
+                roc_parse::module::syntax_errors_after(&arena, state, &problem.problem.problem)
+            }
+            Err(_) => Vec::new(),
+        };
+
     // TODO this is not in fact safe
     let src = unsafe { from_utf8_unchecked(problem.problem.bytes) };
     let src_lines = src.lines().collect::<Vec<_>>();
@@ -6466,6 +6640,20 @@ fn to_parse_problem_report<'a>(
 
     report.render(render, &mut buf, &alloc, &palette);
 
+    for error in later_errors {
+        let problem = FileError {
+            problem: SourceError {
+                problem: error,
+                bytes,
+            },
+            filename: filename.clone(),
+        };
+        let report = parse_problem(&alloc, &lines, filename.clone(), starting_line, problem);
+
+        buf.push_str("\n\n");
+        report.render(render, &mut buf, &alloc, &palette);
+    }
+
     buf
 }
 
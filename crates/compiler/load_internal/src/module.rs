@@ -1,4 +1,5 @@
 use crate::docs::ModuleDocumentation;
+use bumpalo::Bump;
 use roc_can::constraint::{Constraint as ConstraintSoa, Constraints};
 use roc_can::expr::{DbgLookup, ExpectLookup};
 use roc_can::{
@@ -185,6 +186,7 @@ pub struct MonomorphizedModule<'a> {
     pub exposed_to_host: ExposedToHost,
     pub sources: MutMap<ModuleId, (PathBuf, Box<str>)>,
     pub timings: MutMap<ModuleId, ModuleTiming>,
+    pub mono_timing: MonoTiming,
     pub expectations: VecMap<ModuleId, Expectations>,
     pub uses_prebuilt_platform: bool,
     pub glue_layouts: GlueLayouts<'a>,
@@ -308,3 +310,44 @@ impl ModuleTiming {
         calculate(Some(end_time.duration_since(*start_time))).unwrap_or_default()
     }
 }
+
+/// Statistics of the passes over the mono IR of the whole program, after all specializations
+/// have been made
+#[derive(Debug, Default)]
+pub struct MonoTiming {
+    pub passes: Vec<MonoPassTiming>,
+    /// The number of specialized procedures after the last pass
+    pub procedures: usize,
+    /// The number of statements in all procedures after the last pass
+    pub statements: usize,
+}
+
+#[derive(Debug)]
+pub struct MonoPassTiming {
+    pub name: &'static str,
+    pub duration: Duration,
+    /// How many bytes the pass allocated in the arena
+    pub allocated_bytes: usize,
+}
+
+impl MonoTiming {
+    pub fn time_pass<T>(
+        &mut self,
+        arena: &Bump,
+        name: &'static str,
+        pass: impl FnOnce() -> T,
+    ) -> T {
+        let start = Instant::now();
+        let allocated_before = arena.allocated_bytes();
+
+        let result = pass();
+
+        self.passes.push(MonoPassTiming {
+            name,
+            duration: start.elapsed(),
+            allocated_bytes: arena.allocated_bytes().saturating_sub(allocated_before),
+        });
+
+        result
+    }
+}
@@ -19,7 +19,7 @@ use bumpalo::Bump;
 use roc_can::module::ExposedByModule;
 use roc_load_internal::docs::DocDef;
 use roc_load_internal::file::{
-    ExecutionMode, LoadConfig, LoadResult, LoadStart, LoadingProblem, Threading,
+    ExecutionMode, LoadConfig, LoadResult, LoadStart, LoadingProblem, OptLevel, Threading,
 };
 use roc_load_internal::module::LoadedModule;
 use roc_module::ident::ModuleName;
@@ -61,6 +61,7 @@ fn load_and_typecheck(
         palette: DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Check,
+        opt_level: OptLevel::Development,
     };
 
     match roc_load_internal::file::load(
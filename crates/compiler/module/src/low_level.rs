@@ -3,7 +3,7 @@ use crate::symbol::Symbol;
 /// Low-level operations that get translated directly into e.g. LLVM instructions.
 /// These are always wrapped when exposed to end users, and can only make it
 /// into an Expr when added directly by can::builtins
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum LowLevel {
     StrConcat,
     StrJoinWith,
@@ -308,7 +308,7 @@ map_symbol_to_lowlevel! {
     NumLte <= NUM_LTE;
     NumCompare <= NUM_COMPARE;
     NumDivFrac <= NUM_DIV_FRAC;
-    NumDivCeilUnchecked <= NUM_DIV_CEIL;
+    NumDivCeilUnchecked <= NUM_DIV_CEIL_UNCHECKED;
     NumDivTruncUnchecked <= NUM_DIV_TRUNC_UNCHECKED;
     NumRemUnchecked <= NUM_REM_UNCHECKED;
     NumIsMultipleOf <= NUM_IS_MULTIPLE_OF;
@@ -1,28 +1,150 @@
 use bumpalo::{collections::Vec, Bump};
+use roc_collections::{MutMap, MutSet};
+use roc_module::low_level::LowLevelWrapperType;
 use roc_module::symbol::Symbol;
 
 use crate::{
     inc_dec::Ownership,
-    ir::{Call, CallType, Expr, Proc, Stmt},
+    ir::{Call, CallType, Expr, ListLiteralElement, Proc, ProcLayout, Stmt},
     layout::{Builtin, InLayout, LayoutInterner, LayoutRepr},
 };
 
-#[allow(unused)]
-pub(crate) fn infer_borrow_signature<'a>(
+/// The ownership of the parameters of every procedure, in argument order.
+pub(crate) type BorrowSignatures<'a> = MutMap<(Symbol, ProcLayout<'a>), &'a [Ownership]>;
+
+/// Infer for every procedure which of its parameters can be passed as borrowed.
+///
+/// A parameter starts out as borrowed if its layout allows it, and becomes owned as soon as the
+/// body consumes it. Whether passing a parameter to another procedure consumes it depends on
+/// the signature of the callee, so we iterate until none of the signatures change. Parameters
+/// only ever go from borrowed to owned, so this terminates.
+///
+/// Procedures that are exposed to the host, erased, or passed to a higher-order lowlevel are
+/// called from outside of the mono IR with owned arguments, and keep an all-owned signature.
+pub(crate) fn infer_borrow_signatures<'a>(
     arena: &'a Bump,
     interner: &impl LayoutInterner<'a>,
-    proc: &'a Proc<'a>,
-) -> &'a [Ownership] {
-    let mut state = State::new(arena, interner, proc);
-    state.inspect_stmt(&proc.body);
-    state.borrow_signature
+    procedures: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    host_exposed: &[Symbol],
+) -> BorrowSignatures<'a> {
+    let escaping = escaping_procedures(procedures);
+
+    let mut borrow_signatures: BorrowSignatures<'a> = procedures
+        .iter()
+        .map(|(key, proc)| {
+            let symbol = key.0;
+            let must_own = proc.is_erased
+                || host_exposed.contains(&symbol)
+                || escaping.contains(&symbol)
+                || !matches!(
+                    LowLevelWrapperType::from_symbol(symbol),
+                    LowLevelWrapperType::NotALowLevelWrapper
+                );
+
+            let signature = Vec::from_iter_in(
+                proc.args.iter().map(|(in_layout, _)| {
+                    if must_own {
+                        Ownership::Owned
+                    } else {
+                        layout_to_ownership(*in_layout, interner)
+                    }
+                }),
+                arena,
+            )
+            .into_bump_slice();
+
+            (*key, signature)
+        })
+        .collect();
+
+    loop {
+        let mut changed = false;
+
+        for (key, proc) in procedures.iter() {
+            let current = borrow_signatures[key];
+
+            if current.iter().all(Ownership::is_owned) {
+                continue;
+            }
+
+            let mut state = State::new(arena, proc, current, &borrow_signatures);
+            state.inspect_stmt(&proc.body);
+
+            let State {
+                borrow_signature, ..
+            } = state;
+
+            if *borrow_signature != *current {
+                borrow_signatures.insert(*key, borrow_signature);
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break borrow_signatures;
+        }
+    }
 }
 
-struct State<'a> {
+/// Procedures that are referenced other than by a direct call, so we can't know all of their call
+/// sites.
+fn escaping_procedures(procedures: &MutMap<(Symbol, ProcLayout<'_>), Proc<'_>>) -> MutSet<Symbol> {
+    let mut escaping = MutSet::default();
+
+    for proc in procedures.values() {
+        let mut stack = vec![&proc.body];
+
+        while let Some(stmt) = stack.pop() {
+            match stmt {
+                Stmt::Let(_, expr, _, cont) => {
+                    match expr {
+                        Expr::FunctionPointer { lambda_name } => {
+                            escaping.insert(lambda_name.name());
+                        }
+                        Expr::Call(Call {
+                            call_type: CallType::HigherOrder(higher_order),
+                            ..
+                        }) => {
+                            escaping.insert(higher_order.passed_function.name.name());
+                        }
+                        _ => {}
+                    }
+
+                    stack.push(cont);
+                }
+                Stmt::Switch {
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                    stack.push(default_branch.1);
+                }
+                Stmt::Refcounting(_, cont) => stack.push(cont),
+                Stmt::Expect { remainder, .. }
+                | Stmt::ExpectFx { remainder, .. }
+                | Stmt::Dbg { remainder, .. } => stack.push(remainder),
+                Stmt::Join {
+                    body, remainder, ..
+                } => {
+                    stack.push(body);
+                    stack.push(remainder);
+                }
+                Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => {}
+            }
+        }
+    }
+
+    escaping
+}
+
+struct State<'a, 's> {
     /// Argument symbols with a layout of `List *` or `Str`, i.e. the layouts
     /// for which borrow inference might decide to pass as borrowed
     args: &'a [(InLayout<'a>, Symbol)],
     borrow_signature: &'a mut [Ownership],
+    /// The signatures of all procedures, as inferred so far
+    borrow_signatures: &'s BorrowSignatures<'a>,
 }
 
 fn layout_to_ownership<'a>(
@@ -38,19 +160,20 @@ fn layout_to_ownership<'a>(
     }
 }
 
-impl<'a> State<'a> {
-    fn new(arena: &'a Bump, interner: &impl LayoutInterner<'a>, proc: &'a Proc<'a>) -> Self {
-        let borrow_signature = Vec::from_iter_in(
-            proc.args
-                .iter()
-                .map(|(in_layout, _)| layout_to_ownership(*in_layout, interner)),
-            arena,
-        )
-        .into_bump_slice_mut();
+impl<'a, 's> State<'a, 's> {
+    fn new(
+        arena: &'a Bump,
+        proc: &Proc<'a>,
+        current: &[Ownership],
+        borrow_signatures: &'s BorrowSignatures<'a>,
+    ) -> Self {
+        let borrow_signature =
+            Vec::from_iter_in(current.iter().copied(), arena).into_bump_slice_mut();
 
         Self {
             args: proc.args,
             borrow_signature,
+            borrow_signatures,
         }
     }
 
@@ -60,11 +183,24 @@ impl<'a> State<'a> {
         }
     }
 
-    fn inspect_stmt(&mut self, stmt: &'a Stmt<'a>) {
+    fn mark_all_owned(&mut self, symbols: &[Symbol]) {
+        for symbol in symbols.iter() {
+            self.mark_owned(*symbol)
+        }
+    }
+
+    fn inspect_stmt(&mut self, stmt: &Stmt<'a>) {
         match stmt {
-            Stmt::Let(_, expr, _, stmt) => {
-                self.inspect_expr(expr);
-                self.inspect_stmt(stmt);
+            Stmt::Let(_, _, _, _) => {
+                // Walk the chain of let bindings in a loop,
+                // to prevent the stack from overflowing when there are many of them.
+                let mut current_stmt = stmt;
+                while let Stmt::Let(_, expr, _, next_stmt) = current_stmt {
+                    self.inspect_expr(expr);
+                    current_stmt = next_stmt;
+                }
+
+                self.inspect_stmt(current_stmt);
             }
             Stmt::Switch {
                 branches,
@@ -76,15 +212,22 @@ impl<'a> State<'a> {
                 }
                 self.inspect_stmt(default_branch.1);
             }
-            Stmt::Ret(_) => todo!(),
-            Stmt::Refcounting(_, _) => todo!(),
-            Stmt::Expect { .. } | Stmt::ExpectFx { .. } => {
+            Stmt::Ret(symbol) => {
+                // the caller takes ownership of the returned value
+                self.mark_owned(*symbol);
+            }
+            Stmt::Refcounting(_, cont) => {
+                self.inspect_stmt(cont);
+            }
+            Stmt::Expect { remainder, .. } | Stmt::ExpectFx { remainder, .. } => {
                 // TODO do we rely on values being passed by-value here?
                 // it would be better to pass by-reference in general
+                self.inspect_stmt(remainder);
             }
-            Stmt::Dbg { .. } => {
+            Stmt::Dbg { remainder, .. } => {
                 // TODO do we rely on values being passed by-value here?
                 // it would be better to pass by-reference in general
+                self.inspect_stmt(remainder);
             }
             Stmt::Join {
                 body, remainder, ..
@@ -92,33 +235,72 @@ impl<'a> State<'a> {
                 self.inspect_stmt(body);
                 self.inspect_stmt(remainder);
             }
-
-            Stmt::Jump(_, _) | Stmt::Crash(_, _) => { /* not relevant for ownership */ }
+            Stmt::Jump(_, arguments) => {
+                // join point parameters are always owned
+                self.mark_all_owned(arguments);
+            }
+            Stmt::Crash(_, _) => { /* not relevant for ownership */ }
         }
     }
 
-    fn inspect_expr(&mut self, expr: &'a Expr<'a>) {
-        if let Expr::Call(call) = expr {
-            self.inspect_call(call)
+    fn inspect_expr(&mut self, expr: &Expr<'a>) {
+        match expr {
+            Expr::Call(call) => self.inspect_call(call),
+            Expr::Tag { arguments, .. } | Expr::Struct(arguments) => {
+                // the new value holds on to the arguments
+                self.mark_all_owned(arguments);
+            }
+            Expr::Array { elems, .. } => {
+                for element in elems.iter() {
+                    if let ListLiteralElement::Symbol(symbol) = element {
+                        self.mark_owned(*symbol);
+                    }
+                }
+            }
+            Expr::ErasedMake {
+                value: Some(value), ..
+            } => self.mark_owned(*value),
+            Expr::Alloca {
+                initializer: Some(initializer),
+                ..
+            } => self.mark_owned(*initializer),
+            _ => { /* only reads from its arguments */ }
         }
     }
 
-    fn inspect_call(&mut self, call: &'a Call<'a>) {
+    fn inspect_call(&mut self, call: &Call<'a>) {
         let Call {
             call_type,
             arguments,
         } = call;
 
-        match call_type {
-            CallType::ByName { name: _, .. } => {
-                // TODO ownership should depend on the borrow signature of the called function
-                for argument in arguments.iter() {
-                    self.mark_owned(*argument)
+        match call_type.clone().replace_lowlevel_wrapper() {
+            CallType::ByName {
+                name,
+                ret_layout,
+                arg_layouts,
+                ..
+            } => {
+                let proc_layout = ProcLayout {
+                    arguments: arg_layouts,
+                    result: ret_layout,
+                    niche: name.niche(),
+                };
+
+                match self.borrow_signatures.get(&(name.name(), proc_layout)) {
+                    Some(borrow_signature) => {
+                        for (argument, ownership) in arguments.iter().zip(borrow_signature.iter()) {
+                            if ownership.is_owned() {
+                                self.mark_owned(*argument);
+                            }
+                        }
+                    }
+                    None => self.mark_all_owned(arguments),
                 }
             }
             CallType::LowLevel { op, .. } => {
                 // if the lowlevel must own the argument, mark it as owned
-                let borrow_signature = crate::inc_dec::lowlevel_borrow_signature(*op);
+                let borrow_signature = crate::inc_dec::lowlevel_borrow_signature(op);
 
                 for (argument, ownership) in arguments.iter().zip(borrow_signature) {
                     if ownership.is_owned() {
@@ -127,9 +309,7 @@ impl<'a> State<'a> {
                 }
             }
             CallType::ByPointer { .. } | CallType::Foreign { .. } | CallType::HigherOrder(_) => {
-                for argument in arguments.iter() {
-                    self.mark_owned(*argument)
-                }
+                self.mark_all_owned(arguments);
             }
         }
     }
@@ -0,0 +1,567 @@
+//! Common subexpression elimination.
+//!
+//! Pattern match compilation loads the same field out of the same structure over and over. When a
+//! binding computes the same value as an earlier binding that dominates it, the later binding is
+//! removed and its uses refer to the earlier binding instead.
+//!
+//! In the mono IR a binding dominates exactly its continuation, so the bindings that are in scope
+//! are the ones that are available.
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::MutMap;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+
+use crate::dead_code::lowlevel_has_no_side_effects;
+use crate::ir::{
+    BranchInfo, Call, CallType, Expr, HigherOrderLowLevel, ListLiteralElement, ModifyRc,
+    PassedFunction, Proc, ProcLayout, Stmt,
+};
+use crate::layout::{InLayout, TagIdIntType, UnionLayout};
+use crate::low_level::HigherOrder;
+
+pub fn eliminate_common_subexpressions<'a>(
+    arena: &'a Bump,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    for proc in procs.values_mut() {
+        let mut env = Env {
+            arena,
+            available: MutMap::default(),
+            introduced: vec![],
+            renaming: MutMap::default(),
+        };
+
+        let new_body = env.eliminate_stmt(&proc.body);
+        proc.body = new_body.clone();
+    }
+}
+
+/// The expressions that are worth sharing, with their symbols already renamed
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Key<'a> {
+    StructAtIndex(Symbol, u64, &'a [InLayout<'a>]),
+    UnionAtIndex(Symbol, TagIdIntType, UnionLayout<'a>, u64),
+    GetTagId(Symbol, UnionLayout<'a>),
+    LowLevel(LowLevel, &'a [Symbol], InLayout<'a>),
+}
+
+impl<'a> Key<'a> {
+    fn from_expr(expr: &Expr<'a>, layout: InLayout<'a>) -> Option<Self> {
+        match expr {
+            Expr::StructAtIndex {
+                index,
+                field_layouts,
+                structure,
+            } => Some(Key::StructAtIndex(*structure, *index, *field_layouts)),
+            Expr::UnionAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => Some(Key::UnionAtIndex(
+                *structure,
+                *tag_id,
+                *union_layout,
+                *index,
+            )),
+            Expr::GetTagId {
+                structure,
+                union_layout,
+            } => Some(Key::GetTagId(*structure, *union_layout)),
+            Expr::Call(Call {
+                call_type: CallType::LowLevel { op, .. },
+                arguments,
+            }) if lowlevel_has_no_side_effects(*op) => Some(Key::LowLevel(*op, *arguments, layout)),
+            _ => None,
+        }
+    }
+}
+
+struct Env<'a> {
+    arena: &'a Bump,
+    /// The symbol that holds the value of every available expression
+    available: MutMap<Key<'a>, Symbol>,
+    /// The keys added to `available`, in order, so they can be removed when leaving a scope
+    introduced: std::vec::Vec<Key<'a>>,
+    /// Removed bindings, and the binding that has the same value
+    renaming: MutMap<Symbol, Symbol>,
+}
+
+impl<'a> Env<'a> {
+    fn rename(&self, symbol: Symbol) -> Symbol {
+        self.renaming.get(&symbol).copied().unwrap_or(symbol)
+    }
+
+    fn rename_all(&self, symbols: &[Symbol]) -> &'a [Symbol] {
+        Vec::from_iter_in(symbols.iter().map(|s| self.rename(*s)), self.arena).into_bump_slice()
+    }
+
+    /// Eliminate in a nested scope. Expressions that become available in it are forgotten after.
+    fn eliminate_in_scope(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        let scope_start = self.introduced.len();
+
+        let stmt = self.eliminate_stmt(stmt);
+
+        for key in self.introduced.drain(scope_start..) {
+            self.available.remove(&key);
+        }
+
+        stmt
+    }
+
+    fn eliminate_stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        let arena = self.arena;
+
+        match stmt {
+            Stmt::Let(_, _, _, _) => {
+                // Collect all the subsequent let bindings,
+                // to prevent the stack from overflowing when there are many let bindings.
+                let mut triples = vec![];
+                let mut current_stmt = stmt;
+                while let Stmt::Let(binding, expr, layout, next_stmt) = current_stmt {
+                    let expr = self.rename_expr(expr);
+
+                    match Key::from_expr(&expr, *layout) {
+                        Some(key) => match self.available.get(&key) {
+                            Some(existing) => {
+                                self.renaming.insert(*binding, *existing);
+                            }
+                            None => {
+                                self.available.insert(key.clone(), *binding);
+                                self.introduced.push(key);
+                                triples.push((*binding, expr, *layout));
+                            }
+                        },
+                        None => triples.push((*binding, expr, *layout)),
+                    }
+
+                    current_stmt = next_stmt;
+                }
+
+                let cont = self.eliminate_stmt(current_stmt);
+
+                triples
+                    .into_iter()
+                    .rev()
+                    .fold(cont, |cont, (binding, expr, layout)| {
+                        arena.alloc(Stmt::Let(binding, expr, layout, cont))
+                    })
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        let info = self.rename_branch_info(info);
+                        (*label, info, self.eliminate_in_scope(branch).clone())
+                    }),
+                    arena,
+                )
+                .into_bump_slice();
+
+                let default_branch = (
+                    self.rename_branch_info(&default_branch.0),
+                    self.eliminate_in_scope(default_branch.1),
+                );
+
+                arena.alloc(Stmt::Switch {
+                    cond_symbol: self.rename(*cond_symbol),
+                    cond_layout: *cond_layout,
+                    branches,
+                    default_branch,
+                    ret_layout: *ret_layout,
+                })
+            }
+            Stmt::Ret(symbol) => arena.alloc(Stmt::Ret(self.rename(*symbol))),
+            Stmt::Refcounting(modify, cont) => {
+                let modify = match *modify {
+                    ModifyRc::Inc(symbol, n) => ModifyRc::Inc(self.rename(symbol), n),
+                    ModifyRc::Dec(symbol) => ModifyRc::Dec(self.rename(symbol)),
+                    ModifyRc::DecRef(symbol) => ModifyRc::DecRef(self.rename(symbol)),
+                    ModifyRc::Free(symbol) => ModifyRc::Free(self.rename(symbol)),
+                };
+
+                let cont = self.eliminate_stmt(cont);
+                arena.alloc(Stmt::Refcounting(modify, cont))
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::Expect {
+                condition: self.rename(*condition),
+                region: *region,
+                lookups: self.rename_all(lookups),
+                variables: *variables,
+                remainder: self.eliminate_stmt(remainder),
+            }),
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::ExpectFx {
+                condition: self.rename(*condition),
+                region: *region,
+                lookups: self.rename_all(lookups),
+                variables: *variables,
+                remainder: self.eliminate_stmt(remainder),
+            }),
+            Stmt::Dbg {
+                source_location,
+                source,
+                symbol,
+                variable,
+                remainder,
+            } => arena.alloc(Stmt::Dbg {
+                source_location: *source_location,
+                source: *source,
+                symbol: self.rename(*symbol),
+                variable: *variable,
+                remainder: self.eliminate_stmt(remainder),
+            }),
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                // the body is not dominated by the remainder, nor the other way around
+                let body = self.eliminate_in_scope(body);
+                let remainder = self.eliminate_in_scope(remainder);
+
+                arena.alloc(Stmt::Join {
+                    id: *id,
+                    parameters: *parameters,
+                    body,
+                    remainder,
+                })
+            }
+            Stmt::Jump(id, arguments) => arena.alloc(Stmt::Jump(*id, self.rename_all(arguments))),
+            Stmt::Crash(symbol, tag) => arena.alloc(Stmt::Crash(self.rename(*symbol), *tag)),
+        }
+    }
+
+    fn rename_branch_info(&self, info: &BranchInfo<'a>) -> BranchInfo<'a> {
+        match info {
+            BranchInfo::None => BranchInfo::None,
+            BranchInfo::Constructor {
+                scrutinee,
+                layout,
+                tag_id,
+            } => BranchInfo::Constructor {
+                scrutinee: self.rename(*scrutinee),
+                layout: *layout,
+                tag_id: *tag_id,
+            },
+            BranchInfo::List { scrutinee, len } => BranchInfo::List {
+                scrutinee: self.rename(*scrutinee),
+                len: *len,
+            },
+            BranchInfo::Unique { scrutinee, unique } => BranchInfo::Unique {
+                scrutinee: self.rename(*scrutinee),
+                unique: *unique,
+            },
+        }
+    }
+
+    fn rename_expr(&self, expr: &Expr<'a>) -> Expr<'a> {
+        match expr {
+            Expr::Literal(_)
+            | Expr::NullPointer
+            | Expr::EmptyArray
+            | Expr::FunctionPointer { .. }
+            | Expr::RuntimeErrorFunction(_) => expr.clone(),
+            Expr::Call(call) => Expr::Call(self.rename_call(call)),
+            Expr::Tag {
+                tag_layout,
+                tag_id,
+                arguments,
+                reuse,
+            } => Expr::Tag {
+                tag_layout: *tag_layout,
+                tag_id: *tag_id,
+                arguments: self.rename_all(arguments),
+                reuse: reuse.map(|mut token| {
+                    token.symbol = self.rename(token.symbol);
+                    token
+                }),
+            },
+            Expr::Struct(arguments) => Expr::Struct(self.rename_all(arguments)),
+            Expr::StructAtIndex {
+                index,
+                field_layouts,
+                structure,
+            } => Expr::StructAtIndex {
+                index: *index,
+                field_layouts: *field_layouts,
+                structure: self.rename(*structure),
+            },
+            Expr::GetTagId {
+                structure,
+                union_layout,
+            } => Expr::GetTagId {
+                structure: self.rename(*structure),
+                union_layout: *union_layout,
+            },
+            Expr::UnionAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => Expr::UnionAtIndex {
+                structure: self.rename(*structure),
+                tag_id: *tag_id,
+                union_layout: *union_layout,
+                index: *index,
+            },
+            Expr::GetElementPointer {
+                structure,
+                union_layout,
+                indices,
+            } => Expr::GetElementPointer {
+                structure: self.rename(*structure),
+                union_layout: *union_layout,
+                indices: *indices,
+            },
+            Expr::Array { elem_layout, elems } => Expr::Array {
+                elem_layout: *elem_layout,
+                elems: Vec::from_iter_in(
+                    elems.iter().map(|element| match element {
+                        ListLiteralElement::Literal(_) => *element,
+                        ListLiteralElement::Symbol(symbol) => {
+                            ListLiteralElement::Symbol(self.rename(*symbol))
+                        }
+                    }),
+                    self.arena,
+                )
+                .into_bump_slice(),
+            },
+            Expr::ErasedMake { value, callee } => Expr::ErasedMake {
+                value: value.map(|value| self.rename(value)),
+                callee: self.rename(*callee),
+            },
+            Expr::ErasedLoad { symbol, field } => Expr::ErasedLoad {
+                symbol: self.rename(*symbol),
+                field: *field,
+            },
+            Expr::Alloca {
+                element_layout,
+                initializer,
+            } => Expr::Alloca {
+                element_layout: *element_layout,
+                initializer: initializer.map(|initializer| self.rename(initializer)),
+            },
+            Expr::Reset {
+                symbol,
+                update_mode,
+            } => Expr::Reset {
+                symbol: self.rename(*symbol),
+                update_mode: *update_mode,
+            },
+            Expr::ResetRef {
+                symbol,
+                update_mode,
+            } => Expr::ResetRef {
+                symbol: self.rename(*symbol),
+                update_mode: *update_mode,
+            },
+        }
+    }
+
+    fn rename_call(&self, call: &Call<'a>) -> Call<'a> {
+        let call_type = match &call.call_type {
+            CallType::ByPointer {
+                pointer,
+                ret_layout,
+                arg_layouts,
+            } => CallType::ByPointer {
+                pointer: self.rename(*pointer),
+                ret_layout: *ret_layout,
+                arg_layouts: *arg_layouts,
+            },
+            CallType::HigherOrder(higher_order) => {
+                let op = match higher_order.op {
+                    HigherOrder::ListMap { xs } => HigherOrder::ListMap {
+                        xs: self.rename(xs),
+                    },
+                    HigherOrder::ListMap2 { xs, ys } => HigherOrder::ListMap2 {
+                        xs: self.rename(xs),
+                        ys: self.rename(ys),
+                    },
+                    HigherOrder::ListMap3 { xs, ys, zs } => HigherOrder::ListMap3 {
+                        xs: self.rename(xs),
+                        ys: self.rename(ys),
+                        zs: self.rename(zs),
+                    },
+                    HigherOrder::ListMap4 { xs, ys, zs, ws } => HigherOrder::ListMap4 {
+                        xs: self.rename(xs),
+                        ys: self.rename(ys),
+                        zs: self.rename(zs),
+                        ws: self.rename(ws),
+                    },
+                    HigherOrder::ListSortWith { xs } => HigherOrder::ListSortWith {
+                        xs: self.rename(xs),
+                    },
+                };
+
+                let passed_function = PassedFunction {
+                    captured_environment: self
+                        .rename(higher_order.passed_function.captured_environment),
+                    ..higher_order.passed_function
+                };
+
+                CallType::HigherOrder(self.arena.alloc(HigherOrderLowLevel {
+                    op,
+                    passed_function,
+                    ..**higher_order
+                }))
+            }
+            CallType::ByName { .. } | CallType::Foreign { .. } | CallType::LowLevel { .. } => {
+                call.call_type.clone()
+            }
+        };
+
+        Call {
+            call_type,
+            arguments: self.rename_all(call.arguments),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::debug::testing::{assert_procs, parse};
+    use crate::layout::STLayoutInterner;
+    use indoc::indoc;
+    use roc_module::symbol::IdentIds;
+    use roc_target::Target;
+
+    #[test]
+    fn share_struct_field() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (pair : {I64, I64}) -> {I64, I64}:
+                    let a : I64 = StructAtIndex 0 pair;
+                    let b : I64 = StructAtIndex 0 pair;
+                    let c : {I64, I64} = Struct {a, b};
+                    ret c;
+                "#
+            ),
+        );
+
+        eliminate_common_subexpressions(&arena, &mut procs);
+
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (pair : {I64, I64}) -> {I64, I64}:
+                    let a : I64 = StructAtIndex 0 pair;
+                    let c : {I64, I64} = Struct {a, a};
+                    ret c;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn share_across_join_point() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (pair : {I64, I64}) -> I64:
+                    let a : I64 = StructAtIndex 0 pair;
+                    joinpoint j (x : I64):
+                        let b : I64 = StructAtIndex 0 pair;
+                        let c : I64 = StructAtIndex 1 pair;
+                        let s : I64 = lowlevel NumAddWrap b c;
+                        ret s;
+                    in
+                    let d : I64 = StructAtIndex 1 pair;
+                    let e : I64 = StructAtIndex 0 pair;
+                    let t : I64 = lowlevel NumAddWrap d e;
+                    jump j t;
+                "#
+            ),
+        );
+
+        eliminate_common_subexpressions(&arena, &mut procs);
+
+        // `a` dominates both the body and the remainder of `j`, but `c` is only available in the
+        // body, so `d` has to compute the field again
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (pair : {I64, I64}) -> I64:
+                    let a : I64 = StructAtIndex 0 pair;
+                    joinpoint j (x : I64):
+                        let c : I64 = StructAtIndex 1 pair;
+                        let s : I64 = lowlevel NumAddWrap a c;
+                        ret s;
+                    in
+                    let d : I64 = StructAtIndex 1 pair;
+                    let t : I64 = lowlevel NumAddWrap d a;
+                    jump j t;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn keep_field_of_other_branch() {
+        let source = indoc!(
+            r#"
+            procedure Test.main (pair : {I64, I64}, flag : Int1) -> I64:
+                if flag then
+                    let a : I64 = StructAtIndex 0 pair;
+                    ret a;
+                else
+                    let b : I64 = StructAtIndex 0 pair;
+                    ret b;
+            "#
+        );
+
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(&arena, &mut interner, &mut ident_ids, source);
+
+        eliminate_common_subexpressions(&arena, &mut procs);
+
+        assert_procs(&arena, &mut interner, &mut ident_ids, &procs, source);
+    }
+}
@@ -0,0 +1,568 @@
+//! Constant folding and propagation.
+//!
+//! Lowlevel operations on literals are evaluated at compile time, constants are propagated into
+//! the parameters of join points that are always jumped to with the same constant, and switches on
+//! a constant are replaced by the branch that would be taken.
+//!
+//! Bindings are never removed here, even when they are no longer used after folding.
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_builtins::bitcode::IntWidth;
+use roc_collections::MutMap;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+
+use crate::ir::{Call, CallType, Expr, JoinPointId, Literal, Proc, ProcLayout, Stmt};
+use crate::layout::{Builtin, InLayout, LayoutInterner, LayoutRepr};
+
+pub fn fold_constants<'a>(
+    arena: &'a Bump,
+    interner: &impl LayoutInterner<'a>,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    for proc in procs.values_mut() {
+        let mut env = Env {
+            arena,
+            interner,
+            constants: MutMap::default(),
+            jump_constants: MutMap::default(),
+        };
+
+        let new_body = env.fold_stmt(&proc.body);
+        proc.body = new_body.clone();
+    }
+}
+
+struct Env<'a, 'i, I> {
+    arena: &'a Bump,
+    interner: &'i I,
+    /// Symbols that are known to hold a literal value
+    constants: MutMap<Symbol, Literal<'a>>,
+    /// For every join point, the arguments that all jumps seen so far agree on
+    jump_constants: MutMap<JoinPointId, std::vec::Vec<Option<Literal<'a>>>>,
+}
+
+impl<'a, 'i, I: LayoutInterner<'a>> Env<'a, 'i, I> {
+    fn fold_stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        let arena = self.arena;
+
+        match stmt {
+            Stmt::Let(_, _, _, _) => {
+                // Collect all the subsequent let bindings,
+                // to prevent the stack from overflowing when there are many let bindings.
+                let mut triples = vec![];
+                let mut current_stmt = stmt;
+                while let Stmt::Let(binding, expr, layout, next_stmt) = current_stmt {
+                    let expr = match self.fold_expr(expr, *layout) {
+                        Some(literal) => Expr::Literal(literal),
+                        None => expr.clone(),
+                    };
+
+                    if let Expr::Literal(literal) = expr {
+                        self.constants.insert(*binding, literal);
+                    }
+
+                    triples.push((*binding, expr, *layout));
+                    current_stmt = next_stmt;
+                }
+
+                let cont = self.fold_stmt(current_stmt);
+
+                triples
+                    .into_iter()
+                    .rev()
+                    .fold(cont, |cont, (binding, expr, layout)| {
+                        arena.alloc(Stmt::Let(binding, expr, layout, cont))
+                    })
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                if let Some(value) = self.switch_value(*cond_symbol) {
+                    // only the branch that is taken remains
+                    let taken = branches
+                        .iter()
+                        .find(|(label, _, _)| *label == value)
+                        .map(|(_, _, branch)| branch)
+                        .unwrap_or(default_branch.1);
+
+                    return self.fold_stmt(taken);
+                }
+
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (*label, info.clone(), self.fold_stmt(branch).clone())
+                    }),
+                    arena,
+                )
+                .into_bump_slice();
+
+                let default_branch = (default_branch.0.clone(), self.fold_stmt(default_branch.1));
+
+                arena.alloc(Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches,
+                    default_branch,
+                    ret_layout: *ret_layout,
+                })
+            }
+            Stmt::Refcounting(modify, cont) => {
+                let cont = self.fold_stmt(cont);
+                arena.alloc(Stmt::Refcounting(*modify, cont))
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.fold_stmt(remainder),
+            }),
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.fold_stmt(remainder),
+            }),
+            Stmt::Dbg {
+                source_location,
+                source,
+                symbol,
+                variable,
+                remainder,
+            } => arena.alloc(Stmt::Dbg {
+                source_location: *source_location,
+                source: *source,
+                symbol: *symbol,
+                variable: *variable,
+                remainder: self.fold_stmt(remainder),
+            }),
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                // All jumps to a join point that isn't a loop are in its remainder,
+                // so once that is folded we know which parameters are constant.
+                let remainder = self.fold_stmt(remainder);
+
+                if !jumps_to(body, *id) {
+                    if let Some(arguments) = self.jump_constants.remove(id) {
+                        for (param, argument) in parameters.iter().zip(arguments) {
+                            if let Some(literal) = argument {
+                                self.constants.insert(param.symbol, literal);
+                            }
+                        }
+                    }
+                }
+
+                let body = self.fold_stmt(body);
+
+                arena.alloc(Stmt::Join {
+                    id: *id,
+                    parameters: *parameters,
+                    body,
+                    remainder,
+                })
+            }
+            Stmt::Jump(id, arguments) => {
+                let constants = arguments
+                    .iter()
+                    .map(|argument| self.constants.get(argument).copied());
+
+                match self.jump_constants.get_mut(id) {
+                    None => {
+                        self.jump_constants.insert(*id, constants.collect());
+                    }
+                    Some(known) => {
+                        for (known, constant) in known.iter_mut().zip(constants) {
+                            if *known != constant {
+                                *known = None;
+                            }
+                        }
+                    }
+                }
+
+                arena.alloc(stmt.clone())
+            }
+            Stmt::Ret(_) | Stmt::Crash(_, _) => arena.alloc(stmt.clone()),
+        }
+    }
+
+    /// The value a switch on this symbol would branch on, if it is a known constant
+    fn switch_value(&self, symbol: Symbol) -> Option<u64> {
+        match self.constants.get(&symbol)? {
+            Literal::Int(bytes) => Some(i128::from_ne_bytes(*bytes) as u64),
+            Literal::Bool(bool) => Some(*bool as u64),
+            Literal::Byte(byte) => Some(*byte as u64),
+            _ => None,
+        }
+    }
+
+    fn fold_expr(&self, expr: &Expr<'a>, layout: InLayout<'a>) -> Option<Literal<'a>> {
+        match expr {
+            Expr::Call(Call {
+                call_type,
+                arguments,
+            }) => match call_type.clone().replace_lowlevel_wrapper() {
+                CallType::LowLevel { op, .. } => {
+                    let arguments: Option<std::vec::Vec<_>> = arguments
+                        .iter()
+                        .map(|argument| self.constants.get(argument).copied())
+                        .collect();
+
+                    self.fold_lowlevel(op, &arguments?, layout)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn fold_lowlevel(
+        &self,
+        op: LowLevel,
+        arguments: &[Literal<'a>],
+        layout: InLayout<'a>,
+    ) -> Option<Literal<'a>> {
+        use LowLevel::*;
+
+        match (op, arguments) {
+            (NumAdd | NumSub | NumMul, [Literal::Int(a), Literal::Int(b)]) => {
+                let width = self.int_width(layout)?;
+                let (a, b) = (i128::from_ne_bytes(*a), i128::from_ne_bytes(*b));
+
+                // overflow is a runtime error, which we must not fold away
+                let result = int_op(op, a, b)?;
+                let (min, max) = int_range(width)?;
+
+                (min..=max)
+                    .contains(&result)
+                    .then(|| Literal::Int(result.to_ne_bytes()))
+            }
+            (NumAddWrap | NumSubWrap | NumMulWrap, [Literal::Int(a), Literal::Int(b)]) => {
+                let width = self.int_width(layout)?;
+                let (a, b) = (i128::from_ne_bytes(*a), i128::from_ne_bytes(*b));
+
+                let result = int_op(op, a, b)?;
+                let (min, max) = int_range(width)?;
+                let wrapped = (result - min).rem_euclid(max - min + 1) + min;
+
+                Some(Literal::Int(wrapped.to_ne_bytes()))
+            }
+            (NumLt | NumLte | NumGt | NumGte, [Literal::Int(a), Literal::Int(b)]) => {
+                let (a, b) = (i128::from_ne_bytes(*a), i128::from_ne_bytes(*b));
+
+                let result = match op {
+                    NumLt => a < b,
+                    NumLte => a <= b,
+                    NumGt => a > b,
+                    NumGte => a >= b,
+                    _ => unreachable!(),
+                };
+
+                Some(Literal::Bool(result))
+            }
+            (Eq | NotEq, [a, b]) => {
+                let equal = match (a, b) {
+                    (Literal::Int(a), Literal::Int(b)) => a == b,
+                    (Literal::Bool(a), Literal::Bool(b)) => a == b,
+                    (Literal::Byte(a), Literal::Byte(b)) => a == b,
+                    (Literal::Str(a), Literal::Str(b)) => a == b,
+                    _ => return None,
+                };
+
+                Some(Literal::Bool(equal == matches!(op, Eq)))
+            }
+            (And, [Literal::Bool(a), Literal::Bool(b)]) => Some(Literal::Bool(*a && *b)),
+            (Or, [Literal::Bool(a), Literal::Bool(b)]) => Some(Literal::Bool(*a || *b)),
+            (Not, [Literal::Bool(a)]) => Some(Literal::Bool(!*a)),
+            _ => None,
+        }
+    }
+
+    fn int_width(&self, layout: InLayout<'a>) -> Option<IntWidth> {
+        match self.interner.get_repr(layout) {
+            LayoutRepr::Builtin(Builtin::Int(width)) => Some(width),
+            _ => None,
+        }
+    }
+}
+
+fn int_op(op: LowLevel, a: i128, b: i128) -> Option<i128> {
+    use LowLevel::*;
+
+    match op {
+        NumAdd | NumAddWrap => a.checked_add(b),
+        NumSub | NumSubWrap => a.checked_sub(b),
+        NumMul | NumMulWrap => a.checked_mul(b),
+        _ => None,
+    }
+}
+
+/// The values of an integer width, if they all fit in an `i128` with room to spare.
+/// 128-bit integers are not folded.
+fn int_range(width: IntWidth) -> Option<(i128, i128)> {
+    use IntWidth::*;
+
+    let range = match width {
+        U8 => (u8::MIN as i128, u8::MAX as i128),
+        U16 => (u16::MIN as i128, u16::MAX as i128),
+        U32 => (u32::MIN as i128, u32::MAX as i128),
+        U64 => (u64::MIN as i128, u64::MAX as i128),
+        I8 => (i8::MIN as i128, i8::MAX as i128),
+        I16 => (i16::MIN as i128, i16::MAX as i128),
+        I32 => (i32::MIN as i128, i32::MAX as i128),
+        I64 => (i64::MIN as i128, i64::MAX as i128),
+        U128 | I128 => return None,
+    };
+
+    Some(range)
+}
+
+fn jumps_to(stmt: &Stmt<'_>, id: JoinPointId) -> bool {
+    let mut stack = vec![stmt];
+
+    while let Some(stmt) = stack.pop() {
+        match stmt {
+            Stmt::Let(_, _, _, cont) | Stmt::Refcounting(_, cont) => stack.push(cont),
+            Stmt::Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                stack.push(default_branch.1);
+            }
+            Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. }
+            | Stmt::Dbg { remainder, .. } => stack.push(remainder),
+            Stmt::Join {
+                body, remainder, ..
+            } => {
+                stack.push(body);
+                stack.push(remainder);
+            }
+            Stmt::Jump(target, _) => {
+                if *target == id {
+                    return true;
+                }
+            }
+            Stmt::Ret(_) | Stmt::Crash(_, _) => {}
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::debug::testing::{assert_procs, parse};
+    use crate::layout::STLayoutInterner;
+    use indoc::indoc;
+    use roc_module::symbol::IdentIds;
+    use roc_target::Target;
+
+    #[test]
+    fn fold_constants_in_switch() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main () -> I64:
+                    let a : I64 = 2;
+                    let b : I64 = 3;
+                    let c : I64 = lowlevel NumMul a b;
+                    let d : Int1 = lowlevel NumGt c a;
+                    if d then
+                        ret c;
+                    else
+                        ret a;
+                "#
+            ),
+        );
+
+        fold_constants(&arena, &interner, &mut procs);
+
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main () -> I64:
+                    let a : I64 = 2;
+                    let b : I64 = 3;
+                    let c : I64 = 6;
+                    let d : Int1 = true;
+                    ret c;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn fold_constants_in_switch_on_parameter() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (n : U8) -> I64:
+                    let a : I64 = 2;
+                    let b : I64 = 3;
+                    switch n:
+                        case 0:
+                            let c : I64 = lowlevel NumAdd a b;
+                            ret c;
+                        case 1:
+                            ret a;
+                        default:
+                            let d : I64 = lowlevel NumMul a b;
+                            ret d;
+                "#
+            ),
+        );
+
+        fold_constants(&arena, &interner, &mut procs);
+
+        // the value of `n` is not known, so every branch stays
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (n : U8) -> I64:
+                    let a : I64 = 2;
+                    let b : I64 = 3;
+                    switch n:
+                        case 0:
+                            let c : I64 = 5;
+                            ret c;
+                        case 1:
+                            ret a;
+                        default:
+                            let d : I64 = 6;
+                            ret d;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn propagate_into_join_point() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (flag : Int1, x : I64) -> I64:
+                    joinpoint done (n : I64, k : I64):
+                        let m : I64 = lowlevel NumMul n n;
+                        let l : I64 = lowlevel NumAdd k n;
+                        let r : I64 = lowlevel NumAdd m l;
+                        ret r;
+                    in
+                    if flag then
+                        let a : I64 = 2;
+                        jump done a x;
+                    else
+                        let b : I64 = 2;
+                        let c : I64 = 3;
+                        jump done b c;
+                "#
+            ),
+        );
+
+        fold_constants(&arena, &interner, &mut procs);
+
+        // both jumps pass 2 for `n`, but `k` is `x` on one of them
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (flag : Int1, x : I64) -> I64:
+                    joinpoint done (n : I64, k : I64):
+                        let m : I64 = 4;
+                        let l : I64 = lowlevel NumAdd k n;
+                        let r : I64 = lowlevel NumAdd m l;
+                        ret r;
+                    in
+                    if flag then
+                        let a : I64 = 2;
+                        jump done a x;
+                    else
+                        let b : I64 = 2;
+                        let c : I64 = 3;
+                        jump done b c;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn keep_overflowing_addition() {
+        let source = indoc!(
+            r#"
+            procedure Test.main () -> I8:
+                let a : I8 = 127;
+                let b : I8 = 1;
+                let c : I8 = lowlevel NumAdd a b;
+                ret c;
+            "#
+        );
+
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(&arena, &mut interner, &mut ident_ids, source);
+
+        fold_constants(&arena, &interner, &mut procs);
+
+        // the overflow must still crash at runtime
+        assert_procs(&arena, &mut interner, &mut ident_ids, &procs, source);
+    }
+}
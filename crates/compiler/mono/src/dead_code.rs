@@ -0,0 +1,670 @@
+//! Dead code elimination.
+//!
+//! Removes let bindings that are never used and whose value has no side effects, switch branches
+//! that can never be taken, and join points that are never jumped to.
+//!
+//! Uses are counted up front, and a binding is only inspected after its continuation has been
+//! cleaned up, so removing one binding can make the bindings it used dead too.
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::{MutMap, MutSet};
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+
+use crate::ir::{
+    BranchInfo, Call, CallType, Expr, JoinPointId, ListLiteralElement, ModifyRc, Proc, ProcLayout,
+    Stmt,
+};
+use crate::low_level::HigherOrder;
+
+pub fn eliminate_dead_code<'a>(
+    arena: &'a Bump,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    for proc in procs.values_mut() {
+        let mut env = Env {
+            arena,
+            uses: MutMap::default(),
+            jumps: MutMap::default(),
+            enclosing_bodies: vec![],
+        };
+
+        env.count_stmt(&proc.body, Count::Add);
+
+        let new_body = env.eliminate_stmt(&proc.body);
+        proc.body = new_body.clone();
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Count {
+    Add,
+    Remove,
+}
+
+struct Env<'a> {
+    arena: &'a Bump,
+    /// How often every symbol is used
+    uses: MutMap<Symbol, usize>,
+    /// How often every join point is jumped to from outside of its own body
+    jumps: MutMap<JoinPointId, usize>,
+    /// The join points whose body we are currently in
+    enclosing_bodies: std::vec::Vec<JoinPointId>,
+}
+
+impl<'a> Env<'a> {
+    fn count_symbol(&mut self, symbol: Symbol, count: Count) {
+        let uses = self.uses.entry(symbol).or_default();
+
+        match count {
+            Count::Add => *uses += 1,
+            Count::Remove => *uses -= 1,
+        }
+    }
+
+    fn count_symbols(&mut self, symbols: &[Symbol], count: Count) {
+        for symbol in symbols.iter() {
+            self.count_symbol(*symbol, count);
+        }
+    }
+
+    fn count_jump(&mut self, id: JoinPointId, count: Count) {
+        // jumps from within the body of the join point itself don't keep it alive
+        if self.enclosing_bodies.contains(&id) {
+            return;
+        }
+
+        let jumps = self.jumps.entry(id).or_default();
+
+        match count {
+            Count::Add => *jumps += 1,
+            Count::Remove => *jumps -= 1,
+        }
+    }
+
+    fn count_stmt(&mut self, stmt: &Stmt<'a>, count: Count) {
+        match stmt {
+            Stmt::Let(_, _, _, _) => {
+                // Walk the chain of let bindings in a loop,
+                // to prevent the stack from overflowing when there are many of them.
+                let mut current_stmt = stmt;
+                while let Stmt::Let(_, expr, _, next_stmt) = current_stmt {
+                    self.count_expr(expr, count);
+                    current_stmt = next_stmt;
+                }
+
+                self.count_stmt(current_stmt, count);
+            }
+            Stmt::Switch {
+                cond_symbol,
+                branches,
+                default_branch,
+                ..
+            } => {
+                self.count_symbol(*cond_symbol, count);
+
+                for (_, info, branch) in branches.iter() {
+                    self.count_branch_info(info, count);
+                    self.count_stmt(branch, count);
+                }
+
+                self.count_branch_info(&default_branch.0, count);
+                self.count_stmt(default_branch.1, count);
+            }
+            Stmt::Ret(symbol) | Stmt::Crash(symbol, _) => self.count_symbol(*symbol, count),
+            Stmt::Refcounting(modify, cont) => {
+                let symbol = match modify {
+                    ModifyRc::Inc(symbol, _)
+                    | ModifyRc::Dec(symbol)
+                    | ModifyRc::DecRef(symbol)
+                    | ModifyRc::Free(symbol) => *symbol,
+                };
+
+                self.count_symbol(symbol, count);
+                self.count_stmt(cont, count);
+            }
+            Stmt::Expect {
+                condition,
+                lookups,
+                remainder,
+                ..
+            }
+            | Stmt::ExpectFx {
+                condition,
+                lookups,
+                remainder,
+                ..
+            } => {
+                self.count_symbol(*condition, count);
+                self.count_symbols(lookups, count);
+                self.count_stmt(remainder, count);
+            }
+            Stmt::Dbg {
+                symbol, remainder, ..
+            } => {
+                self.count_symbol(*symbol, count);
+                self.count_stmt(remainder, count);
+            }
+            Stmt::Join {
+                id,
+                body,
+                remainder,
+                ..
+            } => {
+                self.count_stmt(remainder, count);
+
+                self.enclosing_bodies.push(*id);
+                self.count_stmt(body, count);
+                self.enclosing_bodies.pop();
+            }
+            Stmt::Jump(id, arguments) => {
+                self.count_symbols(arguments, count);
+                self.count_jump(*id, count);
+            }
+        }
+    }
+
+    fn count_branch_info(&mut self, info: &BranchInfo<'a>, count: Count) {
+        match info {
+            BranchInfo::None => {}
+            BranchInfo::Constructor { scrutinee, .. }
+            | BranchInfo::List { scrutinee, .. }
+            | BranchInfo::Unique { scrutinee, .. } => self.count_symbol(*scrutinee, count),
+        }
+    }
+
+    fn count_expr(&mut self, expr: &Expr<'a>, count: Count) {
+        match expr {
+            Expr::Literal(_)
+            | Expr::NullPointer
+            | Expr::EmptyArray
+            | Expr::FunctionPointer { .. }
+            | Expr::RuntimeErrorFunction(_) => {}
+            Expr::Call(call) => self.count_call(call, count),
+            Expr::Tag { arguments, .. } | Expr::Struct(arguments) => {
+                self.count_symbols(arguments, count)
+            }
+            Expr::StructAtIndex { structure, .. }
+            | Expr::GetTagId { structure, .. }
+            | Expr::UnionAtIndex { structure, .. }
+            | Expr::GetElementPointer { structure, .. } => self.count_symbol(*structure, count),
+            Expr::Array { elems, .. } => {
+                for element in elems.iter() {
+                    if let ListLiteralElement::Symbol(symbol) = element {
+                        self.count_symbol(*symbol, count);
+                    }
+                }
+            }
+            Expr::ErasedMake { value, callee } => {
+                if let Some(value) = value {
+                    self.count_symbol(*value, count);
+                }
+                self.count_symbol(*callee, count);
+            }
+            Expr::ErasedLoad { symbol, .. }
+            | Expr::Reset { symbol, .. }
+            | Expr::ResetRef { symbol, .. } => self.count_symbol(*symbol, count),
+            Expr::Alloca { initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.count_symbol(*initializer, count);
+                }
+            }
+        }
+    }
+
+    fn count_call(&mut self, call: &Call<'a>, count: Count) {
+        self.count_symbols(call.arguments, count);
+
+        match &call.call_type {
+            CallType::ByPointer { pointer, .. } => self.count_symbol(*pointer, count),
+            CallType::HigherOrder(higher_order) => {
+                match higher_order.op {
+                    HigherOrder::ListMap { xs } | HigherOrder::ListSortWith { xs } => {
+                        self.count_symbol(xs, count);
+                    }
+                    HigherOrder::ListMap2 { xs, ys } => {
+                        self.count_symbols(&[xs, ys], count);
+                    }
+                    HigherOrder::ListMap3 { xs, ys, zs } => {
+                        self.count_symbols(&[xs, ys, zs], count);
+                    }
+                    HigherOrder::ListMap4 { xs, ys, zs, ws } => {
+                        self.count_symbols(&[xs, ys, zs, ws], count);
+                    }
+                }
+
+                let captured_environment = higher_order.passed_function.captured_environment;
+                self.count_symbol(captured_environment, count);
+            }
+            CallType::ByName { .. } | CallType::Foreign { .. } | CallType::LowLevel { .. } => {}
+        }
+    }
+
+    fn is_used(&self, symbol: Symbol) -> bool {
+        self.uses.get(&symbol).copied().unwrap_or(0) > 0
+    }
+
+    fn eliminate_stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        let arena = self.arena;
+
+        match stmt {
+            Stmt::Let(_, _, _, _) => {
+                // Collect all the subsequent let bindings,
+                // to prevent the stack from overflowing when there are many let bindings.
+                let mut triples = vec![];
+                let mut current_stmt = stmt;
+                while let Stmt::Let(binding, expr, layout, next_stmt) = current_stmt {
+                    triples.push((binding, expr, layout));
+                    current_stmt = next_stmt;
+                }
+
+                let cont = self.eliminate_stmt(current_stmt);
+
+                triples
+                    .into_iter()
+                    .rev()
+                    .fold(cont, |cont, (binding, expr, layout)| {
+                        if !self.is_used(*binding) && has_no_side_effects(expr) {
+                            self.count_expr(expr, Count::Remove);
+                            cont
+                        } else {
+                            arena.alloc(Stmt::Let(*binding, expr.clone(), *layout, cont))
+                        }
+                    })
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                // only the first branch with a given label can ever be taken
+                let mut labels = MutSet::default();
+                let mut reachable = std::vec::Vec::with_capacity(branches.len());
+
+                for branch in branches.iter() {
+                    if labels.insert(branch.0) {
+                        reachable.push(branch);
+                    } else {
+                        self.count_branch_info(&branch.1, Count::Remove);
+                        self.count_stmt(&branch.2, Count::Remove);
+                    }
+                }
+
+                if reachable.is_empty() {
+                    self.count_symbol(*cond_symbol, Count::Remove);
+                    self.count_branch_info(&default_branch.0, Count::Remove);
+
+                    return self.eliminate_stmt(default_branch.1);
+                }
+
+                let branches = Vec::from_iter_in(
+                    reachable.into_iter().map(|(label, info, branch)| {
+                        (*label, info.clone(), self.eliminate_stmt(branch).clone())
+                    }),
+                    arena,
+                )
+                .into_bump_slice();
+
+                let default_branch = (
+                    default_branch.0.clone(),
+                    self.eliminate_stmt(default_branch.1),
+                );
+
+                arena.alloc(Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches,
+                    default_branch,
+                    ret_layout: *ret_layout,
+                })
+            }
+            Stmt::Refcounting(modify, cont) => {
+                let cont = self.eliminate_stmt(cont);
+                arena.alloc(Stmt::Refcounting(*modify, cont))
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.eliminate_stmt(remainder),
+            }),
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.eliminate_stmt(remainder),
+            }),
+            Stmt::Dbg {
+                source_location,
+                source,
+                symbol,
+                variable,
+                remainder,
+            } => arena.alloc(Stmt::Dbg {
+                source_location: *source_location,
+                source: *source,
+                symbol: *symbol,
+                variable: *variable,
+                remainder: self.eliminate_stmt(remainder),
+            }),
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                // All jumps from outside of the body are in the remainder,
+                // so after cleaning that up we know whether the join point is still used.
+                let remainder = self.eliminate_stmt(remainder);
+
+                self.enclosing_bodies.push(*id);
+
+                let stmt = if self.jumps.get(id).copied().unwrap_or(0) == 0 {
+                    self.count_stmt(body, Count::Remove);
+                    remainder
+                } else {
+                    let body = self.eliminate_stmt(body);
+
+                    arena.alloc(Stmt::Join {
+                        id: *id,
+                        parameters: *parameters,
+                        body,
+                        remainder,
+                    })
+                };
+
+                self.enclosing_bodies.pop();
+
+                stmt
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => arena.alloc(stmt.clone()),
+        }
+    }
+}
+
+/// How often every symbol is used in the statement
+pub(crate) fn count_uses<'a>(arena: &'a Bump, stmt: &Stmt<'a>) -> MutMap<Symbol, usize> {
+    let mut env = Env {
+        arena,
+        uses: MutMap::default(),
+        jumps: MutMap::default(),
+        enclosing_bodies: vec![],
+    };
+
+    env.count_stmt(stmt, Count::Add);
+
+    env.uses
+}
+
+/// How often every symbol is used in the expression
+pub(crate) fn count_expr_uses<'a>(arena: &'a Bump, expr: &Expr<'a>) -> MutMap<Symbol, usize> {
+    let mut env = Env {
+        arena,
+        uses: MutMap::default(),
+        jumps: MutMap::default(),
+        enclosing_bodies: vec![],
+    };
+
+    env.count_expr(expr, Count::Add);
+
+    env.uses
+}
+
+/// The symbols that the statement uses, and the join points it jumps to.
+/// Backends use this to find the values that are still live at some point in a procedure.
+pub fn used_symbols_and_jumps<'a>(
+    arena: &'a Bump,
+    stmt: &Stmt<'a>,
+) -> (MutSet<Symbol>, MutSet<JoinPointId>) {
+    let mut env = Env {
+        arena,
+        uses: MutMap::default(),
+        jumps: MutMap::default(),
+        enclosing_bodies: vec![],
+    };
+
+    env.count_stmt(stmt, Count::Add);
+
+    (
+        env.uses.into_keys().collect(),
+        env.jumps.into_keys().collect(),
+    )
+}
+
+/// The symbols that the expression uses
+pub fn expr_used_symbols<'a>(arena: &'a Bump, expr: &Expr<'a>) -> MutSet<Symbol> {
+    count_expr_uses(arena, expr).into_keys().collect()
+}
+
+/// Whether the binding can be removed when its value is not used
+fn has_no_side_effects(expr: &Expr<'_>) -> bool {
+    match expr {
+        Expr::Call(call) => match call.call_type.clone().replace_lowlevel_wrapper() {
+            CallType::LowLevel { op, .. } => lowlevel_has_no_side_effects(op),
+            // procedures may crash, loop forever, or perform effects
+            CallType::ByName { .. }
+            | CallType::ByPointer { .. }
+            | CallType::Foreign { .. }
+            | CallType::HigherOrder(_) => false,
+        },
+        // these update the value in-place
+        Expr::Reset { .. } | Expr::ResetRef { .. } => false,
+        _ => true,
+    }
+}
+
+/// Lowlevels that neither crash nor write to memory. Anything not listed here is kept.
+pub(crate) fn lowlevel_has_no_side_effects(op: LowLevel) -> bool {
+    use LowLevel::*;
+
+    matches!(
+        op,
+        StrIsEmpty
+            | StrStartsWith
+            | StrEndsWith
+            | StrCountUtf8Bytes
+            | StrGetUnsafe
+            | ListLenUsize
+            | ListLenU64
+            | ListGetUnsafe
+            | ListGetCapacity
+            | NumAddWrap
+            | NumAddChecked
+            | NumAddSaturated
+            | NumSubWrap
+            | NumSubChecked
+            | NumSubSaturated
+            | NumMulWrap
+            | NumMulChecked
+            | NumMulSaturated
+            | NumGt
+            | NumGte
+            | NumLt
+            | NumLte
+            | NumCompare
+            | NumIsNan
+            | NumIsInfinite
+            | NumIsFinite
+            | NumBitwiseAnd
+            | NumBitwiseXor
+            | NumBitwiseOr
+            | NumIntCast
+            | NumToFloatCast
+            | NumToFrac
+            | NumToIntChecked
+            | NumToFloatChecked
+            | NumCountLeadingZeroBits
+            | NumCountTrailingZeroBits
+            | NumCountOneBits
+            | Eq
+            | NotEq
+            | And
+            | Or
+            | Not
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::debug::testing::{assert_procs, parse};
+    use crate::layout::STLayoutInterner;
+    use indoc::indoc;
+    use roc_module::symbol::IdentIds;
+    use roc_target::Target;
+
+    #[test]
+    fn remove_dead_code() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (x : I64) -> I64:
+                    let unused : I64 = lowlevel NumAddWrap x x;
+                    let pair : {I64, I64} = Struct {unused, x};
+                    joinpoint never (y : I64):
+                        ret y;
+                    in
+                    ret x;
+                "#
+            ),
+        );
+
+        eliminate_dead_code(&arena, &mut procs);
+
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (x : I64) -> I64:
+                    ret x;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn keep_side_effects() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.effect (a : I64) -> I64:
+                    ret a;
+
+                procedure Test.main (x : I64) -> I64:
+                    let called : I64 = CallByName Test.effect x;
+                    let overflowing : I64 = lowlevel NumAdd x x;
+                    let wrapped : I64 = lowlevel NumAddWrap x x;
+                    let zero : I64 = 0;
+                    let isZero : Int1 = lowlevel Eq x zero;
+                    if isZero then
+                        let message : Str = "zero";
+                        let unused : I64 = 1;
+                        crash message;
+                    else
+                        ret x;
+                "#
+            ),
+        );
+
+        eliminate_dead_code(&arena, &mut procs);
+
+        // the call and the addition that may overflow stay, even though nothing uses them
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.effect (a : I64) -> I64:
+                    ret a;
+
+                procedure Test.main (x : I64) -> I64:
+                    let called : I64 = CallByName Test.effect x;
+                    let overflowing : I64 = lowlevel NumAdd x x;
+                    let zero : I64 = 0;
+                    let isZero : Int1 = lowlevel Eq x zero;
+                    if isZero then
+                        let message : Str = "zero";
+                        crash message;
+                    else
+                        ret x;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn remove_loop_that_is_never_entered() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let mut procs = parse(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (x : I64) -> I64:
+                    let one : I64 = 1;
+                    joinpoint loop (n : I64):
+                        let next : I64 = lowlevel NumAddWrap n one;
+                        jump loop next;
+                    in
+                    ret x;
+                "#
+            ),
+        );
+
+        eliminate_dead_code(&arena, &mut procs);
+
+        // the only jump to `loop` is in its own body, and then `one` is unused too
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (x : I64) -> I64:
+                    ret x;
+                "#
+            ),
+        );
+    }
+}
@@ -1,5 +1,10 @@
 mod checker;
 mod report;
+mod text;
 
 pub use checker::{check_procs, Problem, Problems};
 pub use report::format_problems;
+pub use text::{parse_procs, print_procs, ParseError};
+
+#[cfg(test)]
+pub(crate) use text::testing;
@@ -0,0 +1,1532 @@
+//! A textual format for the [ir][crate::ir::Proc], so optimization passes can be tested on small
+//! hand-written programs.
+//!
+//! [print_procs] writes procedures in a stable form, and [parse_procs] reads that form back. The
+//! syntax follows the output of `Proc::to_pretty`, except that procedure and join point parameters
+//! are annotated with their layouts:
+//!
+//! ```text
+//! procedure Test.add (x : I64, y : I64) -> I64:
+//!     let sum : I64 = lowlevel NumAdd x y;
+//!     ret sum;
+//! ```
+//!
+//! All other layouts, like the argument layouts of a call or the layout a switch returns, follow
+//! from the layouts of the symbols involved. That includes the width of an integer literal,
+//! which is written without a suffix; the other literals have one, like `3u128` or `1.5f64`.
+//!
+//! Only a part of the IR can be written down: all symbols live in the home module, unions are
+//! non-recursive, and branch info, expects, `dbg`, and reuse are not supported. The only
+//! higher-order lowlevel is `List.map`, written `ListMap xs f env`, where `env` is the captured
+//! environment of `f`, or `{}` if it captures nothing.
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::MutMap;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+use roc_std::RocDec;
+use std::fmt;
+
+use crate::ir::{
+    symbol_to_doc_string, BranchInfo, Call, CallSpecId, CallType, CrashTag, Expr,
+    HigherOrderLowLevel, JoinPointId, ListLiteralElement, Literal, ModifyRc, Param, PassedFunction,
+    Proc, ProcLayout, SelfRecursive, Stmt, UpdateModeIds,
+};
+use crate::layout::{
+    Builtin, InLayout, LambdaName, Layout, LayoutInterner, LayoutRepr, Niche, UnionLayout,
+};
+use crate::low_level::HigherOrder;
+
+type Procs<'a> = MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>;
+
+/// Print procedures in the textual format, sorted by name.
+pub fn print_procs<'a, I>(
+    interner: &I,
+    home: ModuleId,
+    ident_ids: &IdentIds,
+    procs: &Procs<'a>,
+) -> String
+where
+    I: LayoutInterner<'a>,
+{
+    let mut printed: std::vec::Vec<_> = procs
+        .values()
+        .map(|proc| {
+            let mut printer = Printer {
+                interner,
+                home,
+                ident_ids,
+                out: String::new(),
+            };
+
+            printer.proc(proc);
+
+            (printer.symbol(proc.name.name()), printer.out)
+        })
+        .collect();
+
+    printed.sort();
+
+    let printed: std::vec::Vec<_> = printed.into_iter().map(|(_, proc)| proc).collect();
+
+    printed.join("\n")
+}
+
+/// Parse procedures in the textual format. Symbols are added to the `ident_ids` of `home`.
+pub fn parse_procs<'a, I>(
+    arena: &'a Bump,
+    interner: &mut I,
+    home: ModuleId,
+    ident_ids: &mut IdentIds,
+    source: &str,
+) -> Result<Procs<'a>, ParseError>
+where
+    I: LayoutInterner<'a>,
+{
+    let mut parser = Parser {
+        arena,
+        interner,
+        home,
+        ident_ids,
+        source,
+        tokens: tokenize(source)?,
+        position: 0,
+        layouts: MutMap::default(),
+        ret_layout: Layout::VOID,
+        update_mode_ids: UpdateModeIds::new(),
+        call_spec_id: CallSpecId::BACKEND_DUMMY,
+    };
+
+    let mut procs = MutMap::default();
+
+    while parser.peek().is_some() {
+        let (key, proc) = parser.proc()?;
+        procs.insert(key, proc);
+    }
+
+    Ok(procs)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(source: &str, offset: usize, message: impl Into<String>) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+struct Printer<'p, I> {
+    interner: &'p I,
+    home: ModuleId,
+    ident_ids: &'p IdentIds,
+    out: String,
+}
+
+impl<'a, 'p, I: LayoutInterner<'a>> Printer<'p, I> {
+    fn line(&mut self, indent: usize, text: &str) {
+        self.out.push_str(&" ".repeat(indent));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn proc(&mut self, proc: &Proc<'a>) {
+        let params = proc
+            .args
+            .iter()
+            .map(|(layout, symbol)| self.param(*symbol, *layout))
+            .collect::<std::vec::Vec<_>>()
+            .join(", ");
+
+        let header = format!(
+            "procedure {} ({}) -> {}:",
+            self.symbol(proc.name.name()),
+            params,
+            self.layout(proc.ret_layout)
+        );
+
+        self.line(0, &header);
+        self.stmt(&proc.body, 4);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt<'a>, indent: usize) {
+        let mut stmt = stmt;
+
+        // Walk the chain of statements in a loop,
+        // to prevent the stack from overflowing when there are many of them.
+        loop {
+            match stmt {
+                Stmt::Let(symbol, expr, layout, cont) => {
+                    let line = format!(
+                        "let {} : {} = {};",
+                        self.symbol(*symbol),
+                        self.layout(*layout),
+                        self.expr(expr)
+                    );
+
+                    self.line(indent, &line);
+                    stmt = cont;
+                }
+                Stmt::Refcounting(modify, cont) => {
+                    let line = match modify {
+                        ModifyRc::Inc(symbol, 1) => format!("inc {};", self.symbol(*symbol)),
+                        ModifyRc::Inc(symbol, n) => format!("inc {} {};", n, self.symbol(*symbol)),
+                        ModifyRc::Dec(symbol) => format!("dec {};", self.symbol(*symbol)),
+                        ModifyRc::DecRef(symbol) => format!("decref {};", self.symbol(*symbol)),
+                        ModifyRc::Free(symbol) => format!("free {};", self.symbol(*symbol)),
+                    };
+
+                    self.line(indent, &line);
+                    stmt = cont;
+                }
+                Stmt::Dbg {
+                    symbol, remainder, ..
+                } => {
+                    let line = format!("dbg {};", self.symbol(*symbol));
+
+                    self.line(indent, &line);
+                    stmt = remainder;
+                }
+                Stmt::Expect {
+                    condition,
+                    remainder,
+                    ..
+                } => {
+                    let line = format!("expect {};", self.symbol(*condition));
+
+                    self.line(indent, &line);
+                    stmt = remainder;
+                }
+                Stmt::ExpectFx {
+                    condition,
+                    remainder,
+                    ..
+                } => {
+                    let line = format!("expect-fx {};", self.symbol(*condition));
+
+                    self.line(indent, &line);
+                    stmt = remainder;
+                }
+                Stmt::Join {
+                    id,
+                    parameters,
+                    body,
+                    remainder,
+                } => {
+                    let params = parameters
+                        .iter()
+                        .map(|param| self.param(param.symbol, param.layout))
+                        .collect::<std::vec::Vec<_>>()
+                        .join(", ");
+
+                    let line = format!("joinpoint {} ({}):", self.symbol(id.0), params);
+
+                    self.line(indent, &line);
+                    self.stmt(body, indent + 4);
+                    self.line(indent, "in");
+                    stmt = remainder;
+                }
+                Stmt::Switch {
+                    cond_symbol,
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    match branches {
+                        [(1, _, pass)] => {
+                            let line = format!("if {} then", self.symbol(*cond_symbol));
+
+                            self.line(indent, &line);
+                            self.stmt(pass, indent + 4);
+                            self.line(indent, "else");
+                            self.stmt(default_branch.1, indent + 4);
+                        }
+                        _ => {
+                            let line = format!("switch {}:", self.symbol(*cond_symbol));
+
+                            self.line(indent, &line);
+
+                            for (label, _, branch) in branches.iter() {
+                                self.line(indent + 4, &format!("case {label}:"));
+                                self.stmt(branch, indent + 8);
+                            }
+
+                            self.line(indent + 4, "default:");
+                            self.stmt(default_branch.1, indent + 8);
+                        }
+                    }
+
+                    return;
+                }
+                Stmt::Ret(symbol) => {
+                    let line = format!("ret {};", self.symbol(*symbol));
+
+                    self.line(indent, &line);
+                    return;
+                }
+                Stmt::Jump(id, arguments) => {
+                    let line =
+                        format!("jump {};", self.with_symbols(&self.symbol(id.0), arguments));
+
+                    self.line(indent, &line);
+                    return;
+                }
+                Stmt::Crash(symbol, _) => {
+                    let line = format!("crash {};", self.symbol(*symbol));
+
+                    self.line(indent, &line);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn expr(&self, expr: &Expr<'a>) -> String {
+        match expr {
+            Expr::Literal(literal) => print_literal(literal),
+            Expr::Call(Call {
+                call_type: CallType::ByName { name, .. },
+                arguments,
+            }) => self.with_symbols(
+                &format!("CallByName {}", self.symbol(name.name())),
+                arguments,
+            ),
+            Expr::Call(Call {
+                call_type: CallType::LowLevel { op, .. },
+                arguments,
+            }) => self.with_symbols(&format!("lowlevel {op:?}"), arguments),
+            Expr::Call(Call {
+                call_type:
+                    CallType::HigherOrder(HigherOrderLowLevel {
+                        op: HigherOrder::ListMap { .. },
+                        ..
+                    }),
+                arguments,
+            }) => self.with_symbols("ListMap", arguments),
+            Expr::Tag {
+                tag_id,
+                arguments,
+                reuse: None,
+                ..
+            } => self.with_symbols(&format!("TagId({tag_id})"), arguments),
+            Expr::Struct(fields) => {
+                let fields: std::vec::Vec<_> = fields.iter().map(|s| self.symbol(*s)).collect();
+
+                format!("Struct {{{}}}", fields.join(", "))
+            }
+            Expr::NullPointer => "NullPointer".to_string(),
+            Expr::StructAtIndex {
+                index, structure, ..
+            } => format!("StructAtIndex {} {}", index, self.symbol(*structure)),
+            Expr::GetTagId { structure, .. } => format!("GetTagId {}", self.symbol(*structure)),
+            Expr::UnionAtIndex {
+                structure,
+                tag_id,
+                index,
+                ..
+            } => format!(
+                "UnionAtIndex (Id {}) (Index {}) {}",
+                tag_id,
+                index,
+                self.symbol(*structure)
+            ),
+            Expr::Array { elems, .. } => {
+                let elems: std::vec::Vec<_> = elems
+                    .iter()
+                    .map(|elem| match elem {
+                        ListLiteralElement::Literal(literal) => print_literal(literal),
+                        ListLiteralElement::Symbol(symbol) => self.symbol(*symbol),
+                    })
+                    .collect();
+
+                format!("Array [{}]", elems.join(", "))
+            }
+            Expr::EmptyArray => "Array []".to_string(),
+            // not part of the format; printed the way the IR usually is, so the output is still
+            // readable even though it can't be parsed
+            _ => expr.to_pretty(200, false).trim_end().to_string(),
+        }
+    }
+
+    fn with_symbols(&self, head: &str, symbols: &[Symbol]) -> String {
+        let mut text = head.to_string();
+
+        for symbol in symbols {
+            text.push(' ');
+            text.push_str(&self.symbol(*symbol));
+        }
+
+        text
+    }
+
+    fn param(&self, symbol: Symbol, layout: InLayout<'a>) -> String {
+        format!("{} : {}", self.symbol(symbol), self.layout(layout))
+    }
+
+    fn symbol(&self, symbol: Symbol) -> String {
+        if symbol.module_id() == self.home {
+            if let Some(name) = self.ident_ids.get_name(symbol.ident_id()) {
+                // generated names are just a number, which would read as a literal
+                return if name.starts_with(|c: char| c.is_ascii_digit()) {
+                    format!("#{name}")
+                } else {
+                    name.to_string()
+                };
+            }
+        }
+
+        symbol_to_doc_string(symbol, false)
+    }
+
+    fn layout(&self, layout: InLayout<'a>) -> String {
+        match self.interner.get_repr(layout) {
+            LayoutRepr::Builtin(builtin) => match builtin {
+                Builtin::Int(width) => format!("{width:?}"),
+                Builtin::Float(width) => format!("{width:?}"),
+                Builtin::Bool => "Int1".to_string(),
+                Builtin::Decimal => "Dec".to_string(),
+                Builtin::Str => "Str".to_string(),
+                Builtin::List(element) => format!("List {}", self.layout(element)),
+            },
+            LayoutRepr::Struct(fields) => {
+                let fields: std::vec::Vec<_> = fields.iter().map(|l| self.layout(*l)).collect();
+
+                format!("{{{}}}", fields.join(", "))
+            }
+            LayoutRepr::Union(UnionLayout::NonRecursive(tags)) => {
+                let tags: std::vec::Vec<_> = tags
+                    .iter()
+                    .map(|fields| {
+                        let mut tag = "C".to_string();
+
+                        for field in fields.iter() {
+                            tag.push(' ');
+                            tag.push_str(&self.layout(*field));
+                        }
+
+                        tag
+                    })
+                    .collect();
+
+                format!("[{}]", tags.join(", "))
+            }
+            // not part of the format
+            _ => self.interner.dbg(layout),
+        }
+    }
+}
+
+fn print_literal(literal: &Literal<'_>) -> String {
+    match literal {
+        Literal::Int(bytes) => i128::from_ne_bytes(*bytes).to_string(),
+        Literal::U128(bytes) => format!("{}u128", u128::from_ne_bytes(*bytes)),
+        Literal::Float(float) => format!("{float}f64"),
+        Literal::Decimal(bytes) => format!("{}dec", RocDec::from_ne_bytes(*bytes)),
+        Literal::Bool(bool) => format!("{bool}"),
+        Literal::Byte(byte) => format!("{byte}u8"),
+        Literal::Str(string) => format!("{string:?}"),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Number,
+    Str,
+    Punct,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'s> {
+    kind: TokenKind,
+    text: &'s str,
+    offset: usize,
+}
+
+fn tokenize(source: &str) -> Result<std::vec::Vec<Token<'_>>, ParseError> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let start = offset;
+        let byte = bytes[offset];
+
+        let kind = if byte.is_ascii_whitespace() {
+            offset += 1;
+            continue;
+        } else if byte.is_ascii_alphabetic() || matches!(byte, b'_' | b'#') {
+            offset = scan(bytes, offset + 1, |b| {
+                b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'#' | b'!')
+            });
+            TokenKind::Word
+        } else if byte.is_ascii_digit()
+            || (byte == b'-' && matches!(bytes.get(offset + 1), Some(b) if b.is_ascii_digit()))
+        {
+            offset = scan(bytes, offset + 1, |b| {
+                b.is_ascii_alphanumeric() || b == b'.'
+            });
+            TokenKind::Number
+        } else if byte == b'"' {
+            offset += 1;
+
+            loop {
+                match bytes.get(offset) {
+                    None => return Err(ParseError::new(source, start, "unterminated string")),
+                    Some(b'\\') => offset += 2,
+                    Some(b'"') => break,
+                    Some(_) => offset += 1,
+                }
+            }
+
+            offset += 1;
+            TokenKind::Str
+        } else if source[offset..].starts_with("->") {
+            offset += 2;
+            TokenKind::Punct
+        } else if b"(){}[],:;=".contains(&byte) {
+            offset += 1;
+            TokenKind::Punct
+        } else {
+            let c = source[offset..].chars().next().unwrap_or_default();
+            let message = format!("unexpected character {c:?}");
+
+            return Err(ParseError::new(source, start, message));
+        };
+
+        tokens.push(Token {
+            kind,
+            text: &source[start..offset],
+            offset: start,
+        });
+    }
+
+    Ok(tokens)
+}
+
+fn scan(bytes: &[u8], mut offset: usize, continues: impl Fn(u8) -> bool) -> usize {
+    while offset < bytes.len() && continues(bytes[offset]) {
+        offset += 1;
+    }
+
+    offset
+}
+
+/// Undo the escaping of a string literal that was printed with `{:?}`
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let escaped = match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let end = rest.find('}')?;
+                let escaped = char::from_u32(u32::from_str_radix(&rest[..end], 16).ok()?)?;
+
+                chars = rest[end + 1..].chars();
+                escaped
+            }
+            _ => return None,
+        };
+
+        result.push(escaped);
+    }
+
+    Some(result)
+}
+
+/// A statement that comes before the rest of a block
+enum Prefix<'a> {
+    Let(Symbol, Expr<'a>, InLayout<'a>),
+    Refcounting(ModifyRc),
+}
+
+struct Parser<'a, 's, 'i, I> {
+    arena: &'a Bump,
+    interner: &'i mut I,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    source: &'s str,
+    tokens: std::vec::Vec<Token<'s>>,
+    position: usize,
+    /// The layout of every symbol that is defined so far
+    layouts: MutMap<Symbol, InLayout<'a>>,
+    /// The return layout of the procedure that is being parsed
+    ret_layout: InLayout<'a>,
+    update_mode_ids: UpdateModeIds,
+    call_spec_id: CallSpecId,
+}
+
+impl<'a, 's, 'i, I: LayoutInterner<'a>> Parser<'a, 's, 'i, I> {
+    fn peek(&self) -> Option<Token<'s>> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn at(&self, text: &str) -> bool {
+        matches!(self.peek(), Some(token) if token.text == text)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let at = self.at(text);
+
+        if at {
+            self.position += 1;
+        }
+
+        at
+    }
+
+    fn error_at(&self, token: Token<'s>, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.source, token.offset, message)
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        match self.peek() {
+            Some(token) => self.error_at(
+                token,
+                format!("expected {expected}, found `{}`", token.text),
+            ),
+            None => ParseError::new(
+                self.source,
+                self.source.len(),
+                format!("expected {expected}, found the end of the input"),
+            ),
+        }
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token<'s>, ParseError> {
+        let token = self.peek().ok_or_else(|| self.unexpected(expected))?;
+        self.position += 1;
+
+        Ok(token)
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), ParseError> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{text}`")))
+        }
+    }
+
+    fn word(&mut self, expected: &str) -> Result<Token<'s>, ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Word => {
+                self.position += 1;
+                Ok(token)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, ParseError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Number => match token.text.parse() {
+                Ok(number) => {
+                    self.position += 1;
+                    Ok(number)
+                }
+                Err(_) => Err(self.error_at(token, format!("`{}` is out of range", token.text))),
+            },
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    fn symbol(&mut self) -> Result<Symbol, ParseError> {
+        let token = self.word("a symbol")?;
+
+        let name = match token.text.strip_prefix('#') {
+            Some(digits) if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => {
+                digits
+            }
+            _ => token.text,
+        };
+
+        Ok(Symbol::new(self.home, self.ident_ids.get_or_insert(name)))
+    }
+
+    /// A symbol that is already defined, with its layout
+    fn defined_symbol(&mut self) -> Result<(Symbol, InLayout<'a>), ParseError> {
+        let token = self.peek();
+        let symbol = self.symbol()?;
+
+        match (self.layouts.get(&symbol), token) {
+            (Some(layout), _) => Ok((symbol, *layout)),
+            (None, Some(token)) => {
+                Err(self.error_at(token, format!("`{}` is not defined", token.text)))
+            }
+            (None, None) => unreachable!("a symbol was parsed"),
+        }
+    }
+
+    /// Symbols separated by spaces, up to (but not including) `end`
+    fn symbols_until(&mut self, end: &str) -> Result<&'a [Symbol], ParseError> {
+        let mut symbols = Vec::new_in(self.arena);
+
+        while !self.at(end) {
+            symbols.push(self.symbol()?);
+        }
+
+        Ok(symbols.into_bump_slice())
+    }
+
+    /// Symbols separated by commas, up to and including `end`
+    fn symbol_list(&mut self, end: &str) -> Result<&'a [Symbol], ParseError> {
+        let mut symbols = Vec::new_in(self.arena);
+
+        if !self.eat(end) {
+            loop {
+                symbols.push(self.symbol()?);
+
+                if self.eat(end) {
+                    break;
+                }
+
+                self.expect(",")?;
+            }
+        }
+
+        Ok(symbols.into_bump_slice())
+    }
+
+    fn proc(&mut self) -> Result<((Symbol, ProcLayout<'a>), Proc<'a>), ParseError> {
+        let arena = self.arena;
+
+        self.expect("procedure")?;
+        let name = self.symbol()?;
+
+        self.layouts.clear();
+        let params = self.params()?;
+
+        self.expect("->")?;
+        let ret_layout = self.layout()?;
+        self.expect(":")?;
+
+        self.ret_layout = ret_layout;
+        let body = self.stmt()?;
+
+        let args = Vec::from_iter_in(params.iter().map(|p| (p.layout, p.symbol)), arena);
+        let arguments = Vec::from_iter_in(params.iter().map(|p| p.layout), arena);
+
+        let proc_layout = ProcLayout {
+            arguments: arguments.into_bump_slice(),
+            result: ret_layout,
+            niche: Niche::NONE,
+        };
+
+        let proc = Proc {
+            name: LambdaName::no_niche(name),
+            args: args.into_bump_slice(),
+            body: body.clone(),
+            closure_data_layout: None,
+            ret_layout,
+            is_self_recursive: SelfRecursive::NotSelfRecursive,
+            is_erased: false,
+        };
+
+        Ok(((name, proc_layout), proc))
+    }
+
+    fn params(&mut self) -> Result<&'a [Param<'a>], ParseError> {
+        let mut params = Vec::new_in(self.arena);
+
+        self.expect("(")?;
+
+        if !self.eat(")") {
+            loop {
+                let symbol = self.symbol()?;
+                self.expect(":")?;
+                let layout = self.layout()?;
+
+                self.layouts.insert(symbol, layout);
+                params.push(Param { symbol, layout });
+
+                if self.eat(")") {
+                    break;
+                }
+
+                self.expect(",")?;
+            }
+        }
+
+        Ok(params.into_bump_slice())
+    }
+
+    fn stmt(&mut self) -> Result<&'a Stmt<'a>, ParseError> {
+        let arena = self.arena;
+
+        // Collect all the leading let bindings and refcounting statements,
+        // to prevent the stack from overflowing when there are many of them.
+        let mut prefixes = vec![];
+        loop {
+            if self.eat("let") {
+                let symbol = self.symbol()?;
+                self.expect(":")?;
+                let layout = self.layout()?;
+                self.expect("=")?;
+                let expr = self.expr(layout)?;
+                self.expect(";")?;
+
+                self.layouts.insert(symbol, layout);
+                prefixes.push(Prefix::Let(symbol, expr, layout));
+            } else if let Some(modify) = self.modify_rc()? {
+                prefixes.push(Prefix::Refcounting(modify));
+            } else {
+                break;
+            }
+        }
+
+        let cont = self.terminal()?;
+
+        Ok(prefixes
+            .into_iter()
+            .rev()
+            .fold(cont, |cont, prefix| match prefix {
+                Prefix::Let(symbol, expr, layout) => {
+                    arena.alloc(Stmt::Let(symbol, expr, layout, cont))
+                }
+                Prefix::Refcounting(modify) => arena.alloc(Stmt::Refcounting(modify, cont)),
+            }))
+    }
+
+    fn modify_rc(&mut self) -> Result<Option<ModifyRc>, ParseError> {
+        let modify = if self.eat("inc") {
+            let count = match self.peek() {
+                Some(token) if token.kind == TokenKind::Number => self.number()?,
+                _ => 1,
+            };
+
+            ModifyRc::Inc(self.symbol()?, count)
+        } else if self.eat("dec") {
+            ModifyRc::Dec(self.symbol()?)
+        } else if self.eat("decref") {
+            ModifyRc::DecRef(self.symbol()?)
+        } else if self.eat("free") {
+            ModifyRc::Free(self.symbol()?)
+        } else {
+            return Ok(None);
+        };
+
+        self.expect(";")?;
+
+        Ok(Some(modify))
+    }
+
+    /// A statement that ends a block
+    fn terminal(&mut self) -> Result<&'a Stmt<'a>, ParseError> {
+        let arena = self.arena;
+        let token = self.word("a statement")?;
+
+        let stmt = match token.text {
+            "ret" => {
+                let symbol = self.symbol()?;
+                self.expect(";")?;
+
+                Stmt::Ret(symbol)
+            }
+            "jump" => {
+                let id = JoinPointId(self.symbol()?);
+                let arguments = self.symbols_until(";")?;
+                self.expect(";")?;
+
+                Stmt::Jump(id, arguments)
+            }
+            "crash" => {
+                let symbol = self.symbol()?;
+                self.expect(";")?;
+
+                Stmt::Crash(symbol, CrashTag::User)
+            }
+            "if" => {
+                let (cond_symbol, cond_layout) = self.defined_symbol()?;
+                self.expect("then")?;
+                let pass = self.stmt()?;
+                self.expect("else")?;
+                let fail = self.stmt()?;
+
+                Stmt::Switch {
+                    cond_symbol,
+                    cond_layout,
+                    branches: arena.alloc([(1, BranchInfo::None, pass.clone())]),
+                    default_branch: (BranchInfo::None, fail),
+                    ret_layout: self.ret_layout,
+                }
+            }
+            "switch" => {
+                let (cond_symbol, cond_layout) = self.defined_symbol()?;
+                self.expect(":")?;
+
+                let mut branches = Vec::new_in(arena);
+                while self.eat("case") {
+                    let label: u64 = self.number()?;
+                    self.expect(":")?;
+                    let branch = self.stmt()?;
+
+                    branches.push((label, BranchInfo::None, branch.clone()));
+                }
+
+                self.expect("default")?;
+                self.expect(":")?;
+                let default = self.stmt()?;
+
+                Stmt::Switch {
+                    cond_symbol,
+                    cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch: (BranchInfo::None, default),
+                    ret_layout: self.ret_layout,
+                }
+            }
+            "joinpoint" => {
+                let id = JoinPointId(self.symbol()?);
+                let parameters = self.params()?;
+                self.expect(":")?;
+                let body = self.stmt()?;
+                self.expect("in")?;
+                let remainder = self.stmt()?;
+
+                Stmt::Join {
+                    id,
+                    parameters,
+                    body,
+                    remainder,
+                }
+            }
+            other => {
+                return Err(self.error_at(token, format!("expected a statement, found `{other}`")))
+            }
+        };
+
+        Ok(arena.alloc(stmt))
+    }
+
+    /// An expression that is bound to a symbol of the given layout
+    fn expr(&mut self, layout: InLayout<'a>) -> Result<Expr<'a>, ParseError> {
+        let arena = self.arena;
+        let token = self.next("an expression")?;
+
+        let expr = match (token.kind, token.text) {
+            (TokenKind::Number | TokenKind::Str, _) | (TokenKind::Word, "true" | "false") => {
+                Expr::Literal(self.literal(token)?)
+            }
+            (TokenKind::Word, "lowlevel") => {
+                let name = self.word("a lowlevel operation")?;
+
+                let op = LOWLEVELS
+                    .iter()
+                    .copied()
+                    .find(|op| format!("{op:?}") == name.text)
+                    .ok_or_else(|| {
+                        self.error_at(name, format!("`{}` is not a lowlevel", name.text))
+                    })?;
+
+                Expr::Call(Call {
+                    call_type: CallType::LowLevel {
+                        op,
+                        update_mode: self.update_mode_ids.next_id(),
+                    },
+                    arguments: self.symbols_until(";")?,
+                })
+            }
+            (TokenKind::Word, "CallByName") => {
+                let name = self.symbol()?;
+                let arguments = self.symbols_until(";")?;
+
+                let mut arg_layouts = Vec::with_capacity_in(arguments.len(), arena);
+                for argument in arguments.iter() {
+                    match self.layouts.get(argument) {
+                        Some(layout) => arg_layouts.push(*layout),
+                        None => {
+                            let name = self.ident_ids.get_name(argument.ident_id());
+                            let message = format!("`{}` is not defined", name.unwrap_or_default());
+
+                            return Err(self.error_at(token, message));
+                        }
+                    }
+                }
+
+                self.call_spec_id = self.call_spec_id.next();
+
+                Expr::Call(Call {
+                    call_type: CallType::ByName {
+                        name: LambdaName::no_niche(name),
+                        ret_layout: layout,
+                        arg_layouts: arg_layouts.into_bump_slice(),
+                        specialization_id: self.call_spec_id,
+                    },
+                    arguments,
+                })
+            }
+            (TokenKind::Word, "ListMap") => {
+                let (xs, list_layout) = self.defined_symbol()?;
+                let name = self.symbol()?;
+                let (env, env_layout) = self.defined_symbol()?;
+
+                let elem_layout = match self.interner.get_repr(list_layout) {
+                    LayoutRepr::Builtin(Builtin::List(elem_layout)) => elem_layout,
+                    _ => return Err(self.error_at(token, "`ListMap` needs a list to map over")),
+                };
+                let return_layout = match self.interner.get_repr(layout) {
+                    LayoutRepr::Builtin(Builtin::List(return_layout)) => return_layout,
+                    _ => return Err(self.error_at(token, "`ListMap` must have a list layout")),
+                };
+
+                // like in the IR, an environment that captures nothing is not passed to `f`
+                let closure_env_layout = (env_layout != Layout::UNIT).then_some(env_layout);
+                let argument_layouts = match closure_env_layout {
+                    Some(env_layout) => arena.alloc([elem_layout, env_layout]) as &[_],
+                    None => arena.alloc([elem_layout]),
+                };
+
+                self.call_spec_id = self.call_spec_id.next();
+
+                let higher_order = HigherOrderLowLevel {
+                    op: HigherOrder::ListMap { xs },
+                    closure_env_layout,
+                    update_mode: self.update_mode_ids.next_id(),
+                    passed_function: PassedFunction {
+                        name: LambdaName::no_niche(name),
+                        argument_layouts,
+                        return_layout,
+                        specialization_id: self.call_spec_id,
+                        captured_environment: env,
+                        owns_captured_environment: true,
+                    },
+                };
+
+                Expr::Call(Call {
+                    call_type: CallType::HigherOrder(arena.alloc(higher_order)),
+                    arguments: arena.alloc([xs, name, env]),
+                })
+            }
+            (TokenKind::Word, "TagId") => {
+                self.expect("(")?;
+                let tag_id = self.number()?;
+                self.expect(")")?;
+
+                Expr::Tag {
+                    tag_layout: self.union_layout(token, layout)?,
+                    tag_id,
+                    arguments: self.symbols_until(";")?,
+                    reuse: None,
+                }
+            }
+            (TokenKind::Word, "Struct") => {
+                self.expect("{")?;
+
+                Expr::Struct(self.symbol_list("}")?)
+            }
+            (TokenKind::Word, "NullPointer") => Expr::NullPointer,
+            (TokenKind::Word, "StructAtIndex") => {
+                let index = self.number()?;
+                let (structure, structure_layout) = self.defined_symbol()?;
+
+                let field_layouts = match self.interner.get_repr(structure_layout) {
+                    LayoutRepr::Struct(field_layouts) => field_layouts,
+                    _ => return Err(self.error_at(token, "the structure is not a struct")),
+                };
+
+                Expr::StructAtIndex {
+                    index,
+                    field_layouts,
+                    structure,
+                }
+            }
+            (TokenKind::Word, "GetTagId") => {
+                let (structure, structure_layout) = self.defined_symbol()?;
+
+                Expr::GetTagId {
+                    structure,
+                    union_layout: self.union_layout(token, structure_layout)?,
+                }
+            }
+            (TokenKind::Word, "UnionAtIndex") => {
+                self.expect("(")?;
+                self.expect("Id")?;
+                let tag_id = self.number()?;
+                self.expect(")")?;
+                self.expect("(")?;
+                self.expect("Index")?;
+                let index = self.number()?;
+                self.expect(")")?;
+                let (structure, structure_layout) = self.defined_symbol()?;
+
+                Expr::UnionAtIndex {
+                    structure,
+                    tag_id,
+                    union_layout: self.union_layout(token, structure_layout)?,
+                    index,
+                }
+            }
+            (TokenKind::Word, "Array") => {
+                self.expect("[")?;
+
+                let mut elems = Vec::new_in(arena);
+                if !self.eat("]") {
+                    loop {
+                        let elem = match self.peek() {
+                            Some(elem) if elem.kind == TokenKind::Word => {
+                                if matches!(elem.text, "true" | "false") {
+                                    self.position += 1;
+                                    ListLiteralElement::Literal(self.literal(elem)?)
+                                } else {
+                                    ListLiteralElement::Symbol(self.symbol()?)
+                                }
+                            }
+                            _ => {
+                                let elem = self.next("a list element")?;
+                                ListLiteralElement::Literal(self.literal(elem)?)
+                            }
+                        };
+
+                        elems.push(elem);
+
+                        if self.eat("]") {
+                            break;
+                        }
+
+                        self.expect(",")?;
+                    }
+                }
+
+                if elems.is_empty() {
+                    Expr::EmptyArray
+                } else {
+                    let elem_layout = match self.interner.get_repr(layout) {
+                        LayoutRepr::Builtin(Builtin::List(elem_layout)) => elem_layout,
+                        _ => return Err(self.error_at(token, "an array must have a list layout")),
+                    };
+
+                    Expr::Array {
+                        elem_layout,
+                        elems: elems.into_bump_slice(),
+                    }
+                }
+            }
+            (_, text) => {
+                return Err(self.error_at(token, format!("expected an expression, found `{text}`")))
+            }
+        };
+
+        Ok(expr)
+    }
+
+    fn union_layout(
+        &self,
+        token: Token<'s>,
+        layout: InLayout<'a>,
+    ) -> Result<UnionLayout<'a>, ParseError> {
+        match self.interner.get_repr(layout) {
+            LayoutRepr::Union(union_layout) => Ok(union_layout),
+            _ => Err(self.error_at(token, format!("`{}` needs a union layout", token.text))),
+        }
+    }
+
+    fn literal(&self, token: Token<'s>) -> Result<Literal<'a>, ParseError> {
+        let text = token.text;
+
+        let literal = match token.kind {
+            TokenKind::Str => unescape(&text[1..text.len() - 1])
+                .map(|string| Literal::Str(self.arena.alloc_str(&string))),
+            _ if text == "true" => Some(Literal::Bool(true)),
+            _ if text == "false" => Some(Literal::Bool(false)),
+            _ => {
+                if let Ok(number) = text.parse::<i128>() {
+                    Some(Literal::Int(number.to_ne_bytes()))
+                } else if let Some(number) = text.strip_suffix("u128") {
+                    number
+                        .parse::<u128>()
+                        .ok()
+                        .map(|n| Literal::U128(n.to_ne_bytes()))
+                } else if let Some(number) = text.strip_suffix("u8") {
+                    number.parse::<u8>().ok().map(Literal::Byte)
+                } else if let Some(number) = text.strip_suffix("f64") {
+                    number.parse::<f64>().ok().map(Literal::Float)
+                } else if let Some(number) = text.strip_suffix("dec") {
+                    RocDec::from_str(number).map(|n| Literal::Decimal(n.to_ne_bytes()))
+                } else {
+                    None
+                }
+            }
+        };
+
+        literal.ok_or_else(|| self.error_at(token, format!("`{text}` is not a valid literal")))
+    }
+
+    fn layout(&mut self) -> Result<InLayout<'a>, ParseError> {
+        let arena = self.arena;
+        let token = self.next("a layout")?;
+
+        let layout = match token.text {
+            "I8" => Layout::I8,
+            "I16" => Layout::I16,
+            "I32" => Layout::I32,
+            "I64" => Layout::I64,
+            "I128" => Layout::I128,
+            "U8" => Layout::U8,
+            "U16" => Layout::U16,
+            "U32" => Layout::U32,
+            "U64" => Layout::U64,
+            "U128" => Layout::U128,
+            "F32" => Layout::F32,
+            "F64" => Layout::F64,
+            "Dec" => Layout::DEC,
+            "Str" => Layout::STR,
+            "Int1" => Layout::BOOL,
+            "List" => {
+                let element = self.layout()?;
+
+                self.interner
+                    .insert_direct_no_semantic(LayoutRepr::Builtin(Builtin::List(element)))
+            }
+            "{" => {
+                let mut fields = Vec::new_in(arena);
+                if !self.eat("}") {
+                    loop {
+                        fields.push(self.layout()?);
+
+                        if self.eat("}") {
+                            break;
+                        }
+
+                        self.expect(",")?;
+                    }
+                }
+
+                if fields.is_empty() {
+                    Layout::UNIT
+                } else {
+                    self.interner
+                        .insert_direct_no_semantic(LayoutRepr::Struct(fields.into_bump_slice()))
+                }
+            }
+            "[" => {
+                let mut tags = Vec::new_in(arena);
+                if !self.eat("]") {
+                    loop {
+                        self.expect("C")?;
+
+                        let mut fields = Vec::new_in(arena);
+                        while !self.at(",") && !self.at("]") {
+                            fields.push(self.layout()?);
+                        }
+
+                        tags.push(&*fields.into_bump_slice());
+
+                        if self.eat("]") {
+                            break;
+                        }
+
+                        self.expect(",")?;
+                    }
+                }
+
+                if tags.is_empty() {
+                    Layout::VOID
+                } else {
+                    let union_layout = UnionLayout::NonRecursive(tags.into_bump_slice());
+
+                    self.interner
+                        .insert_direct_no_semantic(LayoutRepr::Union(union_layout))
+                }
+            }
+            other => {
+                return Err(self.error_at(token, format!("expected a layout, found `{other}`")))
+            }
+        };
+
+        Ok(layout)
+    }
+}
+
+/// Every lowlevel, so they can be looked up by name
+const LOWLEVELS: &[LowLevel] = {
+    use LowLevel::*;
+
+    &[
+        StrConcat,
+        StrJoinWith,
+        StrIsEmpty,
+        StrStartsWith,
+        StrEndsWith,
+        StrSplit,
+        StrCountUtf8Bytes,
+        StrFromInt,
+        StrFromUtf8,
+        StrToUtf8,
+        StrRepeat,
+        StrFromFloat,
+        StrTrim,
+        StrTrimStart,
+        StrTrimEnd,
+        StrToNum,
+        StrGetUnsafe,
+        StrSubstringUnsafe,
+        StrReserve,
+        StrWithCapacity,
+        StrReleaseExcessCapacity,
+        ListLenUsize,
+        ListLenU64,
+        ListWithCapacity,
+        ListReserve,
+        ListReleaseExcessCapacity,
+        ListAppendUnsafe,
+        ListGetUnsafe,
+        ListReplaceUnsafe,
+        ListConcat,
+        ListPrepend,
+        ListMap,
+        ListMap2,
+        ListMap3,
+        ListMap4,
+        ListSortWith,
+        ListSublist,
+        ListDropAt,
+        ListSwap,
+        ListGetCapacity,
+        ListIsUnique,
+        ListClone,
+        NumAdd,
+        NumAddWrap,
+        NumAddChecked,
+        NumAddSaturated,
+        NumSub,
+        NumSubWrap,
+        NumSubChecked,
+        NumSubSaturated,
+        NumMul,
+        NumMulWrap,
+        NumMulSaturated,
+        NumMulChecked,
+        NumGt,
+        NumGte,
+        NumLt,
+        NumLte,
+        NumCompare,
+        NumDivFrac,
+        NumDivTruncUnchecked,
+        NumDivCeilUnchecked,
+        NumRemUnchecked,
+        NumIsMultipleOf,
+        NumAbs,
+        NumNeg,
+        NumSin,
+        NumCos,
+        NumTan,
+        NumSqrtUnchecked,
+        NumLogUnchecked,
+        NumRound,
+        NumToFrac,
+        NumPow,
+        NumCeiling,
+        NumPowInt,
+        NumFloor,
+        NumIsNan,
+        NumIsInfinite,
+        NumIsFinite,
+        NumAtan,
+        NumAcos,
+        NumAsin,
+        NumBitwiseAnd,
+        NumBitwiseXor,
+        NumBitwiseOr,
+        NumShiftLeftBy,
+        NumShiftRightBy,
+        NumShiftRightZfBy,
+        NumIntCast,
+        NumToFloatCast,
+        NumToIntChecked,
+        NumToFloatChecked,
+        NumToStr,
+        NumCountLeadingZeroBits,
+        NumCountTrailingZeroBits,
+        NumCountOneBits,
+        NumWithoutDecimalPoint,
+        NumWithDecimalPoint,
+        NumF32ToParts,
+        NumF64ToParts,
+        NumF32FromParts,
+        NumF64FromParts,
+        Eq,
+        NotEq,
+        And,
+        Or,
+        Not,
+        Hash,
+        PtrCast,
+        PtrStore,
+        PtrLoad,
+        PtrClearTagId,
+        RefCountIncRcPtr,
+        RefCountDecRcPtr,
+        RefCountIncDataPtr,
+        RefCountDecDataPtr,
+        RefCountIsUnique,
+        BoxExpr,
+        UnboxExpr,
+        Unreachable,
+        DictPseudoSeed,
+        SetJmp,
+        LongJmp,
+        SetLongJmpBuffer,
+    ]
+};
+
+/// Helpers for the tests of the optimization passes
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use crate::layout::STLayoutInterner;
+    use roc_module::symbol::ModuleIds;
+
+    pub(crate) fn home() -> ModuleId {
+        ModuleIds::default().get_or_insert(&"Test".into())
+    }
+
+    pub(crate) fn parse<'a>(
+        arena: &'a Bump,
+        interner: &mut STLayoutInterner<'a>,
+        ident_ids: &mut IdentIds,
+        source: &str,
+    ) -> Procs<'a> {
+        match parse_procs(arena, interner, home(), ident_ids, source) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    /// Compare procedures to their expected text, ignoring differences in whitespace
+    pub(crate) fn assert_procs<'a>(
+        arena: &'a Bump,
+        interner: &mut STLayoutInterner<'a>,
+        ident_ids: &mut IdentIds,
+        actual: &Procs<'a>,
+        expected: &str,
+    ) {
+        let expected = parse(arena, interner, ident_ids, expected);
+
+        assert_eq!(
+            print_procs(interner, home(), ident_ids, actual),
+            print_procs(interner, home(), ident_ids, &expected),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::testing::{home, parse};
+    use super::*;
+    use crate::layout::STLayoutInterner;
+    use indoc::indoc;
+    use roc_target::Target;
+
+    #[test]
+    fn print_then_parse() {
+        let source = indoc!(
+            r#"
+            procedure Test.main (x : I64, xs : List I64) -> [C I64, C Str]:
+                let one : I64 = 1;
+                let sum : I64 = lowlevel NumAdd x one;
+                let pair : {I64, List I64} = Struct {sum, xs};
+                let first : I64 = StructAtIndex 0 pair;
+                let text : Str = "a \"quoted\"\nline";
+                let big : Int1 = lowlevel NumGt first one;
+                joinpoint done (result : [C I64, C Str]):
+                    ret result;
+                in
+                if big then
+                    let tagged : [C I64, C Str] = TagId(0) first;
+                    jump done tagged;
+                else
+                    inc xs;
+                    let other : [C I64, C Str] = TagId(1) text;
+                    dec xs;
+                    jump done other;
+
+            procedure Test.pick (n : U8) -> Str:
+                switch n:
+                    case 0:
+                        let zero : Str = CallByName Test.name n;
+                        ret zero;
+                    case 1:
+                        let list : List F64 = Array [1.5f64, 2f64];
+                        let empty : List Dec = Array [];
+                        let message : Str = "unreachable";
+                        crash message;
+                    default:
+                        let many : Str = "many";
+                        ret many;
+
+            procedure Test.unwrap (r : [C I64, C Str]) -> I64:
+                let id : U8 = GetTagId r;
+                let value : I64 = UnionAtIndex (Id 0) (Index 0) r;
+                ret value;
+            "#
+        );
+
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = parse(&arena, &mut interner, &mut ident_ids, source);
+
+        assert_eq!(print_procs(&interner, home(), &ident_ids, &procs), source);
+    }
+
+    #[test]
+    fn undefined_symbol() {
+        let source = indoc!(
+            r#"
+            procedure Test.main () -> I64:
+                let x : I64 = StructAtIndex 0 pair;
+                ret x;
+            "#
+        );
+
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let result = parse_procs(&arena, &mut interner, home(), &mut ident_ids, source);
+
+        assert_eq!(
+            result.map(|_| ()),
+            Err(ParseError {
+                line: 2,
+                column: 35,
+                message: "`pair` is not defined".to_string(),
+            })
+        );
+    }
+}
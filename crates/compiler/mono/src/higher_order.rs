@@ -0,0 +1,976 @@
+//! Specialization of higher-order builtins.
+//!
+//! After specialization, the function that is passed to a higher-order lowlevel is always known.
+//! A call to `List.map` then no longer needs to go through the generic bitcode implementation,
+//! which can only call the function through a pointer. Instead `let ys = List.map xs f; cont`
+//! becomes a loop that calls `f` directly:
+//!
+//! ```text
+//! joinpoint done ys =
+//!     cont
+//! in
+//! let len = lowlevel ListLenU64 xs;
+//! let acc = lowlevel ListWithCapacity len;
+//! let zero = 0;
+//! joinpoint loop index acc =
+//!     let more = lowlevel NumLt index len;
+//!     if more then
+//!         let x = lowlevel ListGetUnsafe xs index;
+//!         let y = CallByName f x;
+//!         let acc = lowlevel ListAppendUnsafe acc y;
+//!         let one = 1;
+//!         let index = lowlevel NumAddWrap index one;
+//!         jump loop index acc;
+//!     else
+//!         jump done acc;
+//! in
+//! jump loop zero acc;
+//! ```
+//!
+//! Later passes can then inline `f` into the loop.
+//!
+//! When `f` returns elements of the same layout it takes, the loop instead reads from and writes
+//! back into the list it is building, which starts out as `xs` itself:
+//!
+//! ```text
+//! let elem = lowlevel ListGetUnsafe acc index;
+//! let y = CallByName f elem;
+//! let replaced = lowlevel ListReplaceUnsafe acc index y;
+//! let acc = StructAtIndex 0 replaced;
+//! ```
+//!
+//! So a unique `xs` is updated in place, and a shared one is copied once by the first write.
+//!
+//! A pipeline like `xs |> List.map f |> List.map g` becomes a single loop that calls `g` on the
+//! result of `f`, so the intermediate list is never allocated. This only happens when nothing else
+//! uses the intermediate list, and when neither `f` nor `g` can crash or `dbg`: the fused loop
+//! interleaves the calls, which would change the order in which those effects are observed.
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::{MutMap, MutSet};
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+
+use crate::dead_code::{count_expr_uses, count_uses};
+use crate::inline::for_each_stmt;
+use crate::ir::{
+    Call, CallType, Expr, HigherOrderLowLevel, JoinPointId, Literal, Param, PassedFunction, Proc,
+    ProcLayout, Stmt, UpdateModeIds,
+};
+use crate::layout::{InLayout, Layout, LayoutInterner, LayoutRepr, STLayoutInterner};
+use crate::low_level::HigherOrder;
+
+pub fn specialize_higher_order_calls<'a>(
+    arena: &'a Bump,
+    interner: &mut STLayoutInterner<'a>,
+    home: ModuleId,
+    ident_ids: &mut IdentIds,
+    update_mode_ids: &mut UpdateModeIds,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    let effectful = effectful_procedures(procs);
+
+    let mut env = Env {
+        arena,
+        interner,
+        home,
+        ident_ids,
+        update_mode_ids,
+        effectful,
+        uses: MutMap::default(),
+    };
+
+    for proc in procs.values_mut() {
+        env.uses = count_uses(arena, &proc.body);
+
+        let new_body = env.specialize_stmt(proc.ret_layout, &proc.body);
+        proc.body = new_body.clone();
+    }
+}
+
+/// The procedures that can crash, `dbg` or `expect`, directly or through the procedures they call.
+/// Calls to anything not defined in `procs` are assumed to have effects too.
+fn effectful_procedures<'a>(procs: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>) -> MutSet<Symbol> {
+    let defined: MutSet<Symbol> = procs.keys().map(|(symbol, _)| *symbol).collect();
+
+    let mut callers: MutMap<Symbol, std::vec::Vec<Symbol>> = MutMap::default();
+    let mut effectful = MutSet::default();
+    let mut stack = vec![];
+
+    for ((symbol, _), proc) in procs {
+        let mut has_effects = false;
+
+        for_each_stmt(&proc.body, |stmt| match stmt {
+            Stmt::Let(_, expr, _, _) => match expr {
+                Expr::Call(Call { call_type, .. }) => match call_type {
+                    CallType::ByName { name, .. } if defined.contains(&name.name()) => {
+                        callers.entry(name.name()).or_default().push(*symbol);
+                    }
+                    CallType::LowLevel { op, .. } => has_effects |= !cannot_crash(*op),
+                    CallType::ByName { .. }
+                    | CallType::ByPointer { .. }
+                    | CallType::Foreign { .. }
+                    | CallType::HigherOrder(_) => has_effects = true,
+                },
+                Expr::RuntimeErrorFunction(_) => has_effects = true,
+                _ => {}
+            },
+            Stmt::Crash(..) | Stmt::Dbg { .. } | Stmt::Expect { .. } | Stmt::ExpectFx { .. } => {
+                has_effects = true
+            }
+            _ => {}
+        });
+
+        if has_effects && effectful.insert(*symbol) {
+            stack.push(*symbol);
+        }
+    }
+
+    while let Some(callee) = stack.pop() {
+        for caller in callers.get(&callee).into_iter().flatten() {
+            if effectful.insert(*caller) {
+                stack.push(*caller);
+            }
+        }
+    }
+
+    effectful
+}
+
+/// Lowlevels that never crash. Anything not listed here, like `NumAdd` which panics on overflow,
+/// is treated as if it could.
+fn cannot_crash(op: LowLevel) -> bool {
+    use LowLevel::*;
+
+    matches!(
+        op,
+        NumAddWrap
+            | NumAddSaturated
+            | NumAddChecked
+            | NumSubWrap
+            | NumSubSaturated
+            | NumSubChecked
+            | NumMulWrap
+            | NumMulSaturated
+            | NumMulChecked
+            | NumGt
+            | NumGte
+            | NumLt
+            | NumLte
+            | NumCompare
+            | NumBitwiseAnd
+            | NumBitwiseXor
+            | NumBitwiseOr
+            | NumIsNan
+            | NumIsInfinite
+            | NumIsFinite
+            | Eq
+            | NotEq
+            | And
+            | Or
+            | Not
+            | ListLenU64
+            | ListLenUsize
+            | ListGetUnsafe
+            | StrIsEmpty
+    )
+}
+
+struct Env<'a, 'i> {
+    arena: &'a Bump,
+    interner: &'i mut STLayoutInterner<'a>,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    update_mode_ids: &'i mut UpdateModeIds,
+    /// Procedures whose calls must not be reordered, see [effectful_procedures]
+    effectful: MutSet<Symbol>,
+    /// How often every symbol is used in the current procedure
+    uses: MutMap<Symbol, usize>,
+}
+
+impl<'a, 'i> Env<'a, 'i> {
+    fn unique_symbol(&mut self) -> Symbol {
+        let ident_id = self.ident_ids.gen_unique();
+
+        Symbol::new(self.home, ident_id)
+    }
+
+    fn lowlevel(&mut self, op: LowLevel, arguments: &[Symbol]) -> Expr<'a> {
+        Expr::Call(Call {
+            call_type: CallType::LowLevel {
+                op,
+                update_mode: self.update_mode_ids.next_id(),
+            },
+            arguments: self.arena.alloc_slice_copy(arguments),
+        })
+    }
+
+    fn specialize_stmt(&mut self, ret_layout: InLayout<'a>, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        let arena = self.arena;
+
+        match stmt {
+            Stmt::Let(_, _, _, _) => {
+                // Collect all the subsequent let bindings,
+                // to prevent the stack from overflowing when there are many let bindings.
+                let mut bindings: std::vec::Vec<Binding> = vec![];
+                let mut current_stmt = stmt;
+                while let Stmt::Let(binding, expr, layout, next_stmt) = current_stmt {
+                    let Some((xs, stage)) = map_stage(expr) else {
+                        bindings.push(Binding::Let(*binding, expr, *layout));
+                        current_stmt = next_stmt;
+                        continue;
+                    };
+
+                    // `let ys = List.map xs f; let zs = List.map ys g` becomes a single loop
+                    // when `ys` is not used anywhere else. That includes `g` capturing `ys`,
+                    // which would then never be built. The loop calls `f` and `g` in turns, so
+                    // neither may have effects whose order could be observed.
+                    match bindings.last_mut() {
+                        Some(Binding::Map {
+                            binding: previous,
+                            layout: previous_layout,
+                            stages,
+                            ..
+                        }) if *previous == xs
+                            && stage.captured_environment != Some(xs)
+                            && self.only_used_by(xs, expr)
+                            && !self.has_effects(&stage)
+                            && !stages.iter().any(|earlier| self.has_effects(earlier)) =>
+                        {
+                            *previous = *binding;
+                            *previous_layout = *layout;
+                            stages.push(stage);
+                        }
+                        _ => bindings.push(Binding::Map {
+                            binding: *binding,
+                            layout: *layout,
+                            xs,
+                            stages: vec![stage],
+                        }),
+                    }
+
+                    current_stmt = next_stmt;
+                }
+
+                let cont = self.specialize_stmt(ret_layout, current_stmt);
+
+                bindings
+                    .into_iter()
+                    .rev()
+                    .fold(cont, |cont, binding| match binding {
+                        Binding::Let(binding, expr, layout) => {
+                            arena.alloc(Stmt::Let(binding, expr.clone(), layout, cont))
+                        }
+                        Binding::Map {
+                            binding,
+                            layout,
+                            xs,
+                            stages,
+                        } => self.list_map_loop(xs, &stages, binding, layout, ret_layout, cont),
+                    })
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout: switch_ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        let branch = self.specialize_stmt(ret_layout, branch);
+                        (*label, info.clone(), branch.clone())
+                    }),
+                    arena,
+                )
+                .into_bump_slice();
+
+                let default_branch = (
+                    default_branch.0.clone(),
+                    self.specialize_stmt(ret_layout, default_branch.1),
+                );
+
+                arena.alloc(Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches,
+                    default_branch,
+                    ret_layout: *switch_ret_layout,
+                })
+            }
+            Stmt::Refcounting(modify, cont) => {
+                let cont = self.specialize_stmt(ret_layout, cont);
+                arena.alloc(Stmt::Refcounting(*modify, cont))
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.specialize_stmt(ret_layout, remainder),
+            }),
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.specialize_stmt(ret_layout, remainder),
+            }),
+            Stmt::Dbg {
+                source_location,
+                source,
+                symbol,
+                variable,
+                remainder,
+            } => arena.alloc(Stmt::Dbg {
+                source_location: *source_location,
+                source: *source,
+                symbol: *symbol,
+                variable: *variable,
+                remainder: self.specialize_stmt(ret_layout, remainder),
+            }),
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => arena.alloc(Stmt::Join {
+                id: *id,
+                parameters: *parameters,
+                body: self.specialize_stmt(ret_layout, body),
+                remainder: self.specialize_stmt(ret_layout, remainder),
+            }),
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => arena.alloc(stmt.clone()),
+        }
+    }
+
+    /// Whether the only uses of `symbol` in the current procedure are in `expr`
+    fn only_used_by(&self, symbol: Symbol, expr: &Expr<'a>) -> bool {
+        let in_expr = count_expr_uses(self.arena, expr);
+
+        self.uses.get(&symbol) == in_expr.get(&symbol)
+    }
+
+    fn has_effects(&self, stage: &Stage<'a>) -> bool {
+        self.effectful.contains(&stage.function.name.name())
+    }
+
+    /// Turn `let binding = List.map xs f; cont` into a loop, see the module documentation.
+    /// The functions of all stages are applied one after the other to every element.
+    fn list_map_loop(
+        &mut self,
+        xs: Symbol,
+        stages: &[Stage<'a>],
+        binding: Symbol,
+        list_layout: InLayout<'a>,
+        ret_layout: InLayout<'a>,
+        cont: &'a Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        let arena = self.arena;
+
+        let elem_layout = stages[0].elem_layout;
+        let in_place = stages[stages.len() - 1].function.return_layout == elem_layout;
+
+        let len = self.unique_symbol();
+        let initial_acc = self.unique_symbol();
+        let zero = self.unique_symbol();
+        let index = self.unique_symbol();
+        let acc = self.unique_symbol();
+        let more = self.unique_symbol();
+        let elem = self.unique_symbol();
+        let appended = self.unique_symbol();
+        let one = self.unique_symbol();
+        let next_index = self.unique_symbol();
+
+        let done_id = JoinPointId(self.unique_symbol());
+        let loop_id = JoinPointId(self.unique_symbol());
+
+        // the body of the loop, from the back to the front
+        let next = arena.alloc(Stmt::Jump(loop_id, arena.alloc([next_index, appended])));
+        let next = arena.alloc(Stmt::Let(
+            next_index,
+            self.lowlevel(LowLevel::NumAddWrap, &[index, one]),
+            Layout::U64,
+            next,
+        ));
+        let next = arena.alloc(Stmt::Let(
+            one,
+            Expr::Literal(Literal::Int(1i128.to_ne_bytes())),
+            Layout::U64,
+            next,
+        ));
+
+        let mut calls = std::vec::Vec::with_capacity(stages.len());
+        let mut mapped = elem;
+        for stage in stages {
+            let input = mapped;
+            mapped = self.unique_symbol();
+
+            let arguments = match stage.captured_environment {
+                Some(captured_environment) => arena.alloc([input, captured_environment]) as &[_],
+                None => arena.alloc([input]),
+            };
+
+            let call = Expr::Call(Call {
+                call_type: CallType::ByName {
+                    name: stage.function.name,
+                    ret_layout: stage.function.return_layout,
+                    arg_layouts: stage.function.argument_layouts,
+                    specialization_id: stage.function.specialization_id,
+                },
+                arguments,
+            });
+
+            calls.push((mapped, call, stage.function.return_layout));
+        }
+
+        let next = if in_place {
+            let replaced = self.unique_symbol();
+
+            // `ListReplaceUnsafe` returns the list and the old element. The element comes first
+            // when its alignment is bigger than that of a list, like in a record.
+            let element_first = self.interner.alignment_bytes(elem_layout)
+                > self.interner.target().ptr_width() as u32;
+            let field_layouts: &'a [InLayout<'a>] = if element_first {
+                arena.alloc([elem_layout, list_layout])
+            } else {
+                arena.alloc([list_layout, elem_layout])
+            };
+            let list_index = if element_first { 1 } else { 0 };
+            let replaced_layout = self
+                .interner
+                .insert_direct_no_semantic(LayoutRepr::Struct(field_layouts));
+
+            let next = arena.alloc(Stmt::Let(
+                appended,
+                Expr::StructAtIndex {
+                    index: list_index,
+                    field_layouts,
+                    structure: replaced,
+                },
+                list_layout,
+                next,
+            ));
+
+            arena.alloc(Stmt::Let(
+                replaced,
+                self.lowlevel(LowLevel::ListReplaceUnsafe, &[acc, index, mapped]),
+                replaced_layout,
+                next,
+            ))
+        } else {
+            arena.alloc(Stmt::Let(
+                appended,
+                self.lowlevel(LowLevel::ListAppendUnsafe, &[acc, mapped]),
+                list_layout,
+                next,
+            ))
+        };
+        let next = calls
+            .into_iter()
+            .rev()
+            .fold(next, |next, (mapped, call, layout)| {
+                arena.alloc(Stmt::Let(mapped, call, layout, next))
+            });
+        // in place, the elements must be read from the list that is written to, or `xs` would
+        // have a second reference and never be unique
+        let source = if in_place { acc } else { xs };
+        let next = arena.alloc(Stmt::Let(
+            elem,
+            self.lowlevel(LowLevel::ListGetUnsafe, &[source, index]),
+            elem_layout,
+            next,
+        ));
+
+        let exit = arena.alloc(Stmt::Jump(done_id, arena.alloc([acc])));
+        let check = Stmt::if_then_else(arena, more, ret_layout, next.clone(), exit);
+        let loop_body = arena.alloc(Stmt::Let(
+            more,
+            self.lowlevel(LowLevel::NumLt, &[index, len]),
+            Layout::BOOL,
+            arena.alloc(check),
+        ));
+
+        let loop_params = arena.alloc([
+            Param {
+                symbol: index,
+                layout: Layout::U64,
+            },
+            Param {
+                symbol: acc,
+                layout: list_layout,
+            },
+        ]);
+
+        let start_acc = if in_place { xs } else { initial_acc };
+        let start = arena.alloc(Stmt::Jump(loop_id, arena.alloc([zero, start_acc])));
+        let looping = arena.alloc(Stmt::Join {
+            id: loop_id,
+            parameters: loop_params,
+            body: loop_body,
+            remainder: start,
+        });
+
+        let looping = arena.alloc(Stmt::Let(
+            zero,
+            Expr::Literal(Literal::Int(0i128.to_ne_bytes())),
+            Layout::U64,
+            looping,
+        ));
+        let looping = if in_place {
+            looping
+        } else {
+            arena.alloc(Stmt::Let(
+                initial_acc,
+                self.lowlevel(LowLevel::ListWithCapacity, &[len]),
+                list_layout,
+                looping,
+            ))
+        };
+        let looping = arena.alloc(Stmt::Let(
+            len,
+            self.lowlevel(LowLevel::ListLenU64, &[xs]),
+            Layout::U64,
+            looping,
+        ));
+
+        let done_params = arena.alloc([Param {
+            symbol: binding,
+            layout: list_layout,
+        }]);
+
+        arena.alloc(Stmt::Join {
+            id: done_id,
+            parameters: done_params,
+            body: cont,
+            remainder: looping,
+        })
+    }
+}
+
+enum Binding<'a, 'b> {
+    Let(Symbol, &'b Expr<'a>, InLayout<'a>),
+    /// A pipeline of `List.map` calls, the first of which maps over `xs`
+    Map {
+        binding: Symbol,
+        layout: InLayout<'a>,
+        xs: Symbol,
+        stages: std::vec::Vec<Stage<'a>>,
+    },
+}
+
+/// One function of a `List.map` pipeline
+struct Stage<'a> {
+    function: PassedFunction<'a>,
+    elem_layout: InLayout<'a>,
+    captured_environment: Option<Symbol>,
+}
+
+/// The list and the function of a `List.map` call that can be turned into a loop
+fn map_stage<'a>(expr: &Expr<'a>) -> Option<(Symbol, Stage<'a>)> {
+    let Expr::Call(Call {
+        call_type:
+            CallType::HigherOrder(HigherOrderLowLevel {
+                op: HigherOrder::ListMap { xs },
+                closure_env_layout,
+                passed_function,
+                ..
+            }),
+        arguments,
+    }) = expr
+    else {
+        return None;
+    };
+
+    // The function takes the element, and the captured environment if there is one.
+    // Anything else is left to the bitcode implementation.
+    let elem_layout = match (passed_function.argument_layouts, closure_env_layout) {
+        ([elem_layout], None) | ([elem_layout, _], Some(_)) => *elem_layout,
+        _ => return None,
+    };
+
+    let stage = Stage {
+        function: *passed_function,
+        elem_layout,
+        captured_environment: closure_env_layout.map(|_| arguments[2]),
+    };
+
+    Some((*xs, stage))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::debug::print_procs;
+    use crate::debug::testing::{assert_procs, home, parse};
+    use indoc::indoc;
+    use roc_target::Target;
+
+    fn specialize<'a>(
+        arena: &'a Bump,
+        interner: &mut STLayoutInterner<'a>,
+        ident_ids: &mut IdentIds,
+        source: &str,
+    ) -> MutMap<(Symbol, ProcLayout<'a>), Proc<'a>> {
+        let mut procs = parse(arena, interner, ident_ids, source);
+
+        specialize_higher_order_calls(
+            arena,
+            interner,
+            home(),
+            ident_ids,
+            &mut UpdateModeIds::new(),
+            &mut procs,
+        );
+
+        procs
+    }
+
+    #[test]
+    fn map_in_place() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = specialize(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (xs : List I64) -> List I64:
+                    let env : {} = Struct {};
+                    let ys : List I64 = ListMap xs Test.double env;
+                    ret ys;
+                "#
+            ),
+        );
+
+        // the elements keep their layout, so every one is replaced in the list that is passed in
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (xs : List I64) -> List I64:
+                    let env : {} = Struct {};
+                    joinpoint #15 (ys : List I64):
+                        ret ys;
+                    in
+                    let #5 : U64 = lowlevel ListLenU64 xs;
+                    let #7 : U64 = 0;
+                    joinpoint #16 (#8 : U64, #9 : List I64):
+                        let #10 : Int1 = lowlevel NumLt #8 #5;
+                        if #10 then
+                            let #11 : I64 = lowlevel ListGetUnsafe #9 #8;
+                            let #17 : I64 = CallByName Test.double #11;
+                            let #18 : {List I64, I64} = lowlevel ListReplaceUnsafe #9 #8 #17;
+                            let #12 : List I64 = StructAtIndex 0 #18;
+                            let #13 : U64 = 1;
+                            let #14 : U64 = lowlevel NumAddWrap #8 #13;
+                            jump #16 #14 #12;
+                        else
+                            jump #15 #9;
+                    in
+                    jump #16 #7 xs;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn map_to_other_layout() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = specialize(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (xs : List I64) -> List Int1:
+                    let env : {} = Struct {};
+                    let ys : List Int1 = ListMap xs Test.isPositive env;
+                    ret ys;
+                "#
+            ),
+        );
+
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (xs : List I64) -> List Int1:
+                    let env : {} = Struct {};
+                    joinpoint #15 (ys : List Int1):
+                        ret ys;
+                    in
+                    let #5 : U64 = lowlevel ListLenU64 xs;
+                    let #6 : List Int1 = lowlevel ListWithCapacity #5;
+                    let #7 : U64 = 0;
+                    joinpoint #16 (#8 : U64, #9 : List Int1):
+                        let #10 : Int1 = lowlevel NumLt #8 #5;
+                        if #10 then
+                            let #11 : I64 = lowlevel ListGetUnsafe xs #8;
+                            let #17 : Int1 = CallByName Test.isPositive #11;
+                            let #12 : List Int1 = lowlevel ListAppendUnsafe #9 #17;
+                            let #13 : U64 = 1;
+                            let #14 : U64 = lowlevel NumAddWrap #8 #13;
+                            jump #16 #14 #12;
+                        else
+                            jump #15 #9;
+                    in
+                    jump #16 #7 #6;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn map_with_captures() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = specialize(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (xs : List I64, n : I64) -> List I64:
+                    let env : {I64} = Struct {n};
+                    let ys : List I64 = ListMap xs Test.add env;
+                    ret ys;
+                "#
+            ),
+        );
+
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (xs : List I64, n : I64) -> List I64:
+                    let env : {I64} = Struct {n};
+                    joinpoint #16 (ys : List I64):
+                        ret ys;
+                    in
+                    let #6 : U64 = lowlevel ListLenU64 xs;
+                    let #8 : U64 = 0;
+                    joinpoint #17 (#9 : U64, #10 : List I64):
+                        let #11 : Int1 = lowlevel NumLt #9 #6;
+                        if #11 then
+                            let #12 : I64 = lowlevel ListGetUnsafe #10 #9;
+                            let #18 : I64 = CallByName Test.add #12 env;
+                            let #19 : {List I64, I64} = lowlevel ListReplaceUnsafe #10 #9 #18;
+                            let #13 : List I64 = StructAtIndex 0 #19;
+                            let #14 : U64 = 1;
+                            let #15 : U64 = lowlevel NumAddWrap #9 #14;
+                            jump #17 #15 #13;
+                        else
+                            jump #16 #10;
+                    in
+                    jump #17 #8 xs;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn fuse_maps() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = specialize(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.double (n : I64) -> I64:
+                    let two : I64 = 2;
+                    let doubled : I64 = lowlevel NumMulWrap n two;
+                    ret doubled;
+
+                procedure Test.main (xs : List I64) -> List I64:
+                    let env : {} = Struct {};
+                    let ys : List I64 = ListMap xs Test.double env;
+                    let zs : List I64 = ListMap ys Test.double env;
+                    ret zs;
+                "#
+            ),
+        );
+
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.double (n : I64) -> I64:
+                    let two : I64 = 2;
+                    let doubled : I64 = lowlevel NumMulWrap n two;
+                    ret doubled;
+
+                procedure Test.main (xs : List I64) -> List I64:
+                    let env : {} = Struct {};
+                    joinpoint #19 (zs : List I64):
+                        ret zs;
+                    in
+                    let #9 : U64 = lowlevel ListLenU64 xs;
+                    let #11 : U64 = 0;
+                    joinpoint #20 (#12 : U64, #13 : List I64):
+                        let #14 : Int1 = lowlevel NumLt #12 #9;
+                        if #14 then
+                            let #15 : I64 = lowlevel ListGetUnsafe #13 #12;
+                            let #21 : I64 = CallByName Test.double #15;
+                            let #22 : I64 = CallByName Test.double #21;
+                            let #23 : {List I64, I64} = lowlevel ListReplaceUnsafe #13 #12 #22;
+                            let #16 : List I64 = StructAtIndex 0 #23;
+                            let #17 : U64 = 1;
+                            let #18 : U64 = lowlevel NumAddWrap #12 #17;
+                            jump #20 #18 #16;
+                        else
+                            jump #19 #13;
+                    in
+                    jump #20 #11 xs;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn keep_maps_whose_functions_can_crash() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = specialize(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.double (n : I64) -> I64:
+                    let two : I64 = 2;
+                    let doubled : I64 = lowlevel NumMulWrap n two;
+                    ret doubled;
+
+                procedure Test.increment (n : I64) -> I64:
+                    let one : I64 = 1;
+                    let sum : I64 = lowlevel NumAdd n one;
+                    ret sum;
+
+                procedure Test.main (xs : List I64) -> List I64:
+                    let env : {} = Struct {};
+                    let ys : List I64 = ListMap xs Test.double env;
+                    let zs : List I64 = ListMap ys Test.increment env;
+                    ret zs;
+                "#
+            ),
+        );
+
+        // `Test.increment` panics on overflow, which must only happen after all elements were doubled
+        let printed = print_procs(&interner, home(), &ident_ids, &procs);
+
+        assert_eq!(printed.matches("ListLenU64").count(), 2, "{printed}");
+    }
+
+    #[test]
+    fn keep_map_that_captures_its_input() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = specialize(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.main (xs : List I64) -> List I64:
+                    let env : {} = Struct {};
+                    let ys : List I64 = ListMap xs Test.double env;
+                    let zs : List I64 = ListMap ys Test.addLength ys;
+                    ret zs;
+                "#
+            ),
+        );
+
+        // the second function needs all of `ys`, so it gets a loop of its own
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (xs : List I64) -> List I64:
+                    let env : {} = Struct {};
+                    joinpoint #31 (ys : List I64):
+                        joinpoint #17 (zs : List I64):
+                            ret zs;
+                        in
+                        let #7 : U64 = lowlevel ListLenU64 ys;
+                        let #9 : U64 = 0;
+                        joinpoint #18 (#10 : U64, #11 : List I64):
+                            let #12 : Int1 = lowlevel NumLt #10 #7;
+                            if #12 then
+                                let #13 : I64 = lowlevel ListGetUnsafe #11 #10;
+                                let #19 : I64 = CallByName Test.addLength #13 ys;
+                                let #20 : {List I64, I64} = lowlevel ListReplaceUnsafe #11 #10 #19;
+                                let #14 : List I64 = StructAtIndex 0 #20;
+                                let #15 : U64 = 1;
+                                let #16 : U64 = lowlevel NumAddWrap #10 #15;
+                                jump #18 #16 #14;
+                            else
+                                jump #17 #11;
+                        in
+                        jump #18 #9 ys;
+                    in
+                    let #21 : U64 = lowlevel ListLenU64 xs;
+                    let #23 : U64 = 0;
+                    joinpoint #32 (#24 : U64, #25 : List I64):
+                        let #26 : Int1 = lowlevel NumLt #24 #21;
+                        if #26 then
+                            let #27 : I64 = lowlevel ListGetUnsafe #25 #24;
+                            let #33 : I64 = CallByName Test.double #27;
+                            let #34 : {List I64, I64} = lowlevel ListReplaceUnsafe #25 #24 #33;
+                            let #28 : List I64 = StructAtIndex 0 #34;
+                            let #29 : U64 = 1;
+                            let #30 : U64 = lowlevel NumAddWrap #24 #29;
+                            jump #32 #30 #28;
+                        else
+                            jump #31 #25;
+                    in
+                    jump #32 #23 xs;
+                "#
+            ),
+        );
+    }
+}
@@ -10,10 +10,14 @@ use std::{collections::HashMap, hash::BuildHasherDefault};
 use bumpalo::collections::{CollectIn, Vec};
 use bumpalo::Bump;
 use roc_collections::{all::WyHash, MutMap, MutSet};
+use roc_debug_flags::dbg_do;
+#[cfg(debug_assertions)]
+use roc_debug_flags::ROC_PRINT_BORROW_SIGNATURES;
 use roc_error_macros::internal_error;
 use roc_module::low_level::LowLevel;
 use roc_module::{low_level::LowLevelWrapperType, symbol::Symbol};
 
+use crate::borrow::{infer_borrow_signatures, BorrowSignatures};
 use crate::ir::ErasedField;
 use crate::{
     ir::{
@@ -26,28 +30,91 @@ use crate::{
 
 /**
 Insert the reference count operations for procedures.
+If `infer_borrows` is set, parameters that are only read from are passed as borrowed.
+The procedures in `host_exposed` are called by the host, and always take their parameters as owned.
 */
 pub fn insert_inc_dec_operations<'a>(
     arena: &'a Bump,
     layout_interner: &STLayoutInterner<'a>,
-    procedures: &mut HashMap<(Symbol, ProcLayout), Proc<'a>, BuildHasherDefault<WyHash>>,
+    procedures: &mut HashMap<(Symbol, ProcLayout<'a>), Proc<'a>, BuildHasherDefault<WyHash>>,
+    infer_borrows: bool,
+    host_exposed: &[Symbol],
 ) {
+    let borrow_signatures = if infer_borrows {
+        infer_borrow_signatures(arena, layout_interner, procedures, host_exposed)
+    } else {
+        BorrowSignatures::default()
+    };
+
+    dbg_do!(ROC_PRINT_BORROW_SIGNATURES, {
+        eprintln!(
+            "{}",
+            format_borrow_signatures(&borrow_signatures, procedures)
+        );
+    });
+
     // All calls to lowlevels are wrapped in another function to help with type inference and return/parameter layouts.
     // But this lowlevel might get inlined into the caller of the wrapper and thus removing any reference counting operations.
     // Thus, these rc operations are performed on the caller of the wrapper instead, and we skip rc on the lowlevel.
     // It might be possible to inline the lowlevels at this point already,
     // but previous attempt conflicted as the parameters layouts and return layout do not match.
-    for ((symbol, _layout), proc) in procedures.iter_mut() {
+    for (key, proc) in procedures.iter_mut() {
         if matches!(
-            LowLevelWrapperType::from_symbol(*symbol),
+            LowLevelWrapperType::from_symbol(key.0),
             LowLevelWrapperType::NotALowLevelWrapper
         ) {
             let symbol_rc_types_env = SymbolRcTypesEnv::from_layout_interner(layout_interner);
-            insert_inc_dec_operations_proc(arena, symbol_rc_types_env, proc);
+            let borrow_signature = borrow_signatures.get(key).copied();
+            insert_inc_dec_operations_proc(
+                arena,
+                symbol_rc_types_env,
+                &borrow_signatures,
+                borrow_signature,
+                proc,
+            );
         }
     }
 }
 
+/// One line per procedure, listing the ownership of each of its parameters
+#[cfg(debug_assertions)]
+fn format_borrow_signatures<'a>(
+    borrow_signatures: &BorrowSignatures<'a>,
+    procedures: &HashMap<(Symbol, ProcLayout<'a>), Proc<'a>, BuildHasherDefault<WyHash>>,
+) -> String {
+    let mut lines: std::vec::Vec<String> = procedures
+        .iter()
+        .map(|(key, proc)| {
+            let parameters: std::vec::Vec<String> = match borrow_signatures.get(key) {
+                Some(signature) => proc
+                    .args
+                    .iter()
+                    .zip(signature.iter())
+                    .map(|((_, symbol), ownership)| {
+                        let ownership = match ownership {
+                            Ownership::Owned => "owned",
+                            Ownership::Borrowed => "borrowed",
+                        };
+
+                        format!("{symbol:?}: {ownership}")
+                    })
+                    .collect(),
+                // without borrow inference, every parameter is owned
+                None => proc
+                    .args
+                    .iter()
+                    .map(|(_, symbol)| format!("{symbol:?}: owned"))
+                    .collect(),
+            };
+
+            format!("procedure {:?} ({})", key.0, parameters.join(", "))
+        })
+        .collect();
+
+    lines.sort();
+    lines.join("\n")
+}
+
 /**
 Enum indicating whether a symbol should be reference counted or not.
 This includes layouts that themselves can be stack allocated but that contain a heap allocated item.
@@ -250,15 +317,19 @@ The environment for the reference counting pass.
 Contains the symbols rc types and the ownership.
 */
 #[derive(Clone)]
-struct RefcountEnvironment<'v> {
+struct RefcountEnvironment<'v, 'a> {
     // Keep track which symbols are reference counted and which are not.
     symbols_rc_types: &'v SymbolRcTypes,
     // The Koka implementation assumes everything that is not owned to be borrowed.
     symbols_ownership: SymbolsOwnership,
     jointpoint_closures: MutMap<JoinPointId, JoinPointConsumption>,
+    // The parameters of the current procedure that are passed as borrowed.
+    borrowed_parameters: &'v MutSet<Symbol>,
+    // The inferred borrow signatures of the procedures that can be called by name.
+    borrow_signatures: &'v BorrowSignatures<'a>,
 }
 
-impl<'v> RefcountEnvironment<'v> {
+impl<'v, 'a> RefcountEnvironment<'v, 'a> {
     /**
     Retrieve the rc type of a symbol.
     */
@@ -316,6 +387,21 @@ impl<'v> RefcountEnvironment<'v> {
         }
     }
 
+    /**
+    Add a borrowed parameter to the environment if it is reference counted.
+    The parameter is never owned, so it is incremented for every owned usage and never decremented.
+    */
+    fn add_borrowed_symbol(&mut self, symbol: Symbol) {
+        match self.get_symbol_rc_type(&symbol) {
+            VarRcType::ReferenceCounted => {
+                self.symbols_ownership.insert(symbol, Ownership::Borrowed);
+            }
+            VarRcType::NotReferenceCounted => {
+                // If this symbol is not reference counted, we don't need to do anything.
+            }
+        }
+    }
+
     /**
     Remove a symbol from the environment.
     Is used when a symbol is no longer in scope (before a let binding).
@@ -403,22 +489,40 @@ impl<'v> RefcountEnvironment<'v> {
 fn insert_inc_dec_operations_proc<'a>(
     arena: &'a Bump,
     mut symbol_rc_types_env: SymbolRcTypesEnv<'a, '_>,
+    borrow_signatures: &BorrowSignatures<'a>,
+    borrow_signature: Option<&[Ownership]>,
     proc: &mut Proc<'a>,
 ) {
     // Clone the symbol_rc_types_env and insert the symbols in the current procedure.
     // As the symbols should be limited in scope for the current proc.
     symbol_rc_types_env.insert_symbols_rc_type_proc(proc);
 
+    let borrowed_parameters = match borrow_signature {
+        Some(borrow_signature) => proc
+            .args
+            .iter()
+            .zip(borrow_signature.iter())
+            .filter_map(|((_layout, symbol), ownership)| ownership.is_borrowed().then_some(*symbol))
+            .collect(),
+        None => MutSet::default(),
+    };
+
     let mut environment = RefcountEnvironment {
         symbols_rc_types: &symbol_rc_types_env.symbols_rc_type,
         symbols_ownership: MutMap::default(),
         jointpoint_closures: MutMap::default(),
+        borrowed_parameters: &borrowed_parameters,
+        borrow_signatures,
     };
 
     // Add all arguments to the environment (if they are reference counted)
     let proc_symbols = proc.args.iter().map(|(_layout, symbol)| symbol);
     for symbol in proc_symbols.clone() {
-        environment.add_symbol(*symbol);
+        if borrowed_parameters.contains(symbol) {
+            environment.add_borrowed_symbol(*symbol);
+        } else {
+            environment.add_symbol(*symbol);
+        }
     }
 
     // Update the body with reference count statements.
@@ -450,7 +554,7 @@ Assuming that a symbol can only be defined once (no binding to the same symbol m
 */
 fn insert_refcount_operations_stmt<'v, 'a>(
     arena: &'a Bump,
-    environment: &mut RefcountEnvironment<'v>,
+    environment: &mut RefcountEnvironment<'v, 'a>,
     stmt: &Stmt<'a>,
 ) -> &'a Stmt<'a> {
     match &stmt {
@@ -563,7 +667,7 @@ fn insert_refcount_operations_stmt<'v, 'a>(
                         let consumed =
                             branch_envs
                                 .iter()
-                                .any(|branch_env: &&RefcountEnvironment<'v>| {
+                                .any(|branch_env: &&RefcountEnvironment<'v, 'a>| {
                                     match branch_env.get_symbol_ownership(symbol) {
                                         None => internal_error!(
                                             "symbol {symbol:?} in the current env should be in the branch's env"
@@ -635,9 +739,20 @@ fn insert_refcount_operations_stmt<'v, 'a>(
             })
         }
         Stmt::Ret(s) => {
-            let ownership = environment.consume_symbol(s);
-            debug_assert!(matches!(ownership, None | Some(Ownership::Owned))); // the return value should be owned or not reference counted at the return.
-            return arena.alloc(Stmt::Ret(*s));
+            let new_ret = arena.alloc(Stmt::Ret(*s));
+
+            match environment.get_symbol_ownership(s) {
+                // The return value should be owned or not reference counted at the return.
+                None | Some(Ownership::Owned) => {
+                    environment.consume_symbol(s);
+                    new_ret
+                }
+                // Unless it is a borrowed parameter, then the caller gets a new reference.
+                Some(Ownership::Borrowed) => {
+                    debug_assert!(environment.borrowed_parameters.contains(s));
+                    insert_inc_stmt(arena, *s, 1, new_ret)
+                }
+            }
         }
         Stmt::Refcounting(_, _) => unreachable!("refcounting should not be in the AST yet"),
         Stmt::Expect {
@@ -720,10 +835,12 @@ fn insert_refcount_operations_stmt<'v, 'a>(
         } => {
             // Assuming that the values in the closure of the body of this jointpoint are already bound.
             // Assuming that all symbols are still owned. (So that we can determine what symbols got consumed in the join point.)
+            // Borrowed parameters are never owned, jumps consuming them again has no effect.
             debug_assert!(environment
                 .symbols_ownership
                 .iter()
-                .all(|(_, ownership)| ownership.is_owned()));
+                .all(|(symbol, ownership)| ownership.is_owned()
+                    || environment.borrowed_parameters.contains(symbol)));
 
             let mut body_env = environment.clone();
 
@@ -855,7 +972,7 @@ fn insert_refcount_operations_stmt<'v, 'a>(
 
 fn insert_refcount_operations_binding<'a>(
     arena: &'a Bump,
-    environment: &mut RefcountEnvironment,
+    environment: &mut RefcountEnvironment<'_, 'a>,
     binding: &Symbol,
     expr: &Expr<'a>,
     layout: &InLayout<'a>,
@@ -891,6 +1008,23 @@ fn insert_refcount_operations_binding<'a>(
         };
     }
 
+    macro_rules! with_borrow_signature {
+        ($arguments:expr, $borrow_signature:expr) => {{
+            let arguments_with_borrow_signature = $arguments
+                .iter()
+                .copied()
+                .zip($borrow_signature.iter().copied());
+            let owned_arguments = arguments_with_borrow_signature
+                .clone()
+                .filter_map(|(symbol, ownership)| ownership.is_owned().then_some(symbol));
+            let borrowed_arguments = arguments_with_borrow_signature
+                .filter_map(|(symbol, ownership)| ownership.is_borrowed().then_some(symbol));
+            let new_stmt = dec_borrowed!(borrowed_arguments, stmt);
+            let new_let = new_let!(new_stmt);
+            inc_owned!(owned_arguments, new_let)
+        }};
+    }
+
     match expr {
         Expr::Literal(_)
         | Expr::NullPointer
@@ -980,10 +1114,33 @@ fn insert_refcount_operations_binding<'a>(
             match call_type.clone().replace_lowlevel_wrapper() {
                 // A by name call refers to a normal function call.
                 // Normal functions take all their parameters as owned, so we can mark them all as such.
-                CallType::ByName { .. } => {
-                    let new_let = new_let!(stmt);
+                // Unless borrow inference decided that some of the parameters can be borrowed.
+                CallType::ByName {
+                    name,
+                    ret_layout,
+                    arg_layouts,
+                    ..
+                } => {
+                    let proc_layout = ProcLayout {
+                        arguments: arg_layouts,
+                        result: ret_layout,
+                        niche: name.niche(),
+                    };
+
+                    let borrow_signatures = environment.borrow_signatures;
+
+                    match borrow_signatures.get(&(name.name(), proc_layout)) {
+                        Some(borrow_signature)
+                            if borrow_signature.iter().any(Ownership::is_borrowed) =>
+                        {
+                            with_borrow_signature!(arguments, borrow_signature)
+                        }
+                        _ => {
+                            let new_let = new_let!(stmt);
 
-                    inc_owned!(arguments.iter().copied(), new_let)
+                            inc_owned!(arguments.iter().copied(), new_let)
+                        }
+                    }
                 }
                 // A normal Roc function call, but we don't actually know where its target is.
                 // As such, we assume that it takes all parameters as owned, as will the function
@@ -1024,23 +1181,7 @@ fn insert_refcount_operations_binding<'a>(
                         new_let!(newer_stmt)
                     }
                     // Otherwise, perform regular reference counting using the lowlevel borrow signature.
-                    _ => {
-                        let borrow_signature = lowlevel_borrow_signature(operator);
-                        let arguments_with_borrow_signature = arguments
-                            .iter()
-                            .copied()
-                            .zip(borrow_signature.iter().copied());
-                        let owned_arguments = arguments_with_borrow_signature.clone().filter_map(
-                            |(symbol, ownership)| ownership.is_owned().then_some(symbol),
-                        );
-                        let borrowed_arguments =
-                            arguments_with_borrow_signature.filter_map(|(symbol, ownership)| {
-                                ownership.is_borrowed().then_some(symbol)
-                            });
-                        let new_stmt = dec_borrowed!(borrowed_arguments, stmt);
-                        let new_let = new_let!(new_stmt);
-                        inc_owned!(owned_arguments, new_let)
-                    }
+                    _ => with_borrow_signature!(arguments, lowlevel_borrow_signature(operator)),
                 },
                 CallType::HigherOrder(HigherOrderLowLevel {
                     op: operator,
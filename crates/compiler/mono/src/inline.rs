@@ -0,0 +1,1105 @@
+//! Inlining of small procedures into their callers.
+//!
+//! A call `let x = f a b; cont` to an inlinable `f` becomes
+//!
+//! ```text
+//! joinpoint j x =
+//!     cont
+//! in
+//! <body of f, with its parameters replaced by a and b, and every `ret` replaced by `jump j`>
+//! ```
+//!
+//! A call in tail position, `let x = f a b; ret x`, is replaced by the body of `f` as it is, so it
+//! keeps its tail calls.
+//!
+//! Callers are always given the *original* body of the callee, so inlining never recurses and
+//! mutually recursive procedures can't make this pass loop. A procedure that calls itself after
+//! inlining is marked as self-recursive.
+//!
+//! Afterwards, procedures that were inlined and can no longer be reached from the roots (the
+//! procedures the host or the test runner calls) are removed.
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::{MutMap, MutSet};
+use roc_module::low_level::LowLevelWrapperType;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+
+use crate::ir::{
+    BranchInfo, Call, CallSpecId, CallType, Expr, HigherOrderLowLevel, JoinPointId,
+    ListLiteralElement, Param, PassedFunction, Proc, ProcLayout, SelfRecursive, Stmt,
+    UpdateModeIds,
+};
+use crate::layout::InLayout;
+use crate::low_level::HigherOrder;
+
+/// Procedures with at most this many statements are inlined at every call site.
+const INLINE_THRESHOLD: usize = 12;
+
+/// Procedures that are called exactly once are inlined up to this many statements. The original
+/// is removed afterwards, so this doesn't grow the program, but it does grow the caller.
+const INLINE_ONCE_THRESHOLD: usize = 64;
+
+pub fn inline_procedures<'a>(
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &mut IdentIds,
+    update_mode_ids: &mut UpdateModeIds,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    roots: &[Symbol],
+) {
+    let candidates = inline_candidates(arena, procs);
+
+    if candidates.is_empty() {
+        return;
+    }
+
+    let mut env = Env {
+        arena,
+        home,
+        ident_ids,
+        update_mode_ids,
+        candidates: &candidates,
+        next_call_spec_id: CallSpecId::BACKEND_DUMMY,
+        inlined: false,
+        inlined_callees: MutSet::default(),
+    };
+
+    // Visit the procedures in a fixed order, so the generated symbols don't depend on the hash map
+    let mut keys: std::vec::Vec<_> = procs.keys().copied().collect();
+    keys.sort_by_key(|(symbol, _)| *symbol);
+
+    for key in keys {
+        let proc = procs.get_mut(&key).unwrap();
+
+        env.inlined = false;
+        env.next_call_spec_id = max_call_spec_id(&proc.body).next();
+
+        let new_body = env.inline_stmt(key.0, proc.ret_layout, &proc.body);
+
+        if env.inlined {
+            proc.body = new_body.clone();
+
+            // Inlining a mutually recursive procedure can make this one call itself. Tail
+            // recursion runs after inlining, and turns those calls into jumps if they are tail
+            // calls.
+            if matches!(proc.is_self_recursive, SelfRecursive::NotSelfRecursive)
+                && calls_itself(key, &proc.body)
+            {
+                proc.is_self_recursive =
+                    SelfRecursive::SelfRecursive(JoinPointId(env.unique_symbol()));
+            }
+        }
+    }
+
+    remove_unreachable(procs, roots, &env.inlined_callees);
+}
+
+/// Remove the inlined procedures that can't be reached from the roots anymore
+fn remove_unreachable<'a>(
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    roots: &[Symbol],
+    inlined_callees: &MutSet<(Symbol, ProcLayout<'a>)>,
+) {
+    // A function pointer only names a symbol, so reachability is tracked per symbol rather than
+    // per specialization.
+    let mut bodies: MutMap<Symbol, std::vec::Vec<&Stmt<'a>>> = MutMap::default();
+    for ((symbol, _), proc) in procs.iter() {
+        bodies.entry(*symbol).or_default().push(&proc.body);
+    }
+
+    let mut reachable: MutSet<Symbol> = roots.iter().copied().collect();
+    let mut stack = roots.to_vec();
+
+    while let Some(symbol) = stack.pop() {
+        for body in bodies.get(&symbol).into_iter().flatten() {
+            for_each_referenced_proc(body, |callee| {
+                if reachable.insert(callee) {
+                    stack.push(callee);
+                }
+            });
+        }
+    }
+
+    let unreachable: std::vec::Vec<_> = inlined_callees
+        .iter()
+        .filter(|(symbol, _)| !reachable.contains(symbol))
+        .copied()
+        .collect();
+
+    for key in unreachable {
+        procs.remove(&key);
+    }
+}
+
+/// Call `f` on every procedure that `stmt` calls or takes a pointer to
+fn for_each_referenced_proc(stmt: &Stmt<'_>, mut f: impl FnMut(Symbol)) {
+    for_each_stmt(stmt, |stmt| {
+        if let Stmt::Let(_, expr, _, _) = stmt {
+            match expr {
+                Expr::Call(call) => match &call.call_type {
+                    CallType::ByName { name, .. } => f(name.name()),
+                    CallType::HigherOrder(higher_order) => {
+                        f(higher_order.passed_function.name.name())
+                    }
+                    CallType::ByPointer { .. }
+                    | CallType::Foreign { .. }
+                    | CallType::LowLevel { .. } => {}
+                },
+                Expr::FunctionPointer { lambda_name } => f(lambda_name.name()),
+                Expr::ErasedMake { callee, .. } => f(*callee),
+                _ => {}
+            }
+        }
+    });
+}
+
+struct Candidate<'a> {
+    args: &'a [(InLayout<'a>, Symbol)],
+    body: &'a Stmt<'a>,
+}
+
+type Candidates<'a> = MutMap<(Symbol, ProcLayout<'a>), Candidate<'a>>;
+
+fn inline_candidates<'a>(
+    arena: &'a Bump,
+    procs: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) -> Candidates<'a> {
+    let mut call_counts: MutMap<(Symbol, ProcLayout<'a>), usize> = MutMap::default();
+    let mut sizes: MutMap<(Symbol, ProcLayout<'a>), usize> = MutMap::default();
+
+    for (key, proc) in procs.iter() {
+        let mut size = 0;
+        let mut inlinable = true;
+
+        for_each_stmt(&proc.body, |stmt| {
+            size += 1;
+
+            match stmt {
+                Stmt::Let(_, Expr::Call(call), _, _) => {
+                    if let Some(callee) = by_name_callee(call) {
+                        // Inlining a procedure into itself would only unroll it once.
+                        inlinable &= callee.0 != key.0;
+                        *call_counts.entry(callee).or_default() += 1;
+                    }
+                }
+                // The variables of expects and dbgs belong to the subs of
+                // the module they are defined in.
+                Stmt::Expect { .. } | Stmt::ExpectFx { .. } | Stmt::Dbg { .. } => {
+                    inlinable = false;
+                }
+                _ => {}
+            }
+        });
+
+        let inlinable = inlinable
+            && !proc.is_erased
+            && matches!(proc.is_self_recursive, SelfRecursive::NotSelfRecursive)
+            && matches!(
+                LowLevelWrapperType::from_symbol(key.0),
+                LowLevelWrapperType::NotALowLevelWrapper
+            );
+
+        if inlinable {
+            sizes.insert(*key, size);
+        }
+    }
+
+    sizes
+        .into_iter()
+        .filter(|(key, size)| {
+            *size <= INLINE_THRESHOLD
+                || (*size <= INLINE_ONCE_THRESHOLD && call_counts.get(key).copied() == Some(1))
+        })
+        .map(|(key, _)| {
+            let proc = &procs[&key];
+            let candidate = Candidate {
+                args: proc.args,
+                body: arena.alloc(proc.body.clone()),
+            };
+
+            (key, candidate)
+        })
+        .collect()
+}
+
+fn calls_itself<'a>(key: (Symbol, ProcLayout<'a>), body: &Stmt<'a>) -> bool {
+    let mut calls_itself = false;
+
+    for_each_stmt(body, |stmt| {
+        if let Stmt::Let(_, Expr::Call(call), _, _) = stmt {
+            calls_itself |= by_name_callee(call) == Some(key);
+        }
+    });
+
+    calls_itself
+}
+
+fn by_name_callee<'a>(call: &Call<'a>) -> Option<(Symbol, ProcLayout<'a>)> {
+    match call.call_type.clone().replace_lowlevel_wrapper() {
+        CallType::ByName {
+            name,
+            ret_layout,
+            arg_layouts,
+            ..
+        } => Some((
+            name.name(),
+            ProcLayout {
+                arguments: arg_layouts,
+                result: ret_layout,
+                niche: name.niche(),
+            },
+        )),
+        _ => None,
+    }
+}
+
+/// Call `f` on every statement, including every let binding in a chain of lets.
+pub(crate) fn for_each_stmt<'b, 'a>(stmt: &'b Stmt<'a>, mut f: impl FnMut(&'b Stmt<'a>)) {
+    let mut stack = vec![stmt];
+
+    while let Some(stmt) = stack.pop() {
+        f(stmt);
+
+        match stmt {
+            Stmt::Let(_, _, _, cont) | Stmt::Refcounting(_, cont) => stack.push(cont),
+            Stmt::Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                stack.push(default_branch.1);
+            }
+            Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. }
+            | Stmt::Dbg { remainder, .. } => stack.push(remainder),
+            Stmt::Join {
+                body, remainder, ..
+            } => {
+                stack.push(body);
+                stack.push(remainder);
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => {}
+        }
+    }
+}
+
+fn max_call_spec_id(stmt: &Stmt<'_>) -> CallSpecId {
+    let mut max = CallSpecId::BACKEND_DUMMY;
+
+    for_each_stmt(stmt, |stmt| {
+        if let Stmt::Let(_, Expr::Call(call), _, _) = stmt {
+            match &call.call_type {
+                CallType::ByName {
+                    specialization_id, ..
+                } => max = max.max(*specialization_id),
+                CallType::HigherOrder(higher_order) => {
+                    max = max.max(higher_order.passed_function.specialization_id)
+                }
+                _ => {}
+            }
+        }
+    });
+
+    max
+}
+
+struct Env<'a, 'i> {
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    update_mode_ids: &'i mut UpdateModeIds,
+    candidates: &'i Candidates<'a>,
+    /// Call specialization ids must be unique within a procedure,
+    /// so the calls in an inlined body get fresh ones.
+    next_call_spec_id: CallSpecId,
+    /// Whether anything was inlined into the current procedure
+    inlined: bool,
+    /// Every procedure that was inlined somewhere
+    inlined_callees: MutSet<(Symbol, ProcLayout<'a>)>,
+}
+
+/// The renaming of the symbols and join points of one inlined body.
+#[derive(Default)]
+struct Renaming {
+    symbols: MutMap<Symbol, Symbol>,
+    join_points: MutMap<JoinPointId, JoinPointId>,
+}
+
+impl Renaming {
+    fn symbol(&self, symbol: Symbol) -> Symbol {
+        self.symbols.get(&symbol).copied().unwrap_or(symbol)
+    }
+
+    fn symbols<'a>(&self, arena: &'a Bump, symbols: &[Symbol]) -> &'a [Symbol] {
+        Vec::from_iter_in(symbols.iter().map(|s| self.symbol(*s)), arena).into_bump_slice()
+    }
+}
+
+impl<'a, 'i> Env<'a, 'i> {
+    fn unique_symbol(&mut self) -> Symbol {
+        let ident_id = self.ident_ids.gen_unique();
+
+        Symbol::new(self.home, ident_id)
+    }
+
+    fn next_call_spec_id(&mut self) -> CallSpecId {
+        let id = self.next_call_spec_id;
+        self.next_call_spec_id = id.next();
+        id
+    }
+
+    fn inline_stmt(
+        &mut self,
+        caller: Symbol,
+        ret_layout: InLayout<'a>,
+        stmt: &Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        let arena = self.arena;
+        let candidates = self.candidates;
+
+        match stmt {
+            Stmt::Let(_, _, _, _) => {
+                // Collect all the subsequent let bindings,
+                // to prevent the stack from overflowing when there are many let bindings.
+                let mut triples = vec![];
+                let mut current_stmt = stmt;
+                while let Stmt::Let(binding, expr, layout, next_stmt) = current_stmt {
+                    triples.push((binding, expr, layout));
+                    current_stmt = next_stmt;
+                }
+
+                let cont = self.inline_stmt(caller, ret_layout, current_stmt);
+
+                triples
+                    .into_iter()
+                    .rev()
+                    .fold(cont, |cont, (binding, expr, layout)| {
+                        let callee = match expr {
+                            Expr::Call(call) => by_name_callee(call)
+                                .filter(|callee| {
+                                    callee.0 != caller && candidates.contains_key(callee)
+                                })
+                                .map(|callee| (callee, call.arguments)),
+                            _ => None,
+                        };
+
+                        match callee {
+                            Some((callee, arguments)) => {
+                                self.inlined_callees.insert(callee);
+
+                                self.inline_call(
+                                    &candidates[&callee],
+                                    arguments,
+                                    *binding,
+                                    *layout,
+                                    ret_layout,
+                                    cont,
+                                )
+                            }
+                            None => arena.alloc(Stmt::Let(*binding, expr.clone(), *layout, cont)),
+                        }
+                    })
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout: switch_ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        let branch = self.inline_stmt(caller, ret_layout, branch);
+                        (*label, info.clone(), branch.clone())
+                    }),
+                    arena,
+                )
+                .into_bump_slice();
+
+                let default_branch = (
+                    default_branch.0.clone(),
+                    self.inline_stmt(caller, ret_layout, default_branch.1),
+                );
+
+                arena.alloc(Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches,
+                    default_branch,
+                    ret_layout: *switch_ret_layout,
+                })
+            }
+            Stmt::Refcounting(modify, cont) => {
+                let cont = self.inline_stmt(caller, ret_layout, cont);
+                arena.alloc(Stmt::Refcounting(*modify, cont))
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.inline_stmt(caller, ret_layout, remainder),
+            }),
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Stmt::ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.inline_stmt(caller, ret_layout, remainder),
+            }),
+            Stmt::Dbg {
+                source_location,
+                source,
+                symbol,
+                variable,
+                remainder,
+            } => arena.alloc(Stmt::Dbg {
+                source_location: *source_location,
+                source: *source,
+                symbol: *symbol,
+                variable: *variable,
+                remainder: self.inline_stmt(caller, ret_layout, remainder),
+            }),
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let body = self.inline_stmt(caller, ret_layout, body);
+                let remainder = self.inline_stmt(caller, ret_layout, remainder);
+
+                arena.alloc(Stmt::Join {
+                    id: *id,
+                    parameters: *parameters,
+                    body,
+                    remainder,
+                })
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => arena.alloc(stmt.clone()),
+        }
+    }
+
+    fn inline_call(
+        &mut self,
+        candidate: &Candidate<'a>,
+        arguments: &[Symbol],
+        binding: Symbol,
+        layout: InLayout<'a>,
+        ret_layout: InLayout<'a>,
+        cont: &'a Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        self.inlined = true;
+
+        let mut renaming = Renaming::default();
+        for ((_, parameter), argument) in candidate.args.iter().zip(arguments.iter()) {
+            renaming.symbols.insert(*parameter, *argument);
+        }
+
+        // a call in tail position keeps the returns of the callee, so it stays a tail call
+        if matches!(cont, Stmt::Ret(symbol) if *symbol == binding) {
+            return self.copy_stmt(&mut renaming, None, ret_layout, candidate.body);
+        }
+
+        let return_to = JoinPointId(self.unique_symbol());
+        let remainder = self.copy_stmt(&mut renaming, Some(return_to), ret_layout, candidate.body);
+
+        self.arena.alloc(Stmt::Join {
+            id: return_to,
+            parameters: self.arena.alloc([Param {
+                symbol: binding,
+                layout,
+            }]),
+            body: cont,
+            remainder,
+        })
+    }
+
+    /// Copy the body of an inlined procedure, giving every binding a fresh name and jumping to
+    /// `return_to` (if any) instead of returning.
+    fn copy_stmt(
+        &mut self,
+        renaming: &mut Renaming,
+        return_to: Option<JoinPointId>,
+        ret_layout: InLayout<'a>,
+        stmt: &Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        let arena = self.arena;
+
+        match stmt {
+            Stmt::Let(_, _, _, _) => {
+                let mut triples = vec![];
+                let mut current_stmt = stmt;
+                while let Stmt::Let(binding, expr, layout, next_stmt) = current_stmt {
+                    let expr = self.copy_expr(renaming, expr);
+                    let new_binding = self.unique_symbol();
+                    renaming.symbols.insert(*binding, new_binding);
+
+                    triples.push((new_binding, expr, *layout));
+                    current_stmt = next_stmt;
+                }
+
+                let cont = self.copy_stmt(renaming, return_to, ret_layout, current_stmt);
+
+                triples
+                    .into_iter()
+                    .rev()
+                    .fold(cont, |cont, (binding, expr, layout)| {
+                        arena.alloc(Stmt::Let(binding, expr, layout, cont))
+                    })
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout: _,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        let branch = self.copy_stmt(renaming, return_to, ret_layout, branch);
+                        (*label, copy_branch_info(renaming, info), branch.clone())
+                    }),
+                    arena,
+                )
+                .into_bump_slice();
+
+                let default_branch = (
+                    copy_branch_info(renaming, &default_branch.0),
+                    self.copy_stmt(renaming, return_to, ret_layout, default_branch.1),
+                );
+
+                arena.alloc(Stmt::Switch {
+                    cond_symbol: renaming.symbol(*cond_symbol),
+                    cond_layout: *cond_layout,
+                    branches,
+                    default_branch,
+                    // the branches now end in a jump within the caller
+                    ret_layout,
+                })
+            }
+            Stmt::Ret(symbol) => {
+                let symbol = renaming.symbol(*symbol);
+
+                match return_to {
+                    Some(return_to) => arena.alloc(Stmt::Jump(return_to, arena.alloc([symbol]))),
+                    None => arena.alloc(Stmt::Ret(symbol)),
+                }
+            }
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let new_id = JoinPointId(self.unique_symbol());
+                renaming.join_points.insert(*id, new_id);
+
+                let parameters = Vec::from_iter_in(
+                    parameters.iter().map(|param| {
+                        let symbol = self.unique_symbol();
+                        renaming.symbols.insert(param.symbol, symbol);
+
+                        Param {
+                            symbol,
+                            layout: param.layout,
+                        }
+                    }),
+                    arena,
+                )
+                .into_bump_slice();
+
+                let body = self.copy_stmt(renaming, return_to, ret_layout, body);
+                let remainder = self.copy_stmt(renaming, return_to, ret_layout, remainder);
+
+                arena.alloc(Stmt::Join {
+                    id: new_id,
+                    parameters,
+                    body,
+                    remainder,
+                })
+            }
+            Stmt::Jump(id, arguments) => arena.alloc(Stmt::Jump(
+                renaming.join_points[id],
+                renaming.symbols(arena, arguments),
+            )),
+            Stmt::Crash(symbol, tag) => arena.alloc(Stmt::Crash(renaming.symbol(*symbol), *tag)),
+            Stmt::Refcounting(_, _) => unreachable!("refcounting should not be in the AST yet"),
+            Stmt::Expect { .. } | Stmt::ExpectFx { .. } | Stmt::Dbg { .. } => {
+                unreachable!("procedures with expects are not inlined")
+            }
+        }
+    }
+
+    fn copy_expr(&mut self, renaming: &Renaming, expr: &Expr<'a>) -> Expr<'a> {
+        let arena = self.arena;
+
+        match expr {
+            Expr::Literal(_)
+            | Expr::NullPointer
+            | Expr::EmptyArray
+            | Expr::FunctionPointer { .. }
+            | Expr::RuntimeErrorFunction(_) => expr.clone(),
+            Expr::Call(call) => Expr::Call(self.copy_call(renaming, call)),
+            Expr::Tag {
+                tag_layout,
+                tag_id,
+                arguments,
+                reuse,
+            } => {
+                debug_assert!(reuse.is_none(), "reuse should not be in the AST yet");
+
+                Expr::Tag {
+                    tag_layout: *tag_layout,
+                    tag_id: *tag_id,
+                    arguments: renaming.symbols(arena, arguments),
+                    reuse: None,
+                }
+            }
+            Expr::Struct(arguments) => Expr::Struct(renaming.symbols(arena, arguments)),
+            Expr::StructAtIndex {
+                index,
+                field_layouts,
+                structure,
+            } => Expr::StructAtIndex {
+                index: *index,
+                field_layouts: *field_layouts,
+                structure: renaming.symbol(*structure),
+            },
+            Expr::GetTagId {
+                structure,
+                union_layout,
+            } => Expr::GetTagId {
+                structure: renaming.symbol(*structure),
+                union_layout: *union_layout,
+            },
+            Expr::UnionAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => Expr::UnionAtIndex {
+                structure: renaming.symbol(*structure),
+                tag_id: *tag_id,
+                union_layout: *union_layout,
+                index: *index,
+            },
+            Expr::GetElementPointer {
+                structure,
+                union_layout,
+                indices,
+            } => Expr::GetElementPointer {
+                structure: renaming.symbol(*structure),
+                union_layout: *union_layout,
+                indices: *indices,
+            },
+            Expr::Array { elem_layout, elems } => Expr::Array {
+                elem_layout: *elem_layout,
+                elems: Vec::from_iter_in(
+                    elems.iter().map(|element| match element {
+                        ListLiteralElement::Literal(_) => *element,
+                        ListLiteralElement::Symbol(symbol) => {
+                            ListLiteralElement::Symbol(renaming.symbol(*symbol))
+                        }
+                    }),
+                    arena,
+                )
+                .into_bump_slice(),
+            },
+            Expr::ErasedMake { value, callee } => Expr::ErasedMake {
+                value: value.map(|value| renaming.symbol(value)),
+                callee: renaming.symbol(*callee),
+            },
+            Expr::ErasedLoad { symbol, field } => Expr::ErasedLoad {
+                symbol: renaming.symbol(*symbol),
+                field: *field,
+            },
+            Expr::Alloca {
+                element_layout,
+                initializer,
+            } => Expr::Alloca {
+                element_layout: *element_layout,
+                initializer: initializer.map(|initializer| renaming.symbol(initializer)),
+            },
+            Expr::Reset { .. } | Expr::ResetRef { .. } => {
+                unreachable!("reset(ref) has not been introduced yet")
+            }
+        }
+    }
+
+    fn copy_call(&mut self, renaming: &Renaming, call: &Call<'a>) -> Call<'a> {
+        let call_type = match &call.call_type {
+            CallType::ByName {
+                name,
+                ret_layout,
+                arg_layouts,
+                specialization_id: _,
+            } => CallType::ByName {
+                name: *name,
+                ret_layout: *ret_layout,
+                arg_layouts: *arg_layouts,
+                specialization_id: self.next_call_spec_id(),
+            },
+            CallType::ByPointer {
+                pointer,
+                ret_layout,
+                arg_layouts,
+            } => CallType::ByPointer {
+                pointer: renaming.symbol(*pointer),
+                ret_layout: *ret_layout,
+                arg_layouts: *arg_layouts,
+            },
+            CallType::Foreign { .. } => call.call_type.clone(),
+            CallType::LowLevel { op, update_mode: _ } => CallType::LowLevel {
+                op: *op,
+                update_mode: self.update_mode_ids.next_id(),
+            },
+            CallType::HigherOrder(higher_order) => {
+                let HigherOrderLowLevel {
+                    op,
+                    closure_env_layout,
+                    update_mode: _,
+                    passed_function,
+                } = higher_order;
+
+                let op = match *op {
+                    HigherOrder::ListMap { xs } => HigherOrder::ListMap {
+                        xs: renaming.symbol(xs),
+                    },
+                    HigherOrder::ListMap2 { xs, ys } => HigherOrder::ListMap2 {
+                        xs: renaming.symbol(xs),
+                        ys: renaming.symbol(ys),
+                    },
+                    HigherOrder::ListMap3 { xs, ys, zs } => HigherOrder::ListMap3 {
+                        xs: renaming.symbol(xs),
+                        ys: renaming.symbol(ys),
+                        zs: renaming.symbol(zs),
+                    },
+                    HigherOrder::ListMap4 { xs, ys, zs, ws } => HigherOrder::ListMap4 {
+                        xs: renaming.symbol(xs),
+                        ys: renaming.symbol(ys),
+                        zs: renaming.symbol(zs),
+                        ws: renaming.symbol(ws),
+                    },
+                    HigherOrder::ListSortWith { xs } => HigherOrder::ListSortWith {
+                        xs: renaming.symbol(xs),
+                    },
+                };
+
+                let passed_function = PassedFunction {
+                    captured_environment: renaming.symbol(passed_function.captured_environment),
+                    specialization_id: self.next_call_spec_id(),
+                    ..*passed_function
+                };
+
+                CallType::HigherOrder(self.arena.alloc(HigherOrderLowLevel {
+                    op,
+                    closure_env_layout: *closure_env_layout,
+                    update_mode: self.update_mode_ids.next_id(),
+                    passed_function,
+                }))
+            }
+        };
+
+        Call {
+            call_type,
+            arguments: renaming.symbols(self.arena, call.arguments),
+        }
+    }
+}
+
+fn copy_branch_info<'a>(renaming: &Renaming, info: &BranchInfo<'a>) -> BranchInfo<'a> {
+    match info {
+        BranchInfo::None => BranchInfo::None,
+        BranchInfo::Constructor {
+            scrutinee,
+            layout,
+            tag_id,
+        } => BranchInfo::Constructor {
+            scrutinee: renaming.symbol(*scrutinee),
+            layout: *layout,
+            tag_id: *tag_id,
+        },
+        BranchInfo::List { scrutinee, len } => BranchInfo::List {
+            scrutinee: renaming.symbol(*scrutinee),
+            len: *len,
+        },
+        BranchInfo::Unique { scrutinee, unique } => BranchInfo::Unique {
+            scrutinee: renaming.symbol(*scrutinee),
+            unique: *unique,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::debug::print_procs;
+    use crate::debug::testing::{assert_procs, home, parse};
+    use crate::layout::STLayoutInterner;
+    use indoc::indoc;
+    use roc_target::Target;
+
+    /// Inline into the procedures of `source`, with `Test.main` as the only root
+    fn inline<'a>(
+        arena: &'a Bump,
+        interner: &mut STLayoutInterner<'a>,
+        ident_ids: &mut IdentIds,
+        source: &str,
+    ) -> MutMap<(Symbol, ProcLayout<'a>), Proc<'a>> {
+        let mut procs = parse(arena, interner, ident_ids, source);
+        let main = Symbol::new(home(), ident_ids.get_id("Test.main").unwrap());
+
+        inline_procedures(
+            arena,
+            home(),
+            ident_ids,
+            &mut UpdateModeIds::new(),
+            &mut procs,
+            &[main],
+        );
+
+        procs
+    }
+
+    /// A procedure of `lets + 1` statements, called once from `Test.main`
+    fn long_procedure(lets: usize) -> String {
+        let mut source = String::from("procedure Test.long (x : I64) -> I64:\n");
+        source.push_str("    let s0 : I64 = lowlevel NumAdd x x;\n");
+
+        for i in 1..lets {
+            source.push_str(&format!(
+                "    let s{i} : I64 = lowlevel NumAdd s{} x;\n",
+                i - 1
+            ));
+        }
+
+        source.push_str(&format!("    ret s{};\n", lets - 1));
+        source.push_str(indoc!(
+            r#"
+
+            procedure Test.main (y : I64) -> I64:
+                let r : I64 = CallByName Test.long y;
+                ret r;
+            "#
+        ));
+
+        source
+    }
+
+    #[test]
+    fn inline_small_procedure() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = inline(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.double (n : I64) -> I64:
+                    let sum : I64 = lowlevel NumAdd n n;
+                    ret sum;
+
+                procedure Test.main (x : I64) -> I64:
+                    let y : I64 = CallByName Test.double x;
+                    let z : I64 = CallByName Test.double y;
+                    ret z;
+                "#
+            ),
+        );
+
+        // `Test.double` is not called anymore, so it is removed. The second call is in tail
+        // position, so it needs no join point.
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.main (x : I64) -> I64:
+                    joinpoint #8 (y : I64):
+                        let #7 : I64 = lowlevel NumAdd y y;
+                        ret #7;
+                    in
+                    let #9 : I64 = lowlevel NumAdd x x;
+                    jump #8 #9;
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn keep_self_recursive_procedure() {
+        let source = indoc!(
+            r#"
+            procedure Test.countdown (n : I64) -> I64:
+                let zero : I64 = 0;
+                let done : Int1 = lowlevel Eq n zero;
+                if done then
+                    ret n;
+                else
+                    let one : I64 = 1;
+                    let m : I64 = lowlevel NumSub n one;
+                    let r : I64 = CallByName Test.countdown m;
+                    ret r;
+
+            procedure Test.main (x : I64) -> I64:
+                let y : I64 = CallByName Test.countdown x;
+                ret y;
+            "#
+        );
+
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = inline(&arena, &mut interner, &mut ident_ids, source);
+
+        assert_procs(&arena, &mut interner, &mut ident_ids, &procs, source);
+    }
+
+    #[test]
+    fn inline_mutually_recursive_procedures() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let procs = inline(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            indoc!(
+                r#"
+                procedure Test.isEven (a : I64) -> Int1:
+                    let zero : I64 = 0;
+                    let aIsZero : Int1 = lowlevel Eq a zero;
+                    if aIsZero then
+                        let yes : Int1 = true;
+                        ret yes;
+                    else
+                        let one : I64 = 1;
+                        let b : I64 = lowlevel NumSub a one;
+                        let odd : Int1 = CallByName Test.isOdd b;
+                        ret odd;
+
+                procedure Test.isOdd (c : I64) -> Int1:
+                    let zero : I64 = 0;
+                    let cIsZero : Int1 = lowlevel Eq c zero;
+                    if cIsZero then
+                        let no : Int1 = false;
+                        ret no;
+                    else
+                        let one : I64 = 1;
+                        let d : I64 = lowlevel NumSub c one;
+                        let even : Int1 = CallByName Test.isEven d;
+                        ret even;
+
+                procedure Test.main (x : I64) -> Int1:
+                    let r : Int1 = CallByName Test.isEven x;
+                    ret r;
+                "#
+            ),
+        );
+
+        // Each procedure gets the original body of the other one, so inlining stops after one
+        // step. `Test.main` now calls `Test.isOdd`, which calls itself, and nothing calls
+        // `Test.isEven` anymore.
+        assert_procs(
+            &arena,
+            &mut interner,
+            &mut ident_ids,
+            &procs,
+            indoc!(
+                r#"
+                procedure Test.isOdd (c : I64) -> Int1:
+                    let zero : I64 = 0;
+                    let cIsZero : Int1 = lowlevel Eq c zero;
+                    if cIsZero then
+                        let no : Int1 = false;
+                        ret no;
+                    else
+                        let one : I64 = 1;
+                        let d : I64 = lowlevel NumSub c one;
+                        let #24 : I64 = 0;
+                        let #25 : Int1 = lowlevel Eq d #24;
+                        if #25 then
+                            let #26 : Int1 = true;
+                            ret #26;
+                        else
+                            let #27 : I64 = 1;
+                            let #28 : I64 = lowlevel NumSub d #27;
+                            let #29 : Int1 = CallByName Test.isOdd #28;
+                            ret #29;
+
+                procedure Test.main (x : I64) -> Int1:
+                    let #31 : I64 = 0;
+                    let #32 : Int1 = lowlevel Eq x #31;
+                    if #32 then
+                        let #33 : Int1 = true;
+                        ret #33;
+                    else
+                        let #34 : I64 = 1;
+                        let #35 : I64 = lowlevel NumSub x #34;
+                        let #36 : Int1 = CallByName Test.isOdd #35;
+                        ret #36;
+                "#
+            ),
+        );
+
+        // so tail recursion can turn its tail call into a jump
+        let is_self_recursive = |name| {
+            let symbol = Symbol::new(home(), ident_ids.get_id(name).unwrap());
+            let (_, proc) = procs.iter().find(|((s, _), _)| *s == symbol).unwrap();
+
+            matches!(proc.is_self_recursive, SelfRecursive::SelfRecursive(_))
+        };
+
+        assert!(is_self_recursive("Test.isOdd"));
+        assert!(!is_self_recursive("Test.main"));
+    }
+
+    #[test]
+    fn inline_procedure_called_once() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let source = long_procedure(INLINE_THRESHOLD * 2);
+        let procs = inline(&arena, &mut interner, &mut ident_ids, &source);
+
+        let printed = print_procs(&interner, home(), &ident_ids, &procs);
+
+        assert_eq!(procs.len(), 1, "{printed}");
+        assert!(!printed.contains("CallByName"), "{printed}");
+    }
+
+    #[test]
+    fn keep_large_procedure_called_once() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let mut ident_ids = IdentIds::default();
+
+        let source = long_procedure(INLINE_ONCE_THRESHOLD);
+        let procs = inline(&arena, &mut interner, &mut ident_ids, &source);
+
+        assert_procs(&arena, &mut interner, &mut ident_ids, &procs, &source);
+    }
+}
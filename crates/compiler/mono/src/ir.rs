@@ -13,7 +13,7 @@ use bumpalo::Bump;
 use roc_can::abilities::SpecializationId;
 use roc_can::expr::{AnnotatedMark, ClosureData, ExpectLookup};
 use roc_can::module::ExposedByModule;
-use roc_collections::all::{default_hasher, BumpMap, BumpMapDefault, MutMap};
+use roc_collections::all::{default_hasher, BumpMap, BumpMapDefault, MutMap, MutSet};
 use roc_collections::VecMap;
 use roc_debug_flags::dbg_do;
 #[cfg(debug_assertions)]
@@ -130,6 +130,13 @@ pub enum OptLevel {
     Optimize,
 }
 
+impl OptLevel {
+    /// Whether to run the optimizations on the mono IR, e.g. borrow inference
+    pub fn optimize_mono_ir(self) -> bool {
+        matches!(self, OptLevel::Size | OptLevel::Optimize)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct SingleEntryPoint<'a> {
     pub symbol: Symbol,
@@ -396,6 +403,14 @@ impl<'a> Proc<'a> {
         w.push(b'\n');
         String::from_utf8(w).unwrap()
     }
+
+    /// The number of statements in the body, counting every let binding in a chain of lets
+    pub fn size(&self) -> usize {
+        let mut size = 0;
+        crate::inline::for_each_stmt(&self.body, |_| size += 1);
+
+        size
+    }
 }
 
 /// A host-exposed function must be specialized; it's a seed for subsequent specializations
@@ -1725,7 +1740,7 @@ impl<'a> Call<'a> {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CallSpecId {
     id: u32,
 }
@@ -1735,6 +1750,11 @@ impl CallSpecId {
         self.id.to_ne_bytes()
     }
 
+    /// The id that follows this one, for passes that create calls after specialization
+    pub(crate) fn next(self) -> Self {
+        Self { id: self.id + 1 }
+    }
+
     /// Dummy value for generating refcount helper procs in the backends
     /// This happens *after* specialization so it's safe
     pub const BACKEND_DUMMY: Self = Self { id: 0 };
@@ -3164,16 +3184,27 @@ fn specialize_external_specializations<'a>(
 
     let offset_variable = StorageSubs::merge_into(store, env.subs);
 
+    // Several modules often request the same specialization.
+    let mut requested = MutSet::default();
+
     for (symbol, solved_types) in it {
         for store_variable in solved_types {
             let imported_variable = offset_variable(store_variable);
 
             roc_tracing::debug!(proc_name = ?symbol, ?store_variable, ?imported_variable, "specializing needed external");
 
-            // historical note: we used to deduplicate with a hash here,
-            // but the cost of that hash is very high. So for now we make
-            // duplicate specializations, and the insertion into a hash map
-            // below will deduplicate them.
+            // historical note: we used to deduplicate with a hash of the solved types here,
+            // but the cost of that hash is very high. The layout is cached per variable,
+            // so instead we skip specializations whose layout we have already seen.
+            if let Ok(raw) = layout_cache.raw_from_var(env.arena, imported_variable, env.subs) {
+                let top_level = ProcLayout::from_raw_named(env.arena, symbol, raw);
+
+                if !requested.insert((symbol.name(), top_level))
+                    || procs.specialized.is_specialized(symbol.name(), &top_level)
+                {
+                    continue;
+                }
+            }
 
             specialize_external_help(env, procs, layout_cache, symbol, imported_variable);
         }
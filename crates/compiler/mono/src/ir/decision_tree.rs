@@ -2238,6 +2238,9 @@ fn decide_to_branching<'a>(
             let mut tag_id_sum: i64 = (0..tests.len() as i64 + 1).sum();
             let mut union_size: i64 = -1;
 
+            // the integer each test compares with, if they all compare with a small integer
+            let mut int_values = Some(Vec::with_capacity(tests.len()));
+
             for (test, decider) in tests {
                 let branch = decide_to_branching(
                     env,
@@ -2250,6 +2253,18 @@ fn decide_to_branching<'a>(
                     jumps,
                 );
 
+                let int_value = match test {
+                    Test::IsInt(v, width) if !matches!(width, IntWidth::U128 | IntWidth::I128) => {
+                        Some(i128::from_ne_bytes(v))
+                    }
+                    _ => None,
+                };
+
+                int_values = int_values.zip(int_value).map(|(mut values, value)| {
+                    values.push(value);
+                    values
+                });
+
                 let tag = match test {
                     Test::IsInt(v, _) => i128::from_ne_bytes(v) as u64,
                     Test::IsFloat(_, _) => unreachable!("floats cannot be switched on"),
@@ -2358,6 +2373,21 @@ fn decide_to_branching<'a>(
                     Layout::usize(env.target),
                     env.arena.alloc(switch),
                 )
+            } else if let Some(int_values) = int_values {
+                let cases = int_values
+                    .into_iter()
+                    .zip(branches)
+                    .map(|(value, (_, _, branch))| (value, branch))
+                    .collect();
+
+                int_switch(
+                    env,
+                    inner_cond_symbol,
+                    inner_cond_layout,
+                    ret_layout,
+                    cases,
+                    default_branch,
+                )
             } else {
                 Stmt::Switch {
                     cond_layout: inner_cond_layout,
@@ -2378,6 +2408,153 @@ fn decide_to_branching<'a>(
     }
 }
 
+/// Runs of at least this many consecutive integers that lead to the same code are handled by a
+/// single range check, rather than by a case per integer.
+const MIN_RANGE_CHECK_LENGTH: usize = 4;
+
+/// Switch on an integer. Cases that do the same thing as the default branch are dropped, and runs
+/// of consecutive integers that do the same thing are tested with a range check before the switch.
+fn int_switch<'a>(
+    env: &mut Env<'a, '_>,
+    cond_symbol: Symbol,
+    cond_layout: InLayout<'a>,
+    ret_layout: InLayout<'a>,
+    mut cases: Vec<(i128, Stmt<'a>)>,
+    default_branch: Stmt<'a>,
+) -> Stmt<'a> {
+    cases.retain(|(_, branch)| *branch != default_branch);
+
+    let mut sorted: Vec<usize> = (0..cases.len()).collect();
+    sorted.sort_by_key(|index| cases[*index].0);
+
+    let mut in_range = vec![false; cases.len()];
+    let mut ranges = vec![];
+
+    let mut start = 0;
+    while start < sorted.len() {
+        let (first, branch) = &cases[sorted[start]];
+
+        let mut end = start;
+        while let Some(next) = sorted.get(end + 1) {
+            let (value, next_branch) = &cases[*next];
+
+            if *value == cases[sorted[end]].0 + 1 && next_branch == branch {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+
+        if end - start + 1 >= MIN_RANGE_CHECK_LENGTH {
+            for index in &sorted[start..=end] {
+                in_range[*index] = true;
+            }
+
+            ranges.push((*first, cases[sorted[end]].0, sorted[start]));
+        }
+
+        start = end + 1;
+    }
+
+    let range_branches: Vec<_> = ranges
+        .iter()
+        .map(|(low, high, index)| (*low, *high, cases[*index].1.clone()))
+        .collect();
+
+    let branches = bumpalo::collections::Vec::from_iter_in(
+        cases
+            .into_iter()
+            .zip(in_range)
+            .filter(|(_, in_range)| !in_range)
+            .map(|((value, branch), _)| (value as u64, BranchInfo::None, branch)),
+        env.arena,
+    );
+
+    let mut stmt = if branches.is_empty() {
+        default_branch
+    } else {
+        Stmt::Switch {
+            cond_layout,
+            cond_symbol,
+            branches: branches.into_bump_slice(),
+            default_branch: (BranchInfo::None, env.arena.alloc(default_branch)),
+            ret_layout,
+        }
+    };
+
+    for (low, high, branch) in range_branches.into_iter().rev() {
+        stmt = range_check(
+            env,
+            cond_symbol,
+            cond_layout,
+            ret_layout,
+            (low, high),
+            branch,
+            stmt,
+        );
+    }
+
+    stmt
+}
+
+/// `if low <= cond && cond <= high then pass else fail`
+fn range_check<'a>(
+    env: &mut Env<'a, '_>,
+    cond_symbol: Symbol,
+    cond_layout: InLayout<'a>,
+    ret_layout: InLayout<'a>,
+    (low, high): (i128, i128),
+    pass: Stmt<'a>,
+    fail: Stmt<'a>,
+) -> Stmt<'a> {
+    let arena = env.arena;
+
+    let low_symbol = env.unique_symbol();
+    let high_symbol = env.unique_symbol();
+    let above_low_symbol = env.unique_symbol();
+    let below_high_symbol = env.unique_symbol();
+    let in_range_symbol = env.unique_symbol();
+
+    let mut lowlevel = |op, arguments: &[Symbol]| {
+        Expr::Call(Call {
+            call_type: CallType::LowLevel {
+                op,
+                update_mode: env.next_update_mode_id(),
+            },
+            arguments: arena.alloc_slice_copy(arguments),
+        })
+    };
+
+    let above_low = lowlevel(LowLevel::NumGte, &[cond_symbol, low_symbol]);
+    let below_high = lowlevel(LowLevel::NumLte, &[cond_symbol, high_symbol]);
+    let in_range = lowlevel(LowLevel::And, &[above_low_symbol, below_high_symbol]);
+
+    let bindings = [
+        (
+            low_symbol,
+            Expr::Literal(Literal::Int(low.to_ne_bytes())),
+            cond_layout,
+        ),
+        (
+            high_symbol,
+            Expr::Literal(Literal::Int(high.to_ne_bytes())),
+            cond_layout,
+        ),
+        (above_low_symbol, above_low, Layout::BOOL),
+        (below_high_symbol, below_high, Layout::BOOL),
+        (in_range_symbol, in_range, Layout::BOOL),
+    ];
+
+    let branch = crate::ir::cond(env, in_range_symbol, Layout::BOOL, pass, fail, ret_layout);
+
+    bindings
+        .into_iter()
+        .rev()
+        .fold(branch, |stmt, (symbol, expr, layout)| {
+            Stmt::Let(symbol, expr, layout, arena.alloc(stmt))
+        })
+}
+
 /*
 fn boolean_all<'a>(arena: &'a Bump, tests: Vec<(Expr<'a>, Expr<'a>, InLayout<'a>)>) -> Expr<'a> {
     let mut expr = Expr::Bool(true);
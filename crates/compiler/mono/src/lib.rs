@@ -11,8 +11,13 @@
 
 pub mod borrow;
 pub mod code_gen_help;
+pub mod common_subexpression;
+pub mod constant_folding;
+pub mod dead_code;
 pub mod drop_specialization;
+pub mod higher_order;
 pub mod inc_dec;
+pub mod inline;
 pub mod ir;
 pub mod layout;
 pub mod low_level;
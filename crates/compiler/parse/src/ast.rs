@@ -671,6 +671,8 @@ pub struct PrecedenceConflict<'a> {
     pub binop2_position: Position,
     pub binop1: BinOp,
     pub binop2: BinOp,
+    /// The operands before `binop1`, between the two operators, and after `binop2`
+    pub operand_regions: [Region; 3],
     pub expr: &'a Loc<Expr<'a>>,
 }
 
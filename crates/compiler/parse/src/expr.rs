@@ -1457,6 +1457,18 @@ pub struct SingleDef<'a> {
     pub spaces_after: &'a [CommentOrNewline<'a>],
 }
 
+/// Whether the next token is the `then` of an `if`, which can never follow a definition
+fn followed_by_then<'a>(arena: &'a Bump, state: &State<'a>) -> bool {
+    let state = match space0_e(EExpr::IndentEnd).parse(arena, state.clone(), 0) {
+        Ok((_, _, state)) => state,
+        Err(_) => return false,
+    };
+
+    parser::keyword(keyword::THEN, EExpr::Start)
+        .parse(arena, state, 0)
+        .is_ok()
+}
+
 fn parse_defs_expr<'a>(
     options: ExprParseOptions,
     min_indent: u32,
@@ -1943,6 +1955,13 @@ fn parse_expr_operator<'a>(
                         let (_, mut body, state) =
                             expr_start(options).parse(arena, state, indented_more)?;
 
+                        // In `if x = 1 then`, this `=` was meant to be a `==`
+                        if followed_by_then(arena, &state) {
+                            let fail = EExpr::BadOperator(arena.alloc("="), loc_op.region.start());
+
+                            return Err((MadeProgress, fail));
+                        }
+
                         // put the spaces from after the operator in front of the call
                         if !spaces_after_operator.is_empty() {
                             body = arena
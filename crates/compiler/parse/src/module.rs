@@ -46,6 +46,43 @@ pub fn parse_module_defs<'a>(
     }
 }
 
+/// The syntax errors in the top-level defs after the one that has `error`, the first syntax
+/// error [parse_module_defs] found. A line that starts in the first column always starts a new
+/// top-level def, so each def is parsed on its own to find the errors the first one hides.
+pub fn syntax_errors_after<'a>(
+    arena: &'a bumpalo::Bump,
+    state: State<'a>,
+    error: &SyntaxError<'a>,
+) -> std::vec::Vec<SyntaxError<'a>> {
+    let bytes = state.original_bytes();
+
+    let starts: std::vec::Vec<usize> = (state.pos().offset as usize..bytes.len())
+        .filter(|&offset| offset == 0 || bytes[offset - 1] == b'\n')
+        .filter(|&offset| !matches!(bytes[offset], b' ' | b'\t' | b'\r' | b'\n' | b'#'))
+        .collect();
+
+    let mut errors = starts.iter().enumerate().filter_map(|(index, &start)| {
+        let end = starts.get(index + 1).copied().unwrap_or(bytes.len());
+        let state = State::new(&bytes[..end]).at_line_start(start);
+
+        parse_module_defs(arena, state, Defs::default()).err()
+    });
+
+    // A def that only fails when it is parsed on its own, like a multiline string with lines
+    // in the first column, would be reported in place of the real error. So the errors only
+    // count when the first one is found again.
+    let is_first_error = |found: &SyntaxError<'a>| match (found, error) {
+        (SyntaxError::Expr(found, _), SyntaxError::Expr(error, _)) => found == error,
+        (found, error) => found == error,
+    };
+
+    if errors.by_ref().any(|found| is_first_error(&found)) {
+        errors.collect()
+    } else {
+        std::vec::Vec::new()
+    }
+}
+
 pub fn parse_header<'a>(
     arena: &'a bumpalo::Bump,
     state: State<'a>,
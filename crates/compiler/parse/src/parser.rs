@@ -589,6 +589,8 @@ pub enum EPattern<'a> {
 
     PInParens(PInParens<'a>, Position),
     NumLiteral(ENumber, Position),
+    /// A range like `0..9`, which patterns do not support yet
+    NumRange(Position),
 
     IndentStart(Position),
     IndentEnd(Position),
@@ -221,26 +221,36 @@ fn loc_pattern_in_parens_help<'a>() -> impl Parser<'a, Loc<Pattern<'a>>, PInPare
 }
 
 fn number_pattern_help<'a>() -> impl Parser<'a, Pattern<'a>, EPattern<'a>> {
-    specialize_err(
-        EPattern::NumLiteral,
-        map!(crate::number_literal::number_literal(), |literal| {
-            use crate::number_literal::NumLiteral::*;
-
-            match literal {
-                Num(s) => Pattern::NumLiteral(s),
-                Float(s) => Pattern::FloatLiteral(s),
-                NonBase10Int {
-                    string,
-                    base,
-                    is_negative,
-                } => Pattern::NonBase10Literal {
-                    string,
-                    base,
-                    is_negative,
-                },
+    move |arena, state: State<'a>, min_indent| {
+        use crate::number_literal::NumLiteral::*;
+
+        let start = state.pos();
+        let (_, literal, state) = specialize_err(
+            EPattern::NumLiteral,
+            crate::number_literal::number_literal(),
+        )
+        .parse(arena, state, min_indent)?;
+
+        let pattern = match literal {
+            // The number literal takes in every `.`, so a range reads as one malformed float
+            Float(s) if s.contains("..") => {
+                return Err((MadeProgress, EPattern::NumRange(start)));
             }
-        }),
-    )
+            Num(s) => Pattern::NumLiteral(s),
+            Float(s) => Pattern::FloatLiteral(s),
+            NonBase10Int {
+                string,
+                base,
+                is_negative,
+            } => Pattern::NonBase10Literal {
+                string,
+                base,
+                is_negative,
+            },
+        };
+
+        Ok((MadeProgress, pattern, state))
+    }
 }
 
 fn string_like_pattern_help<'a>() -> impl Parser<'a, Pattern<'a>, EPattern<'a>> {
@@ -105,6 +105,16 @@ impl<'a> State<'a> {
         self
     }
 
+    /// Continue at the start of the line at the given offset, as if everything before it had
+    /// been parsed already
+    #[must_use]
+    pub(crate) fn at_line_start(mut self, offset: usize) -> State<'a> {
+        self.offset = offset;
+        self.line_start = self.pos();
+        self.line_start_after_whitespace = self.line_start;
+        self
+    }
+
     #[must_use]
     #[inline(always)]
     pub(crate) const fn mark_current_indent(mut self) -> State<'a> {
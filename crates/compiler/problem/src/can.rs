@@ -336,7 +336,7 @@ impl Problem {
             | Problem::UnknownGeneratesWith(Loc { region, .. })
             | Problem::UnusedArgument(_, _, _, region)
             | Problem::UnusedBranchDef(_, region)
-            | Problem::PrecedenceProblem(PrecedenceProblem::BothNonAssociative(region, _, _))
+            | Problem::PrecedenceProblem(PrecedenceProblem::BothNonAssociative(region, ..))
             | Problem::UnsupportedPattern(_, region)
             | Problem::CyclicAlias(_, region, _, _)
             | Problem::PhantomTypeArgument {
@@ -490,13 +490,14 @@ pub enum ExtensionTypeKind {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum PrecedenceProblem {
-    BothNonAssociative(Region, Loc<BinOp>, Loc<BinOp>),
+    /// The regions are of the whole expression, the two operators, and the three operands
+    BothNonAssociative(Region, Loc<BinOp>, Loc<BinOp>, [Region; 3]),
 }
 
 impl PrecedenceProblem {
     pub fn region(&self) -> Region {
         match self {
-            PrecedenceProblem::BothNonAssociative(region, _, _) => *region,
+            PrecedenceProblem::BothNonAssociative(region, ..) => *region,
         }
     }
 }
@@ -1,7 +1,10 @@
 use bumpalo::Bump;
 use roc_can::{constraint::Constraints, module::ExposedByModule};
 use roc_checkmate::with_checkmate;
+use roc_collections::VecMap;
 use roc_derive::SharedDerivedModule;
+use roc_module::symbol::Symbol;
+use roc_region::all::Region;
 use roc_types::subs::{Content, Descriptor, Mark, OptVariable, Rank, Subs, Variable};
 use roc_unify::Env as UEnv;
 
@@ -23,6 +26,13 @@ pub struct SolveEnv<'a> {
     pub checkmate: &'a mut Option<roc_checkmate::Collector>,
 }
 
+/// A `_` placeholder, and the values that are in scope where it is.
+pub struct TypedHole {
+    pub var: Variable,
+    pub region: Region,
+    pub scope: Vec<(Symbol, Variable)>,
+}
+
 /// Environment necessary for inference.
 pub struct InferenceEnv<'a> {
     pub constraints: &'a Constraints,
@@ -31,6 +41,11 @@ pub struct InferenceEnv<'a> {
     pub derived_env: &'a DerivedEnv<'a>,
     pub subs: &'a mut Subs,
     pub pools: &'a mut Pools,
+    /// `_` placeholders, whose types are reported once solving is done.
+    pub typed_holes: Vec<TypedHole>,
+    /// Defs that were not generalized, even though their type has unbound variables, along
+    /// with those variables.
+    pub weakened_defs: VecMap<Symbol, Vec<Variable>>,
     #[cfg(debug_assertions)]
     pub checkmate: Option<roc_checkmate::Collector>,
 }
@@ -3,7 +3,7 @@ use crate::ability::{
     CheckedDerives, ObligationCache, PendingDerivesTable, Resolved,
 };
 use crate::deep_copy::deep_copy_var_in;
-use crate::env::{DerivedEnv, InferenceEnv};
+use crate::env::{DerivedEnv, InferenceEnv, TypedHole};
 use crate::module::{SolveConfig, Solved};
 use crate::pools::Pools;
 use crate::specialize::{
@@ -16,6 +16,8 @@ use roc_can::abilities::{AbilitiesStore, MemberSpecializationInfo};
 use roc_can::constraint::Constraint::{self, *};
 use roc_can::constraint::{Cycle, LetConstraint, OpportunisticResolve};
 use roc_can::expected::{Expected, PExpected};
+use roc_collections::all::HumanIndex;
+use roc_collections::VecMap;
 use roc_debug_flags::dbg_do;
 #[cfg(debug_assertions)]
 use roc_debug_flags::ROC_VERIFY_RIGID_LET_GENERALIZED;
@@ -23,13 +25,15 @@ use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
 use roc_problem::can::CycleEntry;
 use roc_region::all::Loc;
-use roc_solve_problem::TypeError;
+use roc_solve_problem::{TypeCycleStep, TypeError};
 use roc_solve_schema::UnificationMode;
 use roc_types::subs::{
     self, Content, FlatType, GetSubsSlice, Mark, OptVariable, Rank, Subs, TagExt, UlsOfVar,
-    Variable,
+    UnionTags, Variable,
+};
+use roc_types::types::{
+    AliasKind, Category, Polarity, Reason, RecordField, Type, TypeExtension, Types, Uls,
 };
-use roc_types::types::{Category, Polarity, Reason, RecordField, Type, TypeExtension, Types, Uls};
 use roc_unify::unify::{
     unify, unify_introduced_ability_specialization, Obligated, SpecializationLsetCollector,
     Unified::*,
@@ -156,6 +160,8 @@ fn run_help(
         derived_env: &derived_env,
         subs,
         pools: &mut pools,
+        typed_holes: Vec::new(),
+        weakened_defs: VecMap::default(),
         #[cfg(debug_assertions)]
         checkmate: config.checkmate,
     };
@@ -188,6 +194,14 @@ fn run_help(
         &mut awaiting_specializations,
     );
 
+    // Only now are the types of the holes as complete as the rest of the program tells us.
+    for hole in std::mem::take(&mut env.typed_holes) {
+        let candidates = typed_hole_candidates(&mut env, &hole);
+        let typ = env.subs.var_to_error_type(hole.var, Polarity::OF_VALUE);
+
+        problems.push(TypeError::TypedHole(hole.region, typ, candidates));
+    }
+
     RunSolveOutput {
         scope: state.scope,
         #[cfg(debug_assertions)]
@@ -196,6 +210,41 @@ fn run_help(
     }
 }
 
+/// The values in scope at a typed hole whose type fits in the hole
+fn typed_hole_candidates(env: &mut InferenceEnv, hole: &TypedHole) -> Vec<Symbol> {
+    // anything fits in a hole that nothing is known about
+    if let Content::FlexVar(_) = env.subs.get_content_without_compacting(hole.var) {
+        return Vec::new();
+    }
+
+    let mut candidates = Vec::new();
+
+    for (symbol, var) in hole.scope.iter() {
+        // try the unification, but leave the types as they are
+        let snapshot = env.subs.snapshot();
+        let unified = unify(
+            &mut env.uenv(),
+            hole.var,
+            *var,
+            UnificationMode::EQ,
+            Polarity::OF_VALUE,
+        );
+        env.subs.rollback_to(snapshot);
+
+        if let Success {
+            must_implement_ability,
+            ..
+        } = unified
+        {
+            if must_implement_ability.is_empty() {
+                candidates.push(*symbol);
+            }
+        }
+    }
+
+    candidates
+}
+
 #[derive(Debug)]
 enum Work<'a> {
     Constraint {
@@ -296,6 +345,10 @@ fn solve(
 
                 env.pools.get_mut(rank).extend(pool_variables);
 
+                if !let_con.generalizable.0 {
+                    record_weakened_defs(env, &local_def_vars);
+                }
+
                 let mut new_scope = scope.clone();
                 for (symbol, loc_var) in local_def_vars.iter() {
                     check_ability_specialization(
@@ -425,6 +478,10 @@ fn solve(
                     }
                 });
 
+                if !let_con.generalizable.0 {
+                    record_weakened_defs(env, &local_def_vars);
+                }
+
                 let mut new_scope = scope.clone();
                 for (symbol, loc_var) in local_def_vars.iter() {
                     check_ability_specialization(
@@ -610,6 +667,17 @@ fn solve(
                             *expectation.get_type_ref(),
                         );
 
+                        // This has to be checked before unifying, which changes both types.
+                        let weakening_conflict = match env.weakened_defs.get(symbol) {
+                            Some(weakened_vars) => conflicts_with_weakened_vars(
+                                env.subs,
+                                actual,
+                                expected,
+                                weakened_vars,
+                            ),
+                            None => false,
+                        };
+
                         match unify(
                             &mut env.uenv(),
                             actual,
@@ -653,12 +721,21 @@ fn solve(
                             Failure(vars, actual_type, expected_type, _bad_impls) => {
                                 env.introduce(rank, &vars);
 
-                                let problem = TypeError::BadExpr(
-                                    *region,
-                                    Category::Lookup(*symbol),
-                                    actual_type,
-                                    expectation.replace_ref(expected_type),
-                                );
+                                let problem = if weakening_conflict {
+                                    TypeError::NotGeneralized {
+                                        region: *region,
+                                        symbol: *symbol,
+                                        found: actual_type,
+                                        expected: expectation.replace_ref(expected_type),
+                                    }
+                                } else {
+                                    TypeError::BadExpr(
+                                        *region,
+                                        Category::Lookup(*symbol),
+                                        actual_type,
+                                        expectation.replace_ref(expected_type),
+                                    )
+                                };
 
                                 problems.push(problem);
 
@@ -1392,6 +1469,22 @@ fn solve(
                     }
                 }
             }
+            TypedHole(var, region) => {
+                // builtins are always in scope, so suggesting them would mostly be noise
+                let scope = state
+                    .scope
+                    .vars_by_symbol()
+                    .filter(|(symbol, _)| !symbol.is_builtin())
+                    .collect();
+
+                env.typed_holes.push(TypedHole {
+                    var: *var,
+                    region: *region,
+                    scope,
+                });
+
+                state
+            }
         };
     }
 
@@ -1846,7 +1939,7 @@ fn check_for_infinite_type(
 ) {
     let var = loc_var.value;
 
-    'next_occurs_check: while let Err((_, chain)) = env.subs.occurs(var) {
+    'next_occurs_check: while let Err((recursive, chain)) = env.subs.occurs(var) {
         // walk the chain till we find a tag union or lambda set, starting from the variable that
         // occurred recursively, which is always at the end of the chain.
         for &var in chain.iter().rev() {
@@ -1877,7 +1970,7 @@ fn check_for_infinite_type(
             }
         }
 
-        circular_error(env.subs, problems, symbol, &loc_var);
+        circular_error(env.subs, problems, symbol, &loc_var, recursive, &chain);
     }
 }
 
@@ -1886,16 +1979,308 @@ fn circular_error(
     problems: &mut Vec<TypeError>,
     symbol: Symbol,
     loc_var: &Loc<Variable>,
+    recursive: Variable,
+    chain: &[Variable],
 ) {
     let var = loc_var.value;
+    let steps = type_cycle_steps(subs, recursive, chain);
     let error_type = subs.var_to_error_type(var, Polarity::OF_VALUE);
-    let problem = TypeError::CircularType(loc_var.region, symbol, error_type);
+    let problem = TypeError::CircularType(loc_var.region, symbol, error_type, steps);
 
     subs.set_content(var, Content::Error);
 
     problems.push(problem);
 }
 
+/// Remembers the defs of a let-binding that is not generalized but whose type still has unbound
+/// variables, so that using them at two different types can be explained.
+fn record_weakened_defs(
+    env: &mut InferenceEnv,
+    local_def_vars: &LocalDefVarsVec<(Symbol, Loc<Variable>)>,
+) {
+    for (symbol, loc_var) in local_def_vars.iter() {
+        let unbound_vars = unbound_vars(env.subs, loc_var.value);
+
+        if !unbound_vars.is_empty() {
+            env.weakened_defs.insert(*symbol, unbound_vars);
+        }
+    }
+}
+
+fn unbound_vars(subs: &Subs, var: Variable) -> Vec<Variable> {
+    let mut unbound = Vec::new();
+    let mut seen = Vec::new();
+    let mut stack = vec![var];
+
+    while let Some(var) = stack.pop() {
+        let var = subs.get_root_key_without_compacting(var);
+
+        // recursive types would loop forever otherwise
+        if seen.contains(&var) {
+            continue;
+        }
+        seen.push(var);
+
+        match subs.get_content_without_compacting(var) {
+            Content::FlexVar(_) | Content::FlexAbleVar(_, _) => unbound.push(var),
+            Content::RigidVar(_)
+            | Content::RigidAbleVar(_, _)
+            | Content::RecursionVar { .. }
+            | Content::LambdaSet(_)
+            | Content::ErasedLambda
+            | Content::RangedNumber(_)
+            | Content::Error => {}
+            Content::Alias(_, _, real_var, _) => stack.push(*real_var),
+            Content::Structure(flat_type) => match flat_type {
+                FlatType::Apply(_, args) => stack.extend(subs.get_subs_slice(*args)),
+                FlatType::Func(args, _, ret) => {
+                    stack.extend(subs.get_subs_slice(*args));
+                    stack.push(*ret);
+                }
+                FlatType::Record(fields, ext) => {
+                    stack.extend(fields.iter_variables().map(|index| subs[index]));
+                    stack.push(*ext);
+                }
+                FlatType::Tuple(elems, ext) => {
+                    stack.extend(elems.iter_variables().map(|index| subs[index]));
+                    stack.push(*ext);
+                }
+                FlatType::TagUnion(tags, ext) | FlatType::RecursiveTagUnion(_, tags, ext) => {
+                    for slice_index in tags.variables() {
+                        stack.extend(subs.get_subs_slice(subs[slice_index]));
+                    }
+                    stack.push(ext.var());
+                }
+                FlatType::FunctionOrTagUnion(_, _, ext) => stack.push(ext.var()),
+                FlatType::EmptyRecord | FlatType::EmptyTuple | FlatType::EmptyTagUnion => {}
+            },
+        }
+    }
+
+    unbound
+}
+
+/// Whether the type a weakened def is used at disagrees with what an earlier use fixed one of
+/// its unbound variables to. Generalizing the def would have given each use its own copy of
+/// those variables, so only then is the mismatch explained by the def not being generalized.
+fn conflicts_with_weakened_vars(
+    subs: &Subs,
+    def_var: Variable,
+    expected: Variable,
+    weakened_vars: &[Variable],
+) -> bool {
+    let mut seen = Vec::new();
+    let mut stack = vec![(def_var, expected, false)];
+
+    while let Some((def_var, expected, in_weakened_var)) = stack.pop() {
+        let in_weakened_var = in_weakened_var
+            || weakened_vars
+                .iter()
+                .any(|&weakened_var| subs.equivalent_without_compacting(weakened_var, def_var));
+
+        let def_var = unwrap_aliases(subs, def_var);
+        let expected = unwrap_aliases(subs, expected);
+
+        // recursive types would loop forever otherwise
+        if seen.contains(&(def_var, expected)) {
+            continue;
+        }
+        seen.push((def_var, expected));
+
+        let (def_type, expected_type) = match (
+            subs.get_content_without_compacting(def_var),
+            subs.get_content_without_compacting(expected),
+        ) {
+            (Content::Structure(def_type), Content::Structure(expected_type)) => {
+                (def_type, expected_type)
+            }
+            (
+                Content::Alias(def_symbol, def_args, _, AliasKind::Opaque),
+                Content::Alias(symbol, args, _, AliasKind::Opaque),
+            ) if def_symbol == symbol => {
+                let args = args.named_type_arguments().map(|index| subs[index]);
+
+                for (def_arg, arg) in def_args.named_type_arguments().zip(args) {
+                    stack.push((subs[def_arg], arg, in_weakened_var));
+                }
+
+                continue;
+            }
+            // two different opaque types, or an opaque type and a structural one
+            (
+                Content::Structure(_) | Content::Alias(..),
+                Content::Structure(_) | Content::Alias(..),
+            ) => {
+                if in_weakened_var {
+                    return true;
+                }
+
+                continue;
+            }
+            // variables, ranged numbers and the like either unify with anything, or do not
+            // tell us which use fixed them
+            _ => continue,
+        };
+
+        let mut push_pairs = |def_vars: &[Variable], expected_vars: &[Variable]| {
+            for (&def_var, &expected) in def_vars.iter().zip(expected_vars) {
+                stack.push((def_var, expected, in_weakened_var));
+            }
+        };
+
+        match (def_type, expected_type) {
+            (FlatType::Apply(def_symbol, def_args), FlatType::Apply(symbol, args))
+                if def_symbol == symbol =>
+            {
+                push_pairs(subs.get_subs_slice(*def_args), subs.get_subs_slice(*args));
+            }
+            (FlatType::Func(def_args, _, def_ret), FlatType::Func(args, _, ret))
+                if def_args.len() == args.len() =>
+            {
+                push_pairs(subs.get_subs_slice(*def_args), subs.get_subs_slice(*args));
+                push_pairs(&[*def_ret], &[*ret]);
+            }
+            (FlatType::Record(def_fields, _), FlatType::Record(fields, _)) => {
+                for (def_name, def_field, _) in def_fields.iter_all() {
+                    for (name, field, _) in fields.iter_all() {
+                        if subs[def_name] == subs[name] {
+                            push_pairs(&[subs[def_field]], &[subs[field]]);
+                        }
+                    }
+                }
+            }
+            (FlatType::Tuple(def_elems, _), FlatType::Tuple(elems, _)) => {
+                for (def_index, def_elem) in def_elems.iter_all() {
+                    for (index, elem) in elems.iter_all() {
+                        if subs[def_index] == subs[index] {
+                            push_pairs(&[subs[def_elem]], &[subs[elem]]);
+                        }
+                    }
+                }
+            }
+            (
+                FlatType::TagUnion(def_tags, _) | FlatType::RecursiveTagUnion(_, def_tags, _),
+                FlatType::TagUnion(tags, _) | FlatType::RecursiveTagUnion(_, tags, _),
+            ) => {
+                for (def_tag, def_payload) in def_tags.iter_from_subs(subs) {
+                    for (tag, payload) in tags.iter_from_subs(subs) {
+                        if def_tag == tag {
+                            push_pairs(def_payload, payload);
+                        }
+                    }
+                }
+            }
+            // the same kind of type, whose parts do not line up one to one
+            (
+                FlatType::Record(..) | FlatType::EmptyRecord,
+                FlatType::Record(..) | FlatType::EmptyRecord,
+            )
+            | (
+                FlatType::Tuple(..) | FlatType::EmptyTuple,
+                FlatType::Tuple(..) | FlatType::EmptyTuple,
+            )
+            | (
+                FlatType::TagUnion(..)
+                | FlatType::RecursiveTagUnion(..)
+                | FlatType::FunctionOrTagUnion(..)
+                | FlatType::EmptyTagUnion,
+                FlatType::TagUnion(..)
+                | FlatType::RecursiveTagUnion(..)
+                | FlatType::FunctionOrTagUnion(..)
+                | FlatType::EmptyTagUnion,
+            )
+            | (
+                FlatType::Func(..) | FlatType::FunctionOrTagUnion(..),
+                FlatType::Func(..) | FlatType::FunctionOrTagUnion(..),
+            ) => {}
+            _ if in_weakened_var => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Looks through structural aliases, but not opaque ones
+fn unwrap_aliases(subs: &Subs, mut var: Variable) -> Variable {
+    while let Content::Alias(_, _, real_var, AliasKind::Structural) =
+        subs.get_content_without_compacting(var)
+    {
+        var = *real_var;
+    }
+
+    subs.get_root_key_without_compacting(var)
+}
+
+/// Describes how each type in the cycle found by the occurs check contains the next one, going
+/// from the type that contains itself back around to it.
+fn type_cycle_steps(subs: &Subs, recursive: Variable, chain: &[Variable]) -> Vec<TypeCycleStep> {
+    // The chain is ordered from the innermost type outwards, and also contains the types that
+    // lead up to the cycle, before the recursive type is first entered.
+    let mut cycle: Vec<Variable> = chain
+        .iter()
+        .rev()
+        .copied()
+        .skip_while(|&var| var != recursive)
+        .collect();
+    cycle.push(recursive);
+
+    cycle
+        .windows(2)
+        .filter_map(|pair| type_cycle_step(subs, pair[0], pair[1]))
+        .collect()
+}
+
+fn type_cycle_step(subs: &Subs, parent: Variable, child: Variable) -> Option<TypeCycleStep> {
+    let is_child = |var: Variable| subs.get_root_key_without_compacting(var) == child;
+    let tag_payload = |tags: &UnionTags| {
+        tags.iter_from_subs(subs)
+            .find(|(_, payload)| payload.iter().any(|&var| is_child(var)))
+            .map(|(tag_name, _)| TypeCycleStep::TagPayload(tag_name.clone()))
+    };
+
+    match subs.get_content_without_compacting(parent) {
+        Content::Structure(flat_type) => match flat_type {
+            FlatType::Apply(symbol, _) => Some(TypeCycleStep::Apply(*symbol)),
+            FlatType::Func(arguments, closure, ret) => {
+                if is_child(*ret) {
+                    Some(TypeCycleStep::FunctionReturn)
+                } else if is_child(*closure) {
+                    Some(TypeCycleStep::FunctionClosure)
+                } else {
+                    subs.get_subs_slice(*arguments)
+                        .iter()
+                        .position(|&var| is_child(var))
+                        .map(|index| TypeCycleStep::FunctionArgument(HumanIndex::zero_based(index)))
+                }
+            }
+            FlatType::Record(fields, _) => fields
+                .iter_all()
+                .find(|(_, var_index, _)| is_child(subs[*var_index]))
+                .map(|(name_index, _, _)| TypeCycleStep::RecordField(subs[name_index].clone())),
+            FlatType::Tuple(elems, _) => elems
+                .iter_all()
+                .find(|(_, var_index)| is_child(subs[*var_index]))
+                .map(|(elem_index, _)| {
+                    TypeCycleStep::TupleElem(HumanIndex::zero_based(subs[elem_index]))
+                }),
+            FlatType::TagUnion(tags, _) | FlatType::RecursiveTagUnion(_, tags, _) => {
+                tag_payload(tags)
+            }
+            FlatType::FunctionOrTagUnion(..)
+            | FlatType::EmptyRecord
+            | FlatType::EmptyTuple
+            | FlatType::EmptyTagUnion => None,
+        },
+        Content::Alias(symbol, _, _, _) => Some(TypeCycleStep::AliasArgument(*symbol)),
+        Content::LambdaSet(subs::LambdaSet { solved, .. }) => solved
+            .iter_from_subs(subs)
+            .find(|(_, captures)| captures.iter().any(|&var| is_child(var)))
+            .map(|(lambda, _)| TypeCycleStep::LambdaCapture(*lambda)),
+        _ => None,
+    }
+}
+
 /// Generalizes variables at the `young_rank`, which did not escape a let-binding
 /// into a lower scope.
 ///
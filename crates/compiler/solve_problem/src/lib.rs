@@ -2,7 +2,11 @@
 use std::{path::PathBuf, str::Utf8Error};
 
 use roc_can::expected::{Expected, PExpected};
-use roc_module::{ident::Lowercase, symbol::Symbol};
+use roc_collections::all::HumanIndex;
+use roc_module::{
+    ident::{Lowercase, TagName},
+    symbol::Symbol,
+};
 use roc_problem::{can::CycleEntry, Severity};
 use roc_region::all::Region;
 
@@ -12,7 +16,7 @@ use roc_types::types::{Category, ErrorType, PatternCategory};
 pub enum TypeError {
     BadExpr(Region, Category, ErrorType, Expected<ErrorType>),
     BadPattern(Region, PatternCategory, ErrorType, PExpected<ErrorType>),
-    CircularType(Region, Symbol, ErrorType),
+    CircularType(Region, Symbol, ErrorType, Vec<TypeCycleStep>),
     CircularDef(Vec<CycleEntry>),
     UnexposedLookup(Region, Symbol),
     UnfulfilledAbility(Unfulfilled),
@@ -33,6 +37,15 @@ pub enum TypeError {
     },
     IngestedFileBadUtf8(Box<PathBuf>, Utf8Error),
     IngestedFileUnsupportedType(Box<PathBuf>, ErrorType),
+    /// A `_` placeholder, its type, and the values in scope that have that type
+    TypedHole(Region, ErrorType, Vec<Symbol>),
+    /// A def that was not generalized is used at a type other than the one it was given
+    NotGeneralized {
+        region: Region,
+        symbol: Symbol,
+        found: ErrorType,
+        expected: Expected<ErrorType>,
+    },
 }
 
 impl TypeError {
@@ -54,6 +67,8 @@ impl TypeError {
             TypeError::WrongSpecialization { .. } => RuntimeError,
             TypeError::IngestedFileBadUtf8(..) => Fatal,
             TypeError::IngestedFileUnsupportedType(..) => Fatal,
+            TypeError::TypedHole(..) => RuntimeError,
+            TypeError::NotGeneralized { .. } => RuntimeError,
         }
     }
 
@@ -66,6 +81,8 @@ impl TypeError {
             | TypeError::BadExprMissingAbility(region, ..)
             | TypeError::StructuralSpecialization { region, .. }
             | TypeError::WrongSpecialization { region, .. }
+            | TypeError::TypedHole(region, ..)
+            | TypeError::NotGeneralized { region, .. }
             | TypeError::BadPatternMissingAbility(region, ..) => Some(*region),
             TypeError::UnfulfilledAbility(ab, ..) => ab.region(),
             TypeError::Exhaustive(e) => Some(e.region()),
@@ -76,6 +93,25 @@ impl TypeError {
     }
 }
 
+/// How a type in the cycle of a circular type contains the next one.
+///
+/// Steps have no region: the occurs check walks type variables, and `Subs` does not record
+/// which expression a variable's type came from, so the report can only point at the def.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum TypeCycleStep {
+    /// A type argument of a builtin or opaque type, like `List`
+    Apply(Symbol),
+    /// A type argument of an alias
+    AliasArgument(Symbol),
+    FunctionArgument(HumanIndex),
+    FunctionReturn,
+    FunctionClosure,
+    RecordField(Lowercase),
+    TupleElem(HumanIndex),
+    TagPayload(TagName),
+    LambdaCapture(Symbol),
+}
+
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Unfulfilled {
     /// No claimed implementation of an ability for an opaque type.
@@ -101,6 +101,15 @@ fn derivable_record_with_record_ext() {
     );
 }
 
+#[test]
+fn derivable_empty_tuple() {
+    check_derivable(
+        Decoder,
+        v!(EMPTY_TUPLE),
+        DeriveKey::Decoder(FlatDecodableKey::Tuple(0)),
+    );
+}
+
 #[test]
 fn list() {
     derive_test(Decoder, v!(Symbol::LIST_LIST v!(STR)), |golden| {
@@ -116,6 +116,15 @@ fn derivable_record_with_record_ext() {
     );
 }
 
+#[test]
+fn derivable_empty_tuple() {
+    check_derivable(
+        Hash,
+        v!(EMPTY_TUPLE),
+        DeriveKey::Hash(FlatHashKey::Tuple(0)),
+    );
+}
+
 #[test]
 fn derivable_tag_ext_flex_var() {
     check_derivable(
@@ -226,6 +235,19 @@ fn two_element_tuple() {
     })
 }
 
+#[test]
+fn empty_tuple() {
+    derive_test(Hash, v!(EMPTY_TUPLE), |golden| {
+        // Like the empty record, there is nothing to hash
+        assert!(
+            golden.contains(
+                "#Derived.hash_(arity:0) = \\#Derived.hasher, #Derived.tup -> #Derived.hasher"
+            ),
+            "{golden}"
+        );
+    })
+}
+
 #[test]
 fn tag_one_label_no_payloads() {
     derive_test(Hash, v!([A]), |golden| {
@@ -59,6 +59,8 @@ fn roc_function<'a, 'b>(
         ignore_problems: false,
         emit_debug_info: true,
         opt_level: OptLevel::Optimize,
+        cfi_checks: false,
+        gc_stack_maps: false,
     };
 
     let context = inkwell::context::Context::create();
@@ -346,6 +346,60 @@ fn unit_values() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn empty_record_keys() {
+    assert_evals_to!(
+        indoc!(
+            r"
+            Dict.empty {}
+                |> Dict.insert {} 1u8
+                |> Dict.insert {} 2u8
+                |> Dict.get {}
+                |> Result.withDefault 0
+            "
+        ),
+        2,
+        u8
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn tuple_keys() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            Dict.empty {}
+                |> Dict.insert (1u8, "a") 1u8
+                |> Dict.insert (1u8, "b") 2u8
+                |> Dict.insert (1u8, "a") 3u8
+                |> Dict.len
+            "#
+        ),
+        2,
+        u64
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn tuple_of_empty_records_keys() {
+    assert_evals_to!(
+        indoc!(
+            r"
+            Dict.empty {}
+                |> Dict.insert ({}, {}) 1u8
+                |> Dict.insert ({}, {}) 2u8
+                |> Dict.get ({}, {})
+                |> Result.withDefault 0
+            "
+        ),
+        2,
+        u8
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn single() {
@@ -43,3 +43,45 @@ fn multi_branch_capturing() {
         (u64, u64)
     );
 }
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn cfi_checks_erased_calls() {
+    use crate::helpers::llvm::{emitted_llvm_ir, HelperConfig};
+    use roc_gen_llvm::llvm::build::LlvmBackendMode;
+    use roc_mono::ir::OptLevel;
+
+    let src = indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        f = \n, m ->
+          \{} -> n + m + 15u8
+
+        main = (f 10u8 20u8) {}
+        "#
+    );
+
+    let config = |cfi_checks| HelperConfig {
+        mode: LlvmBackendMode::GenTest,
+        ignore_problems: false,
+        emit_debug_info: false,
+        opt_level: OptLevel::Development,
+        cfi_checks,
+        gc_stack_maps: false,
+    };
+
+    let checked = emitted_llvm_ir(src, config(true), roc_load::FunctionKind::Erased);
+
+    // The erased closure is marked as a valid target, and the indirect call checks for that
+    // before it jumps, trapping otherwise
+    assert!(checked.contains("!type !"), "{checked}");
+    assert!(checked.contains("!\"roc_erased.("), "{checked}");
+    assert!(checked.contains("@llvm.type.test("), "{checked}");
+    assert!(checked.contains("cfi_invalid:"), "{checked}");
+    assert!(checked.contains("call void @llvm.trap()"), "{checked}");
+
+    let unchecked = emitted_llvm_ir(src, config(false), roc_load::FunctionKind::Erased);
+
+    assert!(!unchecked.contains("@llvm.type.test("), "{unchecked}");
+}
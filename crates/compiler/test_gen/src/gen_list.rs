@@ -1485,6 +1485,75 @@ fn list_map_closure_string() {
     );
 }
 
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn list_map_optimized_empty_list() {
+    crate::helpers::llvm::assert_evals_to_optimized!(
+        indoc!(
+            r"
+            empty : List I64
+            empty = []
+
+            List.map empty (\x -> x * 2)
+            "
+        ),
+        RocList::<i64>::from_slice(&[]),
+        RocList<i64>
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn list_map_optimized_single_element() {
+    crate::helpers::llvm::assert_evals_to_optimized!(
+        indoc!(
+            r"
+            single : List I64
+            single = [21]
+
+            List.map single (\x -> x * 2)
+            "
+        ),
+        RocList::from_slice(&[42]),
+        RocList<i64>
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn list_map_optimized_closure() {
+    crate::helpers::llvm::assert_evals_to_optimized!(
+        indoc!(
+            r"
+            addAll : List I64, I64 -> List I64
+            addAll = \xs, n -> List.map xs (\x -> x + n)
+
+            addAll [1, 2, 3] 10
+            "
+        ),
+        RocList::from_slice(&[11, 12, 13]),
+        RocList<i64>
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn list_map_optimized_shared_input() {
+    // the list is still used after the map, so it must not be updated in place
+    crate::helpers::llvm::assert_evals_to_optimized!(
+        indoc!(
+            r"
+            xs : List I64
+            xs = [1, 2, 3]
+
+            List.concat xs (List.map xs (\x -> x * 10))
+            "
+        ),
+        RocList::from_slice(&[1, 2, 3, 10, 20, 30]),
+        RocList<i64>
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn list_map4_group() {
@@ -3032,6 +3101,42 @@ fn list_sort_with() {
     );
 }
 
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn cfi_checks_zig_callbacks() {
+    use crate::helpers::llvm::{emitted_llvm_ir, HelperConfig};
+    use roc_gen_llvm::llvm::build::LlvmBackendMode;
+    use roc_mono::ir::OptLevel;
+
+    let src = indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main = List.sortWith [4u8, 3, 2, 1] (\a, b -> Num.compare b a)
+        "#
+    );
+
+    let config = |cfi_checks| HelperConfig {
+        mode: LlvmBackendMode::GenTest,
+        ignore_problems: false,
+        emit_debug_info: false,
+        opt_level: OptLevel::Development,
+        cfi_checks,
+        gc_stack_maps: false,
+    };
+
+    let checked = emitted_llvm_ir(src, config(true), roc_load::FunctionKind::LambdaSet);
+
+    // The compare wrapper is marked as a valid target, and the zig sort calls it only after
+    // checking for that
+    assert!(checked.contains("!\"roc_zig_callback."), "{checked}");
+    assert!(checked.contains("@llvm.type.test("), "{checked}");
+
+    let unchecked = emitted_llvm_ir(src, config(false), roc_load::FunctionKind::LambdaSet);
+
+    assert!(!unchecked.contains("@llvm.type.test("), "{unchecked}");
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn list_sort_asc() {
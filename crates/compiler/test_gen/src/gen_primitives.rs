@@ -270,6 +270,28 @@ fn or_pattern() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn or_pattern_range() {
+    assert_evals_to!(
+        indoc!(
+            r"
+            classify : I64 -> I64
+            classify = \n ->
+                when n is
+                    -2 | -1 | 0 | 1 | 2 -> 1
+                    5 -> 2
+                    6 | 7 | 8 | 9 -> 3
+                    _ -> 4
+
+            List.map [-3, -2, 0, 2, 3, 5, 6, 9, 10] classify
+            "
+        ),
+        RocList::from_slice(&[4, 1, 1, 1, 4, 2, 3, 3, 4]),
+        RocList<i64>
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn apply_identity() {
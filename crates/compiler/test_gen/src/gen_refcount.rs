@@ -571,3 +571,177 @@ fn reset_reuse_alignment_8() {
         ]
     );
 }
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn borrowed_parameter_returned() {
+    // `longest` only reads its arguments, except that it returns one of them, so borrow
+    // inference must not pass that one as borrowed
+    crate::helpers::llvm::assert_evals_to_optimized!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            longest : Str, Str, U64 -> Str
+            longest = \a, b, n ->
+                when n is
+                    0 ->
+                        if Str.countUtf8Bytes a >= Str.countUtf8Bytes b then a else b
+
+                    _ ->
+                        Str.concat (longest a b (n - 1)) "!"
+
+            main =
+                x = "a string that is too long to be a small string"
+                y = "short"
+
+                Str.concat (longest x y 2) x
+            "#
+        ),
+        RocStr::from(
+            "a string that is too long to be a small string!!a string that is too long to be a small string"
+        ),
+        RocStr
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn borrowed_parameter_captured() {
+    // the closure holds on to `prefix`, so borrow inference must not pass it as borrowed
+    crate::helpers::llvm::assert_evals_to_optimized!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            prefixAll : Str, List Str, U64 -> List Str
+            prefixAll = \prefix, list, n ->
+                when n is
+                    0 -> List.map list \s -> Str.concat prefix s
+                    _ -> List.append (prefixAll prefix list (n - 1)) prefix
+
+            main =
+                prefix = "a prefix that is too long to be a small string "
+
+                prefixAll prefix ["x", "y"] 1
+            "#
+        ),
+        RocList::from_slice(&[
+            RocStr::from("a prefix that is too long to be a small string x"),
+            RocStr::from("a prefix that is too long to be a small string y"),
+            RocStr::from("a prefix that is too long to be a small string "),
+        ]),
+        RocList<RocStr>
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn gc_stack_maps() {
+    use crate::helpers::llvm::{emitted_llvm_ir, emitted_object_code, HelperConfig};
+    use object::{Object, ObjectSection};
+    use roc_gen_llvm::llvm::build::LlvmBackendMode;
+    use roc_mono::ir::OptLevel;
+
+    let src = indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        count : List I64, U64 -> U64
+        count = \xs, n ->
+            if n == 0 then
+                List.len xs
+            else
+                1 + count xs (n - 1)
+
+        main =
+            xs = [1, 2, 3]
+
+            count xs 3 + count xs 2 + List.len xs
+        "#
+    );
+
+    let config = |gc_stack_maps| HelperConfig {
+        mode: LlvmBackendMode::GenTest,
+        ignore_problems: false,
+        emit_debug_info: false,
+        opt_level: OptLevel::Normal,
+        cfi_checks: false,
+        gc_stack_maps,
+    };
+
+    let ir = emitted_llvm_ir(src, config(true), roc_load::FunctionKind::LambdaSet);
+    let lines: Vec<&str> = ir.lines().collect();
+
+    let stack_maps: Vec<(usize, &str)> = lines
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, line)| line.contains("call void (i64, i32, ...) @llvm.experimental.stackmap("))
+        .collect();
+
+    // `main` calls `count` twice, and `count` calls itself
+    assert!(stack_maps.len() >= 3, "{ir}");
+
+    // the stack map directly follows its call, so it records the return address
+    for (index, _) in stack_maps.iter() {
+        assert!(lines[index - 1].contains("call fastcc"), "{ir}");
+    }
+
+    // `xs` is live across the calls in `main`: the stack map gets the slot that holds the list,
+    // and the list is loaded back from the slot after the call
+    assert!(ir.contains("%gc_root = alloca"), "{ir}");
+    assert!(
+        stack_maps
+            .iter()
+            .any(|(_, stack_map)| stack_map.contains(", ptr %gc_root")),
+        "{ir}"
+    );
+    assert!(ir.contains("%gc_root_reload"), "{ir}");
+    assert!(
+        stack_maps
+            .iter()
+            .all(|(_, stack_map)| !stack_map.contains('{')),
+        "{ir}"
+    );
+
+    // `count` does not use `xs` after its recursive call, so that call has no roots
+    assert!(
+        stack_maps
+            .iter()
+            .any(|(_, stack_map)| !stack_map.contains(", ptr")),
+        "{ir}"
+    );
+
+    // every call has its own id
+    let mut ids: Vec<&str> = stack_maps
+        .iter()
+        .map(|(_, stack_map)| {
+            let (_, rest) = stack_map.split_once("stackmap(i64 ").unwrap();
+            rest.split(',').next().unwrap()
+        })
+        .collect();
+    let num_stack_maps = ids.len();
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), num_stack_maps, "{ir}");
+
+    let object_code = emitted_object_code(src, config(true), roc_load::FunctionKind::LambdaSet);
+    let object_file = object::File::parse(object_code.as_slice()).unwrap();
+
+    let section = object_file
+        .section_by_name(".llvm_stackmaps")
+        .or_else(|| object_file.section_by_name("__llvm_stackmaps"))
+        .expect("object file has no stack map section");
+
+    let data = section.data().unwrap();
+    let num_records = u32::from_ne_bytes(data[12..16].try_into().unwrap());
+
+    // version 3 of the stack map format, with a record for each call to `count`
+    assert_eq!(data[0], 3);
+    assert!(num_records >= 3);
+
+    let ir = emitted_llvm_ir(src, config(false), roc_load::FunctionKind::LambdaSet);
+
+    assert!(!ir.contains("@llvm.experimental.stackmap("), "{ir}");
+}
@@ -2327,3 +2327,69 @@ fn recursive_tag_id_in_allocation_eq() {
         bool
     );
 }
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn lifetime_markers_for_temporaries() {
+    use crate::helpers::llvm::{emitted_llvm_ir, HelperConfig};
+    use roc_gen_llvm::llvm::build::LlvmBackendMode;
+    use roc_mono::ir::OptLevel;
+
+    let src = indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        Expr : [Nil, Leaf I64, Node Expr Expr]
+
+        size : Expr -> I64
+        size = \expr ->
+            when expr is
+                Nil -> 0
+                Leaf _ -> 1
+                Node l r -> size l + size r
+
+        main =
+            x = Num.f64FromParts (Num.f64ToParts 1.5)
+
+            if x == 1.5 then size (Node (Leaf 1) Nil) else 0
+        "#
+    );
+
+    let config = HelperConfig {
+        mode: LlvmBackendMode::GenTest,
+        ignore_problems: false,
+        emit_debug_info: false,
+        opt_level: OptLevel::Development,
+        cfi_checks: false,
+        gc_stack_maps: false,
+    };
+
+    let ir = emitted_llvm_ir(src, config, roc_load::FunctionKind::LambdaSet);
+
+    let marked_slots = |intrinsic: &str| {
+        let mut slots: Vec<&str> = ir
+            .lines()
+            .filter_map(|line| {
+                let (_, rest) = line.split_once(intrinsic)?;
+                let (_, slot) = rest.split_once("ptr ")?;
+                slot.strip_suffix(')')
+            })
+            .collect();
+        slots.sort_unstable();
+        slots
+    };
+
+    let starts = marked_slots("@llvm.lifetime.start.p0(i64 -1, ");
+    let ends = marked_slots("@llvm.lifetime.end.p0(i64 -1, ");
+
+    // the tag id of the `when` on a nullable union, and the records passed to and returned by
+    // the zig builtins, each get a slot that lives only as long as it is used
+    let has_start = |name| starts.iter().any(|slot| slot.starts_with(name));
+
+    assert!(has_start("%result"), "{ir}");
+    assert!(has_start("%roc_call_alloca"), "{ir}");
+    assert!(has_start("%zig_return_alloca"), "{ir}");
+
+    // and every slot whose lifetime starts also has it end
+    assert_eq!(starts, ends, "{ir}");
+}
@@ -76,6 +76,7 @@ fn create_llvm_module<'a>(
         palette: DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        opt_level: config.opt_level,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -229,6 +230,8 @@ fn create_llvm_module<'a>(
         mode: config.mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        cfi_checks: config.cfi_checks,
+        gc_stack_maps: config.gc_stack_maps,
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
@@ -319,6 +322,8 @@ pub struct HelperConfig {
     pub ignore_problems: bool,
     pub emit_debug_info: bool,
     pub opt_level: OptLevel,
+    pub cfi_checks: bool,
+    pub gc_stack_maps: bool,
 }
 
 #[allow(dead_code)]
@@ -348,6 +353,48 @@ pub fn helper<'a>(
     (main_fn_name, delayed_errors, lib)
 }
 
+/// The LLVM IR that the backend generates for the given program, for tests that check what
+/// gets emitted rather than what the program evaluates to
+#[allow(dead_code)]
+pub fn emitted_llvm_ir(src: &str, config: HelperConfig, function_kind: FunctionKind) -> String {
+    let arena = bumpalo::Bump::new();
+    let context = inkwell::context::Context::create();
+    let target = target_lexicon::Triple::host().into();
+
+    let (_main_fn_name, _delayed_errors, module) =
+        create_llvm_module(&arena, src, config, &context, target, function_kind);
+
+    module.print_to_string().to_string()
+}
+
+/// The object file that the backend emits for the given program, for tests that check what
+/// ends up in its sections
+#[allow(dead_code)]
+pub fn emitted_object_code(
+    src: &str,
+    config: HelperConfig,
+    function_kind: FunctionKind,
+) -> Vec<u8> {
+    use inkwell::targets::{FileType, RelocMode};
+    use roc_build::target::{convert_opt_level, target_machine};
+
+    let arena = bumpalo::Bump::new();
+    let context = inkwell::context::Context::create();
+    let target = target_lexicon::Triple::host().into();
+
+    let (_main_fn_name, _delayed_errors, module) =
+        create_llvm_module(&arena, src, config, &context, target, function_kind);
+
+    let target_machine =
+        target_machine(target, convert_opt_level(config.opt_level), RelocMode::PIC).unwrap();
+
+    target_machine
+        .write_to_memory_buffer(module, FileType::Object)
+        .expect("Writing object code failed")
+        .as_slice()
+        .to_vec()
+}
+
 #[allow(dead_code)]
 fn write_final_wasm() -> bool {
     #[allow(unused_imports)]
@@ -479,6 +526,8 @@ where
         emit_debug_info: false,
         ignore_problems,
         opt_level: OPT_LEVEL,
+        cfi_checks: false,
+        gc_stack_maps: false,
     };
 
     let wasm_bytes = compile_to_wasm_bytes(&arena, config, src, &context, function_kind);
@@ -542,6 +591,7 @@ pub(crate) fn llvm_evals_to<T, U, F>(
     transform: F,
     ignore_problems: bool,
     function_kind: FunctionKind,
+    opt_level: OptLevel,
 ) where
     U: PartialEq + std::fmt::Debug,
     F: FnOnce(T) -> U,
@@ -556,7 +606,9 @@ pub(crate) fn llvm_evals_to<T, U, F>(
         mode: LlvmBackendMode::GenTest,
         emit_debug_info: false,
         ignore_problems,
-        opt_level: crate::helpers::llvm::OPT_LEVEL,
+        opt_level,
+        cfi_checks: false,
+        gc_stack_maps: false,
     };
 
     let (main_fn_name, errors, lib) =
@@ -592,6 +644,7 @@ macro_rules! assert_llvm_evals_to {
             $transform,
             $ignore_problems,
             roc_load::FunctionKind::LambdaSet,
+            $crate::helpers::llvm::OPT_LEVEL,
         );
     };
 
@@ -656,6 +709,22 @@ macro_rules! assert_evals_to_erased {
             $crate::helpers::llvm::identity,
             false,
             roc_load::FunctionKind::Erased,
+            $crate::helpers::llvm::OPT_LEVEL,
+        );
+    }};
+}
+
+/// Like `assert_evals_to!`, but always with the mono IR optimizations of `--optimize`,
+/// which the debug builds of the tests otherwise skip.
+macro_rules! assert_evals_to_optimized {
+    ($src:expr, $expected:expr, $ty:ty) => {{
+        crate::helpers::llvm::llvm_evals_to::<$ty, _, _>(
+            $src,
+            $expected,
+            $crate::helpers::llvm::identity,
+            false,
+            roc_load::FunctionKind::LambdaSet,
+            roc_mono::ir::OptLevel::Optimize,
         );
     }};
 }
@@ -666,6 +735,7 @@ pub fn identity<T>(value: T) -> T {
 
 pub(crate) use assert_evals_to;
 pub(crate) use assert_evals_to_erased;
+pub(crate) use assert_evals_to_optimized;
 pub(crate) use assert_llvm_evals_to;
 #[cfg(feature = "gen-llvm-wasm")]
 pub(crate) use assert_wasm_evals_to;
@@ -4,7 +4,7 @@ use bumpalo::Bump;
 use roc_collections::all::MutSet;
 use roc_gen_wasm::wasm32_result::Wasm32Result;
 use roc_gen_wasm::DEBUG_SETTINGS;
-use roc_load::{ExecutionMode, LoadConfig, Threading};
+use roc_load::{ExecutionMode, LoadConfig, OptLevel, Threading};
 use roc_packaging::cache::RocCacheDir;
 use roc_reporting::report::DEFAULT_PALETTE_HTML;
 use roc_solve::FunctionKind;
@@ -94,6 +94,7 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
         function_kind: FunctionKind::LambdaSet,
+        opt_level: OptLevel::Development,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
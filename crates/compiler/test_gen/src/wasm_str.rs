@@ -1184,3 +1184,38 @@ fn str_to_dec() {
         RocDec
     );
 }
+
+#[test]
+fn identical_big_str_literals_share_data() {
+    let src = indoc!(
+        r#"
+            a = "This string is too long to be a small string"
+            b = "This string is too long to be a small string"
+
+            Str.concat a b
+        "#
+    );
+
+    assert_evals_to!(
+        src,
+        RocStr::from(
+            "This string is too long to be a small stringThis string is too long to be a small string"
+        ),
+        RocStr
+    );
+
+    let arena = bumpalo::Bump::new();
+    let wasm_bytes = crate::helpers::wasm::compile_to_wasm_bytes(
+        &arena,
+        src,
+        std::marker::PhantomData::<RocStr>,
+    );
+
+    let literal: &[u8] = b"This string is too long to be a small string";
+    let copies = wasm_bytes
+        .windows(literal.len())
+        .filter(|window| *window == literal)
+        .count();
+
+    assert_eq!(copies, 1);
+}
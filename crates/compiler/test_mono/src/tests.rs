@@ -19,6 +19,7 @@ use roc_load::ExecutionMode;
 use roc_load::FunctionKind;
 use roc_load::LoadConfig;
 use roc_load::LoadMonomorphizedError;
+use roc_load::OptLevel;
 use roc_load::Threading;
 use roc_module::symbol::Interns;
 use roc_module::symbol::Symbol;
@@ -77,7 +78,14 @@ fn promote_expr_to_module(src: &str) -> String {
     buffer
 }
 
-fn compiles_to_ir(test_name: &str, src: &str, mode: &str, allow_type_errors: bool, no_check: bool) {
+fn compiles_to_ir(
+    test_name: &str,
+    src: &str,
+    mode: &str,
+    opt_level: &str,
+    allow_type_errors: bool,
+    no_check: bool,
+) {
     use roc_packaging::cache::RocCacheDir;
     use std::path::PathBuf;
 
@@ -87,6 +95,14 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, allow_type_errors: boo
         _ => panic!("Invalid test_mono exec mode {mode}"),
     };
 
+    let opt_level = match opt_level {
+        "development" => OptLevel::Development,
+        "normal" => OptLevel::Normal,
+        "size" => OptLevel::Size,
+        "optimize" => OptLevel::Optimize,
+        _ => panic!("Invalid test_mono opt level {opt_level}"),
+    };
+
     let arena = &Bump::new();
 
     let filename = PathBuf::from("Test.roc");
@@ -111,6 +127,7 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, allow_type_errors: boo
         render: roc_reporting::report::RenderTarget::Generic,
         palette: roc_reporting::report::DEFAULT_PALETTE,
         exec_mode,
+        opt_level,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -3534,3 +3551,31 @@ fn issue_6606_2() {
         "
     )
 }
+
+#[mono_test]
+fn list_parameter_without_borrow_inference() {
+    indoc!(
+        r"
+        count = \list, n ->
+            when n is
+                0 -> List.len list
+                _ -> count list (n - 1) + List.len list
+
+        count [1, 2, 3] 2
+        "
+    )
+}
+
+#[mono_test(opt_level = "optimize")]
+fn list_parameter_with_borrow_inference() {
+    indoc!(
+        r"
+        count = \list, n ->
+            when n is
+                0 -> List.len list
+                _ -> count list (n - 1) + List.len list
+
+        count [1, 2, 3] 2
+        "
+    )
+}
@@ -9,6 +9,7 @@ pub fn mono_test(args: TokenStream, item: TokenStream) -> TokenStream {
     let mut no_check = false;
     let mut allow_type_errors = false;
     let mut mode = "exec".to_owned();
+    let mut opt_level = "development".to_owned();
     let mut large_stack = false;
     for arg in syn::parse_macro_input!(args as syn::AttributeArgs) {
         use syn::{Lit, Meta, MetaNameValue, NestedMeta};
@@ -21,6 +22,9 @@ pub fn mono_test(args: TokenStream, item: TokenStream) -> TokenStream {
             if path.is_ident("mode") {
                 mode = s.value();
             }
+            if path.is_ident("opt_level") {
+                opt_level = s.value();
+            }
             if path.is_ident("no_check") {
                 no_check = true;
             }
@@ -49,9 +53,9 @@ pub fn mono_test(args: TokenStream, item: TokenStream) -> TokenStream {
         #(#attributes)*
         #visibility fn #name(#args) {
             if #large_stack {
-                with_larger_debug_stack(|| compiles_to_ir(#name_str, #body, &#mode, #allow_type_errors, #no_check));
+                with_larger_debug_stack(|| compiles_to_ir(#name_str, #body, &#mode, &#opt_level, #allow_type_errors, #no_check));
             } else {
-                compiles_to_ir(#name_str, #body, &#mode, #allow_type_errors, #no_check);
+                compiles_to_ir(#name_str, #body, &#mode, &#opt_level, #allow_type_errors, #no_check);
             }
         }
     };
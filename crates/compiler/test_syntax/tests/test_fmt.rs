@@ -950,6 +950,91 @@ mod test_fmt {
         );
     }
 
+    #[test]
+    fn list_keep_blank_line_between_groups() {
+        expr_formats_same(indoc!(
+            r"
+                list = [
+                    0,
+                    1,
+
+                    2,
+                    3,
+                ]
+
+                list
+                "
+        ));
+
+        expr_formats_to(
+            indoc!(
+                r"
+                list = [
+                    0,
+
+
+                    1,
+                ]
+
+                list
+                "
+            ),
+            indoc!(
+                r"
+                list = [
+                    0,
+
+                    1,
+                ]
+
+                list
+                "
+            ),
+        );
+    }
+
+    #[test]
+    fn record_keep_blank_line_between_groups() {
+        expr_formats_same(indoc!(
+            r#"
+                person = {
+                    firstName: "first",
+                    lastName: "last",
+
+                    age: 42,
+                }
+
+                person
+                "#
+        ));
+
+        expr_formats_to(
+            indoc!(
+                r#"
+                person = {
+                    firstName: "first",
+
+
+                    age: 42,
+                }
+
+                person
+                "#
+            ),
+            indoc!(
+                r#"
+                person = {
+                    firstName: "first",
+
+                    age: 42,
+                }
+
+                person
+                "#
+            ),
+        );
+    }
+
     #[test]
     fn force_space_at_beginning_of_comment() {
         expr_formats_to(
@@ -2,7 +2,7 @@ use std::io;
 
 use bumpalo::Bump;
 use roc_collections::MutMap;
-use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError, Threading};
+use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError, OptLevel, Threading};
 use roc_module::symbol::{Interns, Symbol};
 use roc_mono::{
     ir::{Proc, ProcLayout},
@@ -49,6 +49,7 @@ pub(crate) fn write_compiled_ir<'a>(
         render: roc_reporting::report::RenderTarget::Generic,
         palette: roc_reporting::report::DEFAULT_PALETTE,
         exec_mode,
+        opt_level: OptLevel::Development,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -2,7 +2,7 @@ use roc_collections::VecMap;
 use roc_debug_flags::{dbg_do, dbg_set};
 #[cfg(debug_assertions)]
 use roc_debug_flags::{
-    ROC_PRINT_MISMATCHES, ROC_PRINT_UNIFICATIONS, ROC_VERIFY_OCCURS_ONE_RECURSION,
+    ROC_PRINT_MISMATCHES, ROC_PRINT_UNIFICATIONS, ROC_TRACE_UNIFY, ROC_VERIFY_OCCURS_ONE_RECURSION,
 };
 use roc_error_macros::{internal_error, todo_lambda_erasure};
 use roc_module::ident::{Lowercase, TagName};
@@ -406,7 +406,7 @@ pub fn unify_pool<M: MetaCollector>(
 }
 
 /// Set `ROC_PRINT_UNIFICATIONS` in debug runs to print unifications as they start and complete as
-/// a tree to stderr.
+/// a tree to stderr, or `ROC_TRACE_UNIFY` for a line-per-event trace that also includes ranks.
 /// NOTE: Only run this on individual tests! Run on multiple threads, this would clobber each others' output.
 #[cfg(debug_assertions)]
 fn debug_print_unified_types<M: MetaCollector>(
@@ -468,6 +468,38 @@ fn debug_print_unified_types<M: MetaCollector>(
         );
 
         unsafe { UNIFICATION_DEPTH = new_depth };
+    });
+
+    dbg_do!(ROC_TRACE_UNIFY, {
+        static mut TRACE_DEPTH: usize = 0;
+
+        let depth = unsafe { TRACE_DEPTH };
+        let (event, outcome, use_depth, new_depth) = match opt_outcome {
+            None => ("start", "", depth, depth + 1),
+            Some(outcome) if outcome.mismatches.is_empty() => {
+                ("end", " outcome=ok", depth - 1, depth - 1)
+            }
+            Some(_) => ("end", " outcome=mismatch", depth - 1, depth - 1),
+        };
+
+        let first = env.get(ctx.first);
+        let second = env.get(ctx.second);
+        eprintln!(
+            "{}unify depth={} event={} mode={} left={:?} left_rank={:?} left_content={:?} right={:?} right_rank={:?} right_content={:?}{}",
+            "  ".repeat(use_depth),
+            use_depth,
+            event,
+            ctx.mode.pretty_print(),
+            env.get_root_key_without_compacting(ctx.first),
+            first.rank,
+            SubsFmtContent(&first.content, env),
+            env.get_root_key_without_compacting(ctx.second),
+            second.rank,
+            SubsFmtContent(&second.content, env),
+            outcome,
+        );
+
+        unsafe { TRACE_DEPTH = new_depth };
     })
 }
 
@@ -58,13 +58,16 @@ pub fn generate(
                 opt_level: OptLevel::Development,
                 emit_debug_info: false,
                 emit_llvm_ir: false,
+                emit_size_report: false,
                 fuzz: false,
+                emit_gc_stack_maps: false,
             };
 
             let load_config = standard_load_config(
                 target,
                 BuildOrdering::BuildIfChecks,
                 Threading::AllAvailable,
+                code_gen_options.opt_level,
             );
 
             let arena = ManuallyDrop::new(Bump::new());
@@ -422,6 +425,7 @@ pub fn load_types(
             palette: DEFAULT_PALETTE,
             threading,
             exec_mode: ExecutionMode::Check,
+            opt_level: OptLevel::Development,
         },
     )
     .unwrap_or_else(|problem| match problem {
@@ -6,7 +6,7 @@
 use memmap2::{Mmap, MmapMut};
 use object::Object;
 use roc_error_macros::internal_error;
-use roc_load::{EntryPoint, ExecutionMode, ExposedToHost, LoadConfig, Threading};
+use roc_load::{EntryPoint, ExecutionMode, ExposedToHost, LoadConfig, OptLevel, Threading};
 use roc_module::symbol::Interns;
 use roc_packaging::cache::RocCacheDir;
 use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
@@ -87,6 +87,7 @@ pub fn generate_stub_lib(
             palette: DEFAULT_PALETTE,
             threading: Threading::AllAvailable,
             exec_mode: ExecutionMode::Executable,
+            opt_level: OptLevel::Development,
         },
     )
     .unwrap_or_else(|problem| todo!("{:?}", problem));
@@ -11,10 +11,13 @@ use roc_repl_ui::repl_state::{ReplAction, ReplState};
 use roc_repl_ui::{format_output, is_incomplete, CONT_PROMPT, PROMPT, SHORT_INSTRUCTIONS, TIPS};
 use roc_reporting::report::{to_file_problem_report_string, ANSI_STYLE_CODES, DEFAULT_PALETTE};
 use roc_target::Target;
+use rustyline::completion::Completer;
 use rustyline::highlight::{Highlighter, PromptInfo};
 use rustyline::validate::{self, ValidationContext, ValidationResult, Validator};
-use rustyline_derive::{Completer, Helper, Hinter};
+use rustyline::Context;
+use rustyline_derive::{Helper, Hinter};
 use std::borrow::Cow;
+use std::path::PathBuf;
 use target_lexicon::Triple;
 
 use crate::cli_gen::eval_llvm;
@@ -28,7 +31,10 @@ pub const WELCOME_MESSAGE: &str = concatcp!(
     "\n\n"
 );
 
-#[derive(Completer, Helper, Hinter, Default)]
+/// The file in the home directory where the REPL keeps its history between sessions
+const HISTORY_FILE: &str = ".roc_repl_history";
+
+#[derive(Helper, Hinter, Default)]
 pub struct ReplHelper {
     validator: InputValidator,
     state: ReplState,
@@ -49,7 +55,14 @@ pub fn main() -> i32 {
     let target = Triple::host().into();
     let mut arena = Bump::new();
 
-    loop {
+    let history_path = history_path();
+
+    if let Some(path) = &history_path {
+        // There is no history file yet the first time the REPL runs
+        let _ = editor.load_history(path);
+    }
+
+    let exit_code = loop {
         match editor.readline(PROMPT) {
             Ok(line) => {
                 let line = line.trim();
@@ -72,7 +85,7 @@ pub fn main() -> i32 {
                         }
                     }
                     ReplAction::Exit => {
-                        return 0;
+                        break 0;
                     }
                     ReplAction::FileProblem { filename, error } => {
                         println!("{}", to_file_problem_report_string(filename, error));
@@ -80,6 +93,12 @@ pub fn main() -> i32 {
                     ReplAction::Help => {
                         println!("{TIPS}");
                     }
+                    ReplAction::Env(env) => {
+                        println!("{env}");
+                    }
+                    ReplAction::LoadProblem(problem) => {
+                        println!("{problem}");
+                    }
                     ReplAction::Nothing => {}
                 }
             }
@@ -89,18 +108,35 @@ pub fn main() -> i32 {
             }
             Err(ReadlineError::Eof) => {
                 // End of input; we're done!
-                return 0;
+                break 0;
             }
             Err(ReadlineError::Interrupted) => {
                 eprintln!("CTRL-C");
-                return 1;
+                break 1;
             }
             Err(err) => {
                 eprintln!("REPL error: {err:?}");
-                return 1;
+                break 1;
             }
         }
+    };
+
+    if let Some(path) = &history_path {
+        if let Err(err) = editor.save_history(path) {
+            eprintln!(
+                "Could not save the REPL history to {}: {err:?}",
+                path.display()
+            );
+        }
     }
+
+    exit_code
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(HISTORY_FILE))
 }
 
 pub fn evaluate(
@@ -125,6 +161,33 @@ impl Validator for InputValidator {
     }
 }
 
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    /// Complete the name before the cursor to one defined earlier in the session,
+    /// or to a REPL command like `:help`
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !(c.is_alphanumeric() || *c == '_' || *c == ':'))
+            .map_or(0, |(index, c)| index + c.len_utf8());
+
+        let prefix = &line[start..pos];
+
+        if prefix.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+
+        Ok((start, self.state.completions(prefix)))
+    }
+}
+
 impl Highlighter for ReplHelper {
     fn has_continuation_prompt(&self) -> bool {
         true
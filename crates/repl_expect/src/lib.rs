@@ -124,6 +124,7 @@ mod test {
             palette: DEFAULT_PALETTE,
             threading: Threading::Single,
             exec_mode: ExecutionMode::Test,
+            opt_level,
         };
         let loaded = match roc_load::load_and_monomorphize_from_str(
             arena,
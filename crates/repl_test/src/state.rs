@@ -25,6 +25,135 @@ fn persisted_defs() {
     complete("y = 6", &mut state, "6 : Num *");
 }
 
+#[test]
+fn completions() {
+    let mut state = ReplState::new();
+
+    complete("x = 5", &mut state, "5 : Num *");
+    complete("xs = [1]", &mut state, "[1] : List (Num *)");
+    complete("y = 6", &mut state, "6 : Num *");
+
+    assert_eq!(state.completions("x"), ["x", "xs"]);
+    assert!(state.completions("z").is_empty());
+    assert_eq!(state.completions(":q"), [":q", ":quit"]);
+}
+
+#[test]
+fn env_and_reset() {
+    let mut state = ReplState::new();
+    let arena = Bump::new();
+    let target = Triple::host().into();
+
+    assert!(matches!(
+        state.step(&arena, "Age : U8", target, DEFAULT_PALETTE),
+        ReplAction::Nothing
+    ));
+    complete("x = 5", &mut state, "5 : Num *");
+    complete("y = x + 1", &mut state, "6 : Num *");
+    complete("ages = [x, y]", &mut state, "[5, 6] : List (Num *)");
+
+    match state.step(&arena, ":env", target, DEFAULT_PALETTE) {
+        ReplAction::Env(env) => {
+            assert_eq!(env, "Age : U8\nx : Num *\ny : Num *\nages : List (Num *)")
+        }
+        action => panic!("Unexpected action: {:?}", action),
+    }
+
+    assert!(matches!(
+        state.step(&arena, ":reset", target, DEFAULT_PALETTE),
+        ReplAction::Nothing
+    ));
+
+    assert!(state.completions("x").is_empty());
+
+    match state.step(&arena, ":env", target, DEFAULT_PALETTE) {
+        ReplAction::Env(env) => assert_eq!(env, "Nothing has been defined yet."),
+        action => panic!("Unexpected action: {:?}", action),
+    }
+}
+
+#[test]
+fn load_module() {
+    // Imports look for modules in the current directory, so the module has to be in a
+    // directory inside it, whose name is a valid module name.
+    let cwd = std::env::current_dir().unwrap();
+    let dir = tempfile::Builder::new()
+        .prefix("ReplLoad")
+        .tempdir_in(&cwd)
+        .unwrap();
+    let path = dir.path().join("ReplLoadModule.roc");
+    let arena = Bump::new();
+    let target = Triple::host().into();
+    let mut state = ReplState::new();
+
+    std::fs::write(&path, "module [value]\n\nvalue = 1\n").unwrap();
+
+    let line = format!(":load {}", path.display());
+    let action = state.step(&arena, &line, target, DEFAULT_PALETTE);
+    assert!(matches!(action, ReplAction::Nothing));
+    complete("value", &mut state, "1 : Num *");
+
+    // Edits show up without loading the module again
+    std::fs::write(&path, "module [value, other]\n\nvalue = 2\n\nother = 3\n").unwrap();
+    complete("value", &mut state, "2 : Num *");
+
+    // Loading it again, by its relative path, picks up what it exposes now
+    let line = format!(":load {}", path.strip_prefix(&cwd).unwrap().display());
+    let action = state.step(&arena, &line, target, DEFAULT_PALETTE);
+    assert!(matches!(action, ReplAction::Nothing));
+    complete("other", &mut state, "3 : Num *");
+}
+
+#[test]
+fn load_missing_module() {
+    let arena = Bump::new();
+    let target = Triple::host().into();
+    let mut state = ReplState::new();
+
+    match state.step(&arena, ":load ReplLoadMissing.roc", target, DEFAULT_PALETTE) {
+        ReplAction::FileProblem { filename, error } => {
+            assert_eq!(filename.to_str(), Some("ReplLoadMissing.roc"));
+            assert_eq!(error, std::io::ErrorKind::NotFound);
+        }
+        action => panic!("Unexpected action: {:?}", action),
+    }
+}
+
+#[test]
+fn load_invalid_path() {
+    let arena = Bump::new();
+    let target = Triple::host().into();
+    let mut state = ReplState::new();
+
+    for (path, expected) in [
+        (
+            "../Outside.roc",
+            "only modules inside the current directory",
+        ),
+        ("/Absolute.roc", "only modules inside the current directory"),
+        (
+            "lowercase/Module.roc",
+            "lowercase is not a valid module name",
+        ),
+        ("Module.txt", "only .roc files can be loaded"),
+    ] {
+        let line = format!(":load {path}");
+
+        match state.step(&arena, &line, target, DEFAULT_PALETTE) {
+            ReplAction::LoadProblem(problem) => {
+                assert!(problem.starts_with(&format!("I cannot load {path}: {expected}")));
+            }
+            action => panic!("Unexpected action for {path}: {:?}", action),
+        }
+    }
+
+    // None of them were added to the session
+    match state.step(&arena, ":env", target, DEFAULT_PALETTE) {
+        ReplAction::Env(env) => assert_eq!(env, "Nothing has been defined yet."),
+        action => panic!("Unexpected action: {:?}", action),
+    }
+}
+
 #[test]
 fn annotated_body() {
     let mut input = "t : [A, B, C]".to_string();
@@ -1579,6 +1579,9 @@ fn interpolation_with_nested_interpolation() {
                 Enter an expression to evaluate, or a definition (like x = 1) to use later.
 
                   - ctrl-v + ctrl-j makes a newline
+                  - :load path/to/Module.roc imports a module and everything it exposes
+                  - :env shows what has been defined so far
+                  - :reset forgets everything that has been defined
                   - :q quits
                   - :help shows this text again
             "#
@@ -38,6 +38,27 @@ pub const TIPS: &str = concatcp!(
             "  - ",
             END_COL,
             GREEN,
+            ":load path/to/Module.roc",
+            END_COL,
+            " imports a module and everything it exposes\n",
+            CYAN,
+            "  - ",
+            END_COL,
+            GREEN,
+            ":env",
+            END_COL,
+            " shows what has been defined so far\n",
+            CYAN,
+            "  - ",
+            END_COL,
+            GREEN,
+            ":reset",
+            END_COL,
+            " forgets everything that has been defined\n",
+            CYAN,
+            "  - ",
+            END_COL,
+            GREEN,
             ":q",
             END_COL,
             " quits\n",
@@ -77,6 +98,9 @@ pub fn is_incomplete(input: &str) -> bool {
         ParseOutcome::Empty
         | ParseOutcome::Help
         | ParseOutcome::Exit
+        | ParseOutcome::Load(_)
+        | ParseOutcome::Reset
+        | ParseOutcome::Env
         | ParseOutcome::ValueDef(_)
         | ParseOutcome::TypeDef(_)
         | ParseOutcome::SyntaxErr
@@ -1,10 +1,10 @@
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::{fs, io};
 
 use bumpalo::Bump;
 use roc_collections::MutSet;
 use roc_load::MonomorphizedModule;
-use roc_parse::ast::{Expr, Pattern, StrLiteral, TypeDef, TypeHeader, ValueDef};
+use roc_parse::ast::{Expr, Header, Module, Pattern, StrLiteral, TypeDef, TypeHeader, ValueDef};
 use roc_parse::expr::{parse_single_def, ExprParseOptions, SingleDef};
 use roc_parse::parser::Parser;
 use roc_parse::parser::{EClosure, EExpr, EPattern};
@@ -15,10 +15,16 @@ use roc_region::all::Loc;
 use roc_repl_eval::gen::{compile_to_mono, Problems};
 use roc_reporting::report::Palette;
 use roc_target::Target;
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
 
 #[derive(Debug, Clone, PartialEq)]
 enum PastDef {
-    Def { ident: String, src: String },
+    Def {
+        ident: String,
+        src: String,
+        /// The type the def had when it was entered, if it compiled without errors
+        ty: Option<String>,
+    },
     Import(String),
 }
 
@@ -42,6 +48,10 @@ pub enum ReplAction<'a> {
     },
     Exit,
     Help,
+    /// The definitions entered so far with their types, and the imports as source code
+    Env(String),
+    /// A file that cannot be loaded with `:load`, and why
+    LoadProblem(String),
     FileProblem {
         filename: PathBuf,
         error: io::ErrorKind,
@@ -68,6 +78,13 @@ impl ReplState {
         let src: &str = match parse_src(arena, line) {
             ParseOutcome::Empty | ParseOutcome::Help => return ReplAction::Help,
             ParseOutcome::Exit => return ReplAction::Exit,
+            ParseOutcome::Load(path) => return self.load(arena, path),
+            ParseOutcome::Reset => {
+                *self = Self::new();
+
+                return ReplAction::Nothing;
+            }
+            ParseOutcome::Env => return ReplAction::Env(self.env()),
             ParseOutcome::Expr(_) | ParseOutcome::Incomplete | ParseOutcome::SyntaxErr => {
                 pending_past_def = None;
 
@@ -87,7 +104,7 @@ impl ReplState {
                         _,
                     ) => {
                         // Record the standalone type annotation for future use.
-                        self.add_past_def(ident.trim_end().to_string(), line.to_string());
+                        self.add_past_def(ident.trim_end().to_string(), line.to_string(), None);
 
                         // Return early without running eval, since standalone annotations
                         // cannot be evaluated as expressions.
@@ -214,7 +231,7 @@ impl ReplState {
                 ..
             }) => {
                 // Record the type for future use.
-                self.add_past_def(ident.trim_end().to_string(), line.to_string());
+                self.add_past_def(ident.trim_end().to_string(), line.to_string(), None);
 
                 // Return early without running eval, since none of these
                 // can be evaluated as expressions.
@@ -222,10 +239,10 @@ impl ReplState {
             }
         };
 
-        let (opt_mono, problems) = compile_to_mono(
+        let (mut opt_mono, problems) = compile_to_mono(
             arena,
             self.past_defs.iter().map(|past_def| match past_def {
-                PastDef::Def { ident: _, src } => src.as_str(),
+                PastDef::Def { src, .. } => src.as_str(),
                 PastDef::Import(src) => src.as_str(),
             }),
             src,
@@ -234,21 +251,194 @@ impl ReplState {
         );
 
         if let Some((ident, src)) = pending_past_def {
-            self.add_past_def(ident, src);
+            // The def was evaluated as a lookup of itself, so its type is the one of the
+            // value exposed to the host.
+            let ty = match opt_mono.as_mut() {
+                Some(mono) if problems.errors.is_empty() => {
+                    let var = mono.exposed_to_host.top_level_values.values().next();
+
+                    var.map(|var| {
+                        name_and_print_var(
+                            *var,
+                            &mut mono.subs,
+                            mono.module_id,
+                            &mono.interns,
+                            DebugPrint::NOTHING,
+                        )
+                    })
+                }
+                _ => None,
+            };
+
+            self.add_past_def(ident, src, ty);
         }
 
         ReplAction::Eval { opt_mono, problems }
     }
 
-    fn add_past_def(&mut self, ident: String, src: String) {
+    /// Import the module at the given path, along with everything it exposes.
+    /// Imported modules are read again for every evaluation, so edits to the file show up
+    /// without loading it again; loading it again picks up changes to what it exposes.
+    fn load<'a>(&mut self, arena: &Bump, path: &str) -> ReplAction<'a> {
+        let filename = PathBuf::from(path);
+
+        // An absolute path works as long as it points inside the current directory
+        let relative = std::env::current_dir()
+            .ok()
+            .and_then(|dir| filename.strip_prefix(dir).ok().map(Path::to_path_buf));
+
+        let module_name = match module_name_from_path(relative.as_deref().unwrap_or(&filename)) {
+            Ok(module_name) => module_name,
+            Err(problem) => {
+                return ReplAction::LoadProblem(format!("I cannot load {path}: {problem}"));
+            }
+        };
+
+        let src = match fs::read_to_string(&filename) {
+            Ok(src) => src,
+            Err(err) => {
+                return ReplAction::FileProblem {
+                    filename,
+                    error: err.kind(),
+                }
+            }
+        };
+
+        let exposed: Vec<String> =
+            match roc_parse::module::parse_header(arena, State::new(src.as_bytes())) {
+                Ok((
+                    Module {
+                        header: Header::Module(header),
+                        ..
+                    },
+                    _,
+                )) => header
+                    .exposes
+                    .items
+                    .iter()
+                    .map(|name| name.value.item().as_str().to_string())
+                    .collect(),
+                // Let the import report the problem when it gets evaluated
+                _ => Vec::new(),
+            };
+
+        let import = format!("import {module_name}");
+
+        // Replace the import from an earlier load of the same module
+        self.past_defs.retain(|past_def| match past_def {
+            PastDef::Import(src) => {
+                src != &import && !src.starts_with(&format!("{import} exposing"))
+            }
+            PastDef::Def { .. } => true,
+        });
+
+        let import = if exposed.is_empty() {
+            import
+        } else {
+            format!("{import} exposing [{}]", exposed.join(", "))
+        };
+
+        self.past_defs.push(PastDef::Import(import));
+
+        ReplAction::Nothing
+    }
+
+    fn env(&self) -> String {
+        if self.past_defs.is_empty() {
+            return "Nothing has been defined yet.".to_string();
+        }
+
+        let lines: Vec<String> = self
+            .past_defs
+            .iter()
+            .map(|past_def| match past_def {
+                PastDef::Def {
+                    ident,
+                    ty: Some(ty),
+                    ..
+                } => format!("{ident} : {ty}"),
+                // Type definitions, standalone annotations, and defs that didn't compile
+                PastDef::Def { src, ty: None, .. } | PastDef::Import(src) => {
+                    src.trim_end().to_string()
+                }
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+
+    /// The names defined so far in the session, or the REPL's commands if the prefix starts
+    /// with `:`, that start with the given prefix. Sorted, for tab completion.
+    pub fn completions(&self, prefix: &str) -> Vec<String> {
+        const COMMANDS: [&str; 7] = [":env", ":exit", ":help", ":load", ":q", ":quit", ":reset"];
+
+        let mut completions: Vec<String> = if prefix.starts_with(':') {
+            COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(prefix))
+                .map(|command| command.to_string())
+                .collect()
+        } else {
+            self.past_def_idents
+                .iter()
+                .filter(|ident| ident.starts_with(prefix))
+                .cloned()
+                .collect()
+        };
+
+        completions.sort();
+
+        completions
+    }
+
+    fn add_past_def(&mut self, ident: String, src: String, ty: Option<String>) {
         let existing_idents = &mut self.past_def_idents;
 
         existing_idents.insert(ident.clone());
 
-        self.past_defs.push(PastDef::Def { ident, src });
+        self.past_defs.push(PastDef::Def { ident, src, ty });
     }
 }
 
+/// The name that `import` finds the module at the given path by. Imports look for modules in
+/// the current directory, so the path has to be a relative one inside it, and every part of it
+/// has to be a valid module name.
+fn module_name_from_path(path: &Path) -> Result<String, String> {
+    if path.extension().and_then(|extension| extension.to_str()) != Some("roc") {
+        return Err("only .roc files can be loaded.".to_string());
+    }
+
+    let mut parts = Vec::new();
+
+    for component in path.with_extension("").components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(part) => match part.to_str() {
+                Some(part)
+                    if part.starts_with(|c: char| c.is_ascii_uppercase())
+                        && part.chars().all(|c| c.is_ascii_alphanumeric()) =>
+                {
+                    parts.push(part.to_string());
+                }
+                _ => {
+                    return Err(format!(
+                        "{} is not a valid module name. The module and the directories it is in \
+                        must start with an uppercase letter and contain only letters and digits.",
+                        part.to_string_lossy()
+                    ));
+                }
+            },
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err("only modules inside the current directory can be loaded, \
+                    because that is where imports look for them."
+                    .to_string());
+            }
+        }
+    }
+
+    Ok(parts.join("."))
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ParseOutcome<'a> {
     ValueDef(ValueDef<'a>),
@@ -259,6 +449,9 @@ pub enum ParseOutcome<'a> {
     Empty,
     Help,
     Exit,
+    Load(&'a str),
+    Reset,
+    Env,
 }
 
 pub fn parse_src<'a>(arena: &'a Bump, line: &'a str) -> ParseOutcome<'a> {
@@ -270,6 +463,11 @@ pub fn parse_src<'a>(arena: &'a Bump, line: &'a str) -> ParseOutcome<'a> {
         // If you really need to evaluate `exit` for some reason,
         // you can do `foo = exit` and then evaluate `foo` instead.
         ":exit" | ":quit" | ":q" | "exit" | "quit" | "exit()" | "quit()" => ParseOutcome::Exit,
+        ":reset" => ParseOutcome::Reset,
+        ":env" => ParseOutcome::Env,
+        command if command.starts_with(":load ") => {
+            ParseOutcome::Load(line.trim()[":load ".len()..].trim())
+        }
         _ => {
             let src_bytes = line.as_bytes();
 
@@ -200,10 +200,11 @@ pub async fn entrypoint_from_js(src: String) -> String {
     // Perform the action the state machine asked for, and return the appropriate output string
     match action {
         ReplAction::Help => TIPS.to_string(),
+        ReplAction::Env(env) => env,
         ReplAction::Exit => {
             "To exit the web version of the REPL, just close the browser tab!".to_string()
         }
-        ReplAction::FileProblem { .. } => {
+        ReplAction::FileProblem { .. } | ReplAction::LoadProblem(_) => {
             "The web version of the REPL cannot import files... for now!".to_string()
         }
         ReplAction::Nothing => String::new(),
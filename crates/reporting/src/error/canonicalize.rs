@@ -355,8 +355,13 @@ pub fn can_problem<'b>(
 
             title = UNUSED_DEF.to_string();
         }
-        Problem::PrecedenceProblem(BothNonAssociative(region, left_bin_op, right_bin_op)) => {
-            doc = alloc.stack([
+        Problem::PrecedenceProblem(BothNonAssociative(
+            region,
+            left_bin_op,
+            right_bin_op,
+            operand_regions,
+        )) => {
+            let mut stack = vec![
                 if left_bin_op.value == right_bin_op.value {
                     alloc.concat([
                         alloc.reflow("Using more than one "),
@@ -379,8 +384,28 @@ pub fn can_problem<'b>(
                     ])
                 },
                 alloc.region(lines.convert_region(region)),
-            ]);
+            ];
+
+            let [left, middle, right] =
+                operand_regions.map(|operand| region_text(alloc, lines, operand));
+
+            if let (Some(left), Some(middle), Some(right)) = (left, middle, right) {
+                let (op1, op2) = (left_bin_op.value, right_bin_op.value);
+
+                stack.push(
+                    alloc.reflow("Add parentheses to pick one of the two ways to group them:"),
+                );
+                stack.push(
+                    alloc
+                        .vcat([
+                            alloc.text(format!("({left} {op1} {middle}) {op2} {right}")),
+                            alloc.text(format!("{left} {op1} ({middle} {op2} {right})")),
+                        ])
+                        .indent(4),
+                );
+            }
 
+            doc = alloc.stack(stack);
             title = SYNTAX_PROBLEM.to_string();
         }
         Problem::UnsupportedPattern(BadPattern::Unsupported(pattern_type), region) => {
@@ -449,12 +474,19 @@ pub fn can_problem<'b>(
                     alloc.reflow(" definition:"),
                 ]),
                 alloc.region(lines.convert_region(variable_region)),
-                alloc.reflow("Roc does not allow unused type parameters!"),
-                // TODO add link to this guide section
-                alloc.tip().append(alloc.reflow(
-                    "If you want an unused type parameter (a so-called \"phantom type\"), \
-                read the guide section on phantom values.",
-                )),
+                alloc.reflow("Roc does not allow unused type parameters in aliases!"),
+                alloc.concat([
+                    alloc.tip(),
+                    alloc.reflow(
+                        "If you want an unused type parameter (a so-called \"phantom type\"), make ",
+                    ),
+                    alloc.symbol_unqualified(alias),
+                    alloc.reflow(" an opaque type by defining it with "),
+                    alloc.keyword(":="),
+                    alloc.reflow(" instead of "),
+                    alloc.keyword(":"),
+                    alloc.reflow(". Opaque types keep their type parameters even when they are unused."),
+                ]),
             ]);
 
             title = UNUSED_ALIAS_PARAM.to_string();
@@ -2442,7 +2474,6 @@ pub fn to_circular_def_doc<'b>(
     entries: &[roc_problem::can::CycleEntry],
 ) -> RocDocBuilder<'b> {
     // TODO "are you trying to mutate a variable?
-    // TODO tip?
     match entries {
         [] => unreachable!(),
         [CycleEntry { symbol, symbol_region, expr_region }] =>
@@ -2479,12 +2510,41 @@ pub fn to_circular_def_doc<'b>(
                         .map(|s| alloc.symbol_unqualified(s.symbol))
                         .collect::<Vec<_>>(),
                 ),
-                // TODO tip?
+                alloc.stack(others.iter().map(|other| {
+                    alloc.stack([
+                        alloc.concat([
+                            alloc.symbol_unqualified(other.symbol),
+                            alloc.reflow(" is defined here:"),
+                        ]),
+                        alloc.region(lines.convert_region(other.symbol_region)),
+                    ])
+                })),
+                alloc.reflow("Roc evaluates values strictly, so running this program would enter an infinite loop!"),
+                alloc.hint("").append(alloc.reflow(
+                    "Did you mean to define one of these values as a function?",
+                )),
             ])
         }
     }
 }
 
+/// The source code of a region, if it is on a single line
+fn region_text<'b>(
+    alloc: &RocDocAllocator<'b>,
+    lines: &LineInfo,
+    region: Region,
+) -> Option<&'b str> {
+    let LineColumnRegion { start, end } = lines.convert_region(region);
+
+    if start.line != end.line {
+        return None;
+    }
+
+    let line = *alloc.src_lines.get(start.line as usize)?;
+
+    line.get(start.column as usize..end.column as usize)
+}
+
 fn not_found<'b>(
     alloc: &'b RocDocAllocator<'b>,
     lines: &LineInfo,
@@ -300,6 +300,15 @@ fn to_expr_report<'a>(
                     alloc.parser_suggestion("!"),
                     alloc.reflow(" and the expression after it."),
                 ],
+                "=" => vec![
+                    alloc.reflow("To check whether two values are equal, use "),
+                    alloc.parser_suggestion("=="),
+                    alloc.reflow(" instead. A single "),
+                    alloc.parser_suggestion("="),
+                    alloc.reflow(" can only define a name, like "),
+                    alloc.parser_suggestion("x = 5"),
+                    alloc.reflow("."),
+                ],
                 "<|" => vec![
                     alloc.reflow("Roc doesn't have a "),
                     alloc.parser_suggestion("<|"),
@@ -2140,6 +2149,30 @@ fn to_pattern_report<'a>(
         &EPattern::NumLiteral(ENumber::End, pos) => {
             to_malformed_number_literal_report(alloc, lines, filename, pos)
         }
+        EPattern::NumRange(pos) => {
+            let surroundings = Region::new(start, *pos);
+            let region = LineColumnRegion::from_pos(lines.convert_pos(*pos));
+
+            let doc = alloc.stack([
+                alloc.reflow(r"This looks like a range pattern, but those are not supported yet:"),
+                alloc.region_with_subregion(lines.convert_region(surroundings), region),
+                alloc.concat([
+                    alloc.reflow("Match each number on its own instead, or use an "),
+                    alloc.keyword("if"),
+                    alloc.reflow(" guard:"),
+                ]),
+                alloc
+                    .parser_suggestion("n if n >= 0 && n <= 9 ->")
+                    .indent(4),
+            ]);
+
+            Report {
+                filename,
+                doc,
+                title: "RANGE PATTERN".to_string(),
+                severity: Severity::RuntimeError,
+            }
+        }
         _ => todo!("unhandled parse error: {:?}", parse_problem),
     }
 }
@@ -15,7 +15,7 @@ use roc_module::symbol::Symbol;
 use roc_problem::Severity;
 use roc_region::all::{LineInfo, Region};
 use roc_solve_problem::{
-    NotDerivableContext, NotDerivableEq, TypeError, UnderivableReason, Unfulfilled,
+    NotDerivableContext, NotDerivableEq, TypeCycleStep, TypeError, UnderivableReason, Unfulfilled,
 };
 use roc_std::RocDec;
 use roc_types::pretty_print::{Parens, WILDCARD};
@@ -61,7 +61,7 @@ pub fn type_problem<'b>(
         BadPattern(region, category, found, expected) => Some(to_pattern_report(
             alloc, lines, filename, severity, region, category, found, expected,
         )),
-        CircularType(region, symbol, overall_type) => Some(to_circular_report(
+        CircularType(region, symbol, overall_type, steps) => Some(to_circular_report(
             alloc,
             lines,
             filename,
@@ -69,6 +69,7 @@ pub fn type_problem<'b>(
             region,
             symbol,
             overall_type,
+            steps,
         )),
         UnexposedLookup(_, symbol) => {
             let title = "UNRECOGNIZED NAME".to_string();
@@ -247,6 +248,73 @@ pub fn type_problem<'b>(
                 severity,
             })
         }
+        TypedHole(region, typ, candidates) => {
+            let mut stack = vec![
+                alloc.reflow("This hole needs to be filled in:"),
+                alloc.region(lines.convert_region(region)),
+                alloc.reflow("Based on how it is used, it should have the type:"),
+                alloc.type_block(error_type_to_doc(alloc, typ)),
+            ];
+
+            if !candidates.is_empty() {
+                stack.push(alloc.reflow("These values in scope have that type:"));
+                stack.push(
+                    alloc
+                        .vcat(
+                            candidates
+                                .into_iter()
+                                .map(|symbol| alloc.symbol_unqualified(symbol)),
+                        )
+                        .indent(4),
+                );
+            }
+
+            stack.push(alloc.concat([
+                alloc.tip(),
+                alloc.reflow("Any value of this type can go here. Until it is filled in, reaching the hole at runtime will crash."),
+            ]));
+
+            Some(Report {
+                title: "TYPED HOLE".to_string(),
+                filename,
+                doc: alloc.stack(stack),
+                severity,
+            })
+        }
+        NotGeneralized {
+            region,
+            symbol,
+            found,
+            expected,
+        } => {
+            let mut report = to_expr_report(
+                alloc,
+                lines,
+                filename,
+                severity,
+                region,
+                Category::Lookup(symbol),
+                found,
+                expected,
+            );
+
+            let note = alloc.stack([
+                alloc.concat([
+                    alloc.symbol_unqualified(symbol),
+                    alloc.reflow(" is not a function or a number literal, and it is not defined at the top level, so it only gets a single type. That type must be the same everywhere it is used."),
+                ]),
+                alloc.concat([
+                    alloc.tip(),
+                    alloc.reflow("To use it at different types, turn it into a function, like "),
+                    alloc.keyword("\\{} -> ..."),
+                    alloc.reflow(", and call it at each use."),
+                ]),
+            ]);
+
+            report.doc = alloc.stack([report.doc, note]);
+
+            Some(report)
+        }
     }
 }
 
@@ -452,6 +520,26 @@ pub fn cyclic_alias<'b>(
         .append(alloc.reflow(alias_kind.as_str()))
         .append(alloc.reflow("es is only allowed if recursion happens behind a tagged union, at least one variant of which is not recursive."));
 
+    let wrapped = wrapped_alias_definition(alloc, lines, region, alias_kind);
+
+    let how_to_fix = alloc.concat([
+        alloc.tip(),
+        alloc.reflow("Wrap the recursive use of "),
+        alloc.symbol_unqualified(symbol),
+        alloc.reflow(" in a tag union that also has a variant which does not refer to "),
+        alloc.symbol_unqualified(symbol),
+        alloc.reflow(", so that the recursion can end."),
+        match wrapped {
+            Some(_) => alloc.reflow(" For example:"),
+            None => alloc.nil(),
+        },
+    ]);
+
+    let how_to_fix = match wrapped {
+        Some(wrapped) => alloc.stack([how_to_fix, alloc.type_block(alloc.string(wrapped))]),
+        None => how_to_fix,
+    };
+
     let doc = if others.is_empty() {
         alloc.stack([
             alloc
@@ -462,6 +550,7 @@ pub fn cyclic_alias<'b>(
                 .append(alloc.reflow(" is self-recursive in an invalid way:")),
             alloc.region(lines.convert_region(region)),
             when_is_recursion_legal,
+            how_to_fix,
         ])
     } else {
         alloc.stack([
@@ -490,12 +579,86 @@ pub fn cyclic_alias<'b>(
                     .collect::<Vec<_>>(),
             ),
             when_is_recursion_legal,
+            how_to_fix,
         ])
     };
 
     (doc, "CYCLIC ALIAS".to_string())
 }
 
+/// The definition of the alias whose name is at `region`, with its body wrapped in a tag union
+/// that has a non-recursive `Nil` variant, like `Foo : [Nil, Cons { x : Foo }]`. Only aliases
+/// that are defined on a single line are rewritten.
+fn wrapped_alias_definition(
+    alloc: &RocDocAllocator<'_>,
+    lines: &LineInfo,
+    region: Region,
+    alias_kind: AliasKind,
+) -> Option<String> {
+    let start = lines.convert_pos(region.start());
+    let line = alloc.src_lines.get(start.line as usize)?;
+    let definition = line.get(start.column as usize..)?;
+
+    let separator = match alias_kind {
+        AliasKind::Structural => ":",
+        AliasKind::Opaque => ":=",
+    };
+    let (header, body) = definition.split_once(separator)?;
+
+    // drop a trailing comment and, for opaques, the abilities they implement
+    let body = body.split('#').next()?;
+    let body = body.split(" implements ").next()?.trim();
+
+    if body.is_empty() {
+        return None;
+    }
+
+    let bracketed = is_bracketed(body)?;
+
+    let wrapped = if bracketed && body.starts_with('[') {
+        match body[1..body.len() - 1].trim() {
+            "" => "[Nil]".to_string(),
+            tags => format!("[Nil, {tags}]"),
+        }
+    } else if bracketed || !body.contains(' ') {
+        format!("[Nil, Cons {body}]")
+    } else {
+        format!("[Nil, Cons ({body})]")
+    };
+
+    Some(format!("{} {separator} {wrapped}", header.trim_end()))
+}
+
+/// Whether `text` is a single bracketed type like `{ x : Foo }`, rather than something like
+/// `{} -> {}`. `None` if its brackets are unbalanced, e.g. because the type continues on the
+/// next line.
+fn is_bracketed(text: &str) -> Option<bool> {
+    let mut depth = 0usize;
+    let mut first_closes_at = None;
+
+    for (index, char) in text.char_indices() {
+        match char {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                depth = depth.checked_sub(1)?;
+
+                if depth == 0 && first_closes_at.is_none() {
+                    first_closes_at = Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if depth != 0 {
+        return None;
+    }
+
+    let starts_with_bracket = text.starts_with(['(', '[', '{']);
+
+    Some(starts_with_bracket && first_closes_at == Some(text.len() - 1))
+}
+
 fn report_mismatch<'b>(
     alloc: &'b RocDocAllocator<'b>,
     lines: &LineInfo,
@@ -2160,31 +2323,81 @@ fn to_circular_report<'b>(
     region: roc_region::all::Region,
     symbol: Symbol,
     overall_type: ErrorType,
+    steps: Vec<TypeCycleStep>,
 ) -> Report<'b> {
+    let mut stack = vec![
+        alloc
+            .reflow("I'm inferring a weird self-referential type for ")
+            .append(alloc.symbol_unqualified(symbol))
+            .append(alloc.text(":")),
+        alloc.region(lines.convert_region(region)),
+        alloc.stack([
+            alloc.reflow(
+                "Here is my best effort at writing down the type. \
+                You will see ∞ for parts of the type that repeat \
+                something already printed out infinitely.",
+            ),
+            alloc.type_block(to_doc(alloc, Parens::Unnecessary, overall_type).0),
+        ]),
+    ];
+
+    if !steps.is_empty() {
+        let steps = steps
+            .into_iter()
+            .map(|step| type_cycle_step_to_doc(alloc, step));
+
+        stack.push(alloc.stack([
+            alloc.reflow("The part of the type that repeats contains itself as:"),
+            alloc.vcat(steps).indent(4),
+        ]));
+    }
+
     Report {
         title: "CIRCULAR TYPE".to_string(),
         filename,
-        doc: {
-            alloc.stack([
-                alloc
-                    .reflow("I'm inferring a weird self-referential type for ")
-                    .append(alloc.symbol_unqualified(symbol))
-                    .append(alloc.text(":")),
-                alloc.region(lines.convert_region(region)),
-                alloc.stack([
-                    alloc.reflow(
-                        "Here is my best effort at writing down the type. \
-                        You will see ∞ for parts of the type that repeat \
-                        something already printed out infinitely.",
-                    ),
-                    alloc.type_block(to_doc(alloc, Parens::Unnecessary, overall_type).0),
-                ]),
-            ])
-        },
+        doc: alloc.stack(stack),
         severity,
     }
 }
 
+fn type_cycle_step_to_doc<'b>(
+    alloc: &'b RocDocAllocator<'b>,
+    step: TypeCycleStep,
+) -> RocDocBuilder<'b> {
+    match step {
+        TypeCycleStep::Apply(symbol) | TypeCycleStep::AliasArgument(symbol) => alloc.concat([
+            alloc.reflow("a type argument of "),
+            alloc.symbol_unqualified(symbol),
+        ]),
+        TypeCycleStep::FunctionArgument(index) => alloc.concat([
+            alloc.reflow("the "),
+            alloc.string(index.ordinal()),
+            alloc.reflow(" argument of a function"),
+        ]),
+        TypeCycleStep::FunctionReturn => alloc.reflow("the return type of a function"),
+        TypeCycleStep::FunctionClosure => alloc.reflow("the closure of a function"),
+        TypeCycleStep::RecordField(field) => alloc.concat([
+            alloc.reflow("the "),
+            alloc.record_field(field),
+            alloc.reflow(" field of a record"),
+        ]),
+        TypeCycleStep::TupleElem(index) => alloc.concat([
+            alloc.reflow("the "),
+            alloc.string(index.ordinal()),
+            alloc.reflow(" element of a tuple"),
+        ]),
+        TypeCycleStep::TagPayload(tag_name) => alloc.concat([
+            alloc.reflow("the payload of the "),
+            alloc.tag_name(tag_name),
+            alloc.reflow(" tag"),
+        ]),
+        TypeCycleStep::LambdaCapture(symbol) => alloc.concat([
+            alloc.reflow("a value captured by "),
+            alloc.symbol_unqualified(symbol),
+        ]),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Problem {
     IntFloat,
@@ -587,7 +587,10 @@ impl RocStr {
                             // it tries to use the refcount - which we just overwrote
                             // with string bytes.
                             mem::forget(self);
-                            crate::roc_dealloc(ptr.cast(), mem::align_of::<E>() as u32);
+                            crate::roc_dealloc(
+                                ptr.cast(),
+                                BigString::ALLOC_ALIGNMENT as u32,
+                            );
 
                             answer
                         } else {
@@ -802,6 +805,10 @@ struct BigString {
 const SEAMLESS_SLICE_BIT: usize = isize::MIN as usize;
 
 impl BigString {
+    /// The alignment the allocation backing a big string was made with. The elements are bytes,
+    /// so this is determined by the refcount that precedes them.
+    const ALLOC_ALIGNMENT: usize = mem::align_of::<usize>();
+
     fn len(&self) -> usize {
         self.length & !SEAMLESS_SLICE_BIT
     }
@@ -895,7 +902,10 @@ impl BigString {
                 }
                 isize::MIN => {
                     // refcount becomes zero; free allocation
-                    crate::roc_dealloc(self.ptr_to_allocation().cast(), 1);
+                    crate::roc_dealloc(
+                        self.ptr_to_allocation().cast(),
+                        Self::ALLOC_ALIGNMENT as u32,
+                    );
                 }
                 _ => {
                     std::ptr::write(ptr, (value - 1) as usize);
@@ -920,7 +930,7 @@ impl BigString {
     ///
     /// May return a new BigString, if the provided one was not unique.
     fn reserve(&mut self, n: usize) {
-        let align = std::mem::size_of::<usize>();
+        let align = Self::ALLOC_ALIGNMENT;
         let desired_cap = self.len() + n;
         let desired_alloc = align + desired_cap;
 
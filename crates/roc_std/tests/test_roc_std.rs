@@ -6,10 +6,18 @@ extern crate pretty_assertions;
 extern crate quickcheck;
 extern crate roc_std;
 
+use core::cell::Cell;
 use core::ffi::c_void;
 
 const ROC_SMALL_STR_CAPACITY: usize = core::mem::size_of::<roc_std::RocStr>() - 1;
 
+thread_local! {
+    /// The alignment passed to the most recent roc_realloc or roc_dealloc on this thread,
+    /// so tests can check that frees and reallocations match how the memory was allocated.
+    static LAST_REALLOC_ALIGNMENT: Cell<Option<u32>> = Cell::new(None);
+    static LAST_DEALLOC_ALIGNMENT: Cell<Option<u32>> = Cell::new(None);
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn roc_alloc(size: usize, _alignment: u32) -> *mut c_void {
     libc::malloc(size)
@@ -20,13 +28,17 @@ pub unsafe extern "C" fn roc_realloc(
     c_ptr: *mut c_void,
     new_size: usize,
     _old_size: usize,
-    _alignment: u32,
+    alignment: u32,
 ) -> *mut c_void {
+    LAST_REALLOC_ALIGNMENT.with(|last| last.set(Some(alignment)));
+
     libc::realloc(c_ptr, new_size)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn roc_dealloc(c_ptr: *mut c_void, _alignment: u32) {
+pub unsafe extern "C" fn roc_dealloc(c_ptr: *mut c_void, alignment: u32) {
+    LAST_DEALLOC_ALIGNMENT.with(|last| last.set(Some(alignment)));
+
     libc::free(c_ptr)
 }
 
@@ -54,6 +66,7 @@ pub unsafe extern "C" fn roc_memset(dst: *mut c_void, c: i32, n: usize) -> *mut
 
 #[cfg(test)]
 mod test_roc_std {
+    use core::cell::Cell;
     use roc_std::{RocBox, RocDec, RocList, RocResult, RocStr, SendSafeRocStr};
 
     fn roc_str_byte_representation(string: &RocStr) -> [u8; RocStr::SIZE] {
@@ -133,6 +146,50 @@ mod test_roc_std {
         assert_eq!(roc_str.capacity() >= 5000, true);
     }
 
+    const BIG_STR_ALIGNMENT: Option<u32> = Some(core::mem::align_of::<usize>() as u32);
+
+    #[test]
+    fn big_str_dealloc_alignment() {
+        let roc_str = RocStr::from(str::repeat("a", 100).as_str());
+
+        super::LAST_DEALLOC_ALIGNMENT.with(|last| last.set(None));
+        drop(roc_str);
+
+        assert_eq!(
+            super::LAST_DEALLOC_ALIGNMENT.with(Cell::get),
+            BIG_STR_ALIGNMENT
+        );
+    }
+
+    #[test]
+    fn big_str_realloc_alignment() {
+        let mut roc_str = RocStr::from(str::repeat("a", 100).as_str());
+
+        super::LAST_REALLOC_ALIGNMENT.with(|last| last.set(None));
+        roc_str.reserve(5000);
+
+        assert_eq!(
+            super::LAST_REALLOC_ALIGNMENT.with(Cell::get),
+            BIG_STR_ALIGNMENT
+        );
+    }
+
+    #[test]
+    fn big_str_terminator_in_place_dealloc_alignment() {
+        // A unique big string has room for the terminator in the bytes of its refcount, so the
+        // terminator gets written into the existing allocation, which is freed afterwards.
+        let roc_str = RocStr::from(str::repeat("a", 100).as_str());
+
+        super::LAST_DEALLOC_ALIGNMENT.with(|last| last.set(None));
+        let len = roc_str.with_terminator(0u8, |_, str_slice: &str| str_slice.len());
+
+        assert_eq!(len, 100);
+        assert_eq!(
+            super::LAST_DEALLOC_ALIGNMENT.with(Cell::get),
+            BIG_STR_ALIGNMENT
+        );
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn str_short_serde_roundtrip() {